@@ -84,14 +84,16 @@ pub fn all_tuples(input: TokenStream) -> TokenStream {
     })
 }
 
-static BUNDLE_ATTRIBUTE_NAME: &str = "bundle";
-
 
 /// 实现组件，重载组件存储
+/// 可以是内置的存储：dense（默认）、sparse、hash、tag（零大小的标记组件）、soa、packed，也可以是自定义存储容器的路径
+/// soa将各字段分列存放，生成名为`{组件名}Columns`的列结构，并在模块`{组件名蛇形}_fields`中生成字段标记类型，如`Field<Particle, particle_fields::velocity>`
+/// `#[component(no_ticks)]`使组件不记录ticks，不能再用于Changed、Added、Modifyed、ChangeTrackers查询
 /// example:
 /// 	#[derive(Component)]
-/// 	#[storage=XXX]
-#[proc_macro_derive(Component, attributes(storage))]
+/// 	#[storage(sparse)]
+/// 	#[component(no_ticks)]
+#[proc_macro_derive(Component, attributes(storage, component))]
 pub fn component_derive(input: TokenStream) -> TokenStream {
     let ast = syn::parse(input).unwrap();
     let gen = impl_component(&ast);
@@ -101,6 +103,7 @@ pub fn component_derive(input: TokenStream) -> TokenStream {
 /// 监听，为函数增加监听器属性
 /// example: `#[listen(component = (Node, Position, Modify), entity = (Node, Delete))]`
 /// example: `#[listen(resource = (Viewport, Modify))]`
+/// example: `#[listen(world = Restore)]`
 #[proc_macro_attribute]
 pub fn listen(attr: TokenStream, item: TokenStream) -> TokenStream {
     let gen = impl_listen_component(attr, item);
@@ -269,25 +272,127 @@ pub fn derive_system_param(input: TokenStream) -> TokenStream {
 fn impl_component(ast: &DeriveInput) -> proc_macro2::TokenStream {
     let name = &ast.ident;
     let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
-    let storage = ast
-        .attrs
-        .iter()
-        .find(|attr| attr.path.segments[0].ident == "storage")
-        .map(|attr| {
-            syn::parse2::<StorageAttribute>(attr.tokens.clone())
-                .unwrap()
-                .storage
-        });
+    let storage = storage_attribute(ast);
+	let no_ticks = ast
+		.attrs
+		.iter()
+		.filter(|attr| attr.path.segments[0].ident == "component")
+		.any(|attr| {
+			syn::parse2::<ComponentAttribute>(attr.tokens.clone())
+				.unwrap()
+				.no_ticks
+		});
+
+	let path = pi_ecs_path();
+	let no_ticks = if no_ticks {
+		quote! {
+			impl #impl_generics #path::component::NoTicks for #name #ty_generics #where_clause {}
+		}
+	} else {
+		quote! {}
+	};
+
 	// 如果没有指定存储容器，没有必要重载实现
 	let storage = match storage {
 		Some(r) => r,
-		None => return quote! {}
+		None => return no_ticks,
+	};
+
+	let mut soa = quote! {};
+	let storage = match storage.get_ident().map(|r| r.to_string()).as_deref() {
+		Some("soa") => {
+			soa = impl_soa(ast, &path);
+			quote! { #path::storage::SoAMap<#path::storage::LocalVersion, Self> }
+		},
+		Some("dense") => quote! { #path::storage::SecondaryMap<#path::storage::LocalVersion, Self> },
+		Some("sparse") => quote! { #path::storage::SparseSecondaryMap<#path::storage::LocalVersion, Self> },
+		Some("hash") => quote! { #path::storage::HashSecondaryMap<#path::storage::LocalVersion, Self> },
+		Some("tag") => quote! { #path::storage::TagMap<#path::storage::LocalVersion, Self> },
+		Some("packed") => quote! { #path::storage::PackedMap<#path::storage::LocalVersion, Self> },
+		_ => quote! { #storage },
 	};
 
     quote! {
-        impl #impl_generics pi_ecs::component::Component for #name #ty_generics #where_clause {
-            type Storage = #storage;
+        impl #impl_generics #path::component::ComponentStorage for #name #ty_generics #where_clause {
+            type Type = #storage;
+        }
+
+        #soa
+
+        #no_ticks
+    }
+}
+
+/// 为SoA存储的组件生成列结构，并实现SoAComponent和各字段的SoAField
+fn impl_soa(ast: &DeriveInput, path: &Path) -> proc_macro2::TokenStream {
+    let name = &ast.ident;
+    if !ast.generics.params.is_empty() {
+        panic!("#[storage(soa)] does not support generic components");
+    }
+    let fields = match &ast.data {
+        Data::Struct(DataStruct { fields: Fields::Named(fields), .. }) => &fields.named,
+        _ => panic!("#[storage(soa)] expects a struct with named fields"),
+    };
+    let idents: Vec<&Ident> = fields.iter().map(|f| f.ident.as_ref().unwrap()).collect();
+    let names: Vec<String> = idents.iter().map(|r| r.to_string()).collect();
+    let types: Vec<&Type> = fields.iter().map(|f| &f.ty).collect();
+
+    let vis = &ast.vis;
+    let columns = format_ident!("{}Columns", name);
+    let markers = field_markers(path, vis, name, &names);
+    let module = format_ident!("{}_fields", snake_case(&name.to_string()));
+
+    quote! {
+        #markers
+
+        /// 组件按字段拆分的列，按实体的`offset`索引
+        #[derive(Default)]
+        #vis struct #columns {
+            #(pub #idents: Vec<#types>,)*
+        }
+
+        impl #path::storage::SoAComponent for #name {
+            type Columns = #columns;
+
+            fn write(self, columns: &mut Self::Columns, offset: usize) {
+                #(columns.#idents[offset] = self.#idents;)*
+            }
+
+            fn take(columns: &mut Self::Columns, offset: usize) -> Self {
+                Self {
+                    #(#idents: std::mem::take(&mut columns.#idents[offset]),)*
+                }
+            }
+
+            fn resize(columns: &mut Self::Columns, len: usize) {
+                #(columns.#idents.resize_with(len, Default::default);)*
+            }
+
+            fn mem_size(columns: &Self::Columns) -> usize {
+                0 #(+ columns.#idents.capacity() * std::mem::size_of::<#types>())*
+            }
+
+            fn shrink(columns: &mut Self::Columns, len: usize) {
+                #(
+                    columns.#idents.truncate(len);
+                    columns.#idents.shrink_to_fit();
+                )*
+            }
         }
+
+        #(
+            impl #path::storage::SoAField<#module::#idents> for #name {
+                type Type = #types;
+
+                fn column(columns: &Self::Columns) -> &[Self::Type] {
+                    &columns.#idents
+                }
+
+                fn column_mut(columns: &mut Self::Columns) -> &mut [Self::Type] {
+                    &mut columns.#idents
+                }
+            }
+        )*
     }
 }
 
@@ -295,6 +400,18 @@ struct StorageAttribute {
     storage: Path,
 }
 
+/// `#[storage(..)]`指定的存储容器
+fn storage_attribute(ast: &DeriveInput) -> Option<Path> {
+    ast.attrs
+        .iter()
+        .find(|attr| attr.path.segments[0].ident == "storage")
+        .map(|attr| {
+            syn::parse2::<StorageAttribute>(attr.tokens.clone())
+                .unwrap()
+                .storage
+        })
+}
+
 impl Parse for StorageAttribute {
     fn parse(input: ParseStream) -> Result<Self> {
         let content;
@@ -306,6 +423,27 @@ impl Parse for StorageAttribute {
     }
 }
 
+struct ComponentAttribute {
+    no_ticks: bool,
+}
+
+impl Parse for ComponentAttribute {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let content;
+        let _parenthesized_token = parenthesized!(content in input);
+
+        let mut no_ticks = false;
+        for option in content.parse_terminated::<Ident, Token![,]>(Ident::parse)? {
+            if option == "no_ticks" {
+                no_ticks = true;
+            } else {
+                return Err(syn::Error::new(option.span(), "unknown component option, expected `no_ticks`"));
+            }
+        }
+        Ok(ComponentAttribute { no_ticks })
+    }
+}
+
 fn impl_listen_component(attr: TokenStream, item: TokenStream) -> proc_macro2::TokenStream {
 
 	let s = attr.to_string();
@@ -341,43 +479,49 @@ fn impl_listen_component(attr: TokenStream, item: TokenStream) -> proc_macro2::T
 				r += "pi_ecs::monitor::ResourceListen";
 			} else if key=="entity" {
 				r += "pi_ecs::monitor::EntityListen";
+			} else if key=="world" {
+				r += "pi_ecs::monitor::WorldListen";
 			} else {
-				panic!("!Component | Resource | EntityListen, is{:?}", key);
+				panic!("!Component | Resource | EntityListen | WorldListen, is{:?}", key);
 			}
 			
 
 			let r = TokenStream::from_str(r.as_str()).unwrap();
 			let p = syn::parse::<Path>(r).unwrap();
 
+			// 世界监听只有事件类型，如：world = Restore
+			if key=="world" {
+				if let Type::Path(p) = &mut binding.ty {
+					let pp = &p.path;
+					let mut path = quote!{#pp}.to_string();
+					if path == "Restore" {
+						path = String::from("pi_ecs::monitor::") + path.as_str();
+					}
+					p.path = syn::parse::<Path>(TokenStream::from_str(path.as_str()).unwrap()).unwrap();
+				}
+				let mut elems = syn::punctuated::Punctuated::new();
+				elems.push(binding.ty.clone());
+				list.push(ListenItem(p, elems));
+				continue;
+			}
+
 			if let Type::Tuple(r) = &mut binding.ty {
 				if let Some(last) = r.elems.last_mut() {
 					match last {
 						Type::Tuple(t) => {
 							for e in &mut t.elems {
 								if let Type::Path(p) = e {
-									let pp = &p.path;
-									let mut path = quote!{#pp}.to_string();
-									if path == "Create" || path == "Modify" || path == "Delete"{
-										path = String::from("pi_ecs::monitor::") + path.as_str();
-									}
-									p.path = syn::parse::<Path>(TokenStream::from_str(path.as_str()).unwrap()).unwrap();
+									qualify_event(p);
 								} else {
 									panic!("is not path:{:?}", quote!{#e}.to_string());
 								}
 							}
 						},
-						Type::Path(p) => {
-							let pp = &p.path;
-							let mut path = quote!{#pp}.to_string();
-							if path == "Create" || path == "Modify" || path == "Delete"{
-								path = String::from("pi_ecs::monitor::") + path.as_str();
-							}
-							p.path = syn::parse::<Path>(TokenStream::from_str(path.as_str()).unwrap()).unwrap();
-						},
+						Type::Path(p) => qualify_event(p),
 						_ => panic!("event must is Tuple or Path")
 					}
 				}
-				list.push(ListenItem(p, &r.elems));
+				list.push(ListenItem(p, r.elems.clone()));
 			} else {
 				let ty = &binding.ty;
 				panic!("!TypeTuple, {:?}", quote!{#ty}.to_string());
@@ -512,8 +656,8 @@ impl<'a> ToTokens for GenericsCall<'a> {
 	}
 }
 
-struct ListenItem<'a>(Path, &'a syn::punctuated::Punctuated<Type, Comma>);
-impl<'a> ToTokens for ListenItem<'a> {
+struct ListenItem(Path, syn::punctuated::Punctuated<Type, Comma>);
+impl ToTokens for ListenItem {
     fn to_tokens(&self, tokens: &mut TokenStream2) {
 		let (name, fileds) = (&self.0, &self.1);
 		tokens.extend(quote! {#name<#fileds>});
@@ -529,95 +673,248 @@ impl<'a> ToTokens for ListenItem<'a> {
 
 // }
 
-#[proc_macro_derive(Bundle, attributes(bundle))]
+/// 实现组件集合，结构体的每个字段都是一个组件
+/// example:
+/// 	#[derive(Bundle)]
+/// 	pub struct NodeBundle {
+/// 		node: Node,
+/// 		transform: Transform,
+/// 	}
+#[proc_macro_derive(Bundle)]
 pub fn derive_bundle(input: TokenStream) -> TokenStream {
     let ast = parse_macro_input!(input as DeriveInput);
     let ecs_path = pi_ecs_path();
 
-    let named_fields = match &ast.data {
-        Data::Struct(DataStruct {
-            fields: Fields::Named(fields),
-            ..
-        }) => &fields.named,
-        _ => panic!("Expected a struct with named fields."),
+    let fields = match &ast.data {
+        Data::Struct(DataStruct { fields, .. }) => fields,
+        _ => panic!("Expected a struct."),
     };
-
-    let is_bundle = named_fields
+    let field = fields
         .iter()
-        .map(|field| {
-            field
-                .attrs
-                .iter()
-                .any(|a| *a.path.get_ident().as_ref().unwrap() == BUNDLE_ATTRIBUTE_NAME)
+        .enumerate()
+        .map(|(i, field)| match &field.ident {
+            Some(ident) => quote! { #ident },
+            None => {
+                let index = Index::from(i);
+                quote! { #index }
+            }
         })
-        .collect::<Vec<bool>>();
-    let field = named_fields
-        .iter()
-        .map(|field| field.ident.as_ref().unwrap())
         .collect::<Vec<_>>();
-    let field_type = named_fields
+    let field_type = fields
         .iter()
         .map(|field| &field.ty)
         .collect::<Vec<_>>();
 
-    let mut field_type_infos = Vec::new();
-    let mut field_get_components = Vec::new();
-    let mut field_from_components = Vec::new();
-    for ((field_type, is_bundle), field) in
-        field_type.iter().zip(is_bundle.iter()).zip(field.iter())
-    {
-        if *is_bundle {
-            field_type_infos.push(quote! {
-                type_info.extend(<#field_type as #ecs_path::bundle::Bundle>::type_info());
-            });
-            field_get_components.push(quote! {
-                self.#field.get_components(&mut func);
-            });
-            field_from_components.push(quote! {
-                #field: <#field_type as #ecs_path::bundle::Bundle>::from_components(&mut func),
-            });
-        } else {
-            field_type_infos.push(quote! {
-                type_info.push(#ecs_path::component::TypeInfo::of::<#field_type>());
-            });
-            field_get_components.push(quote! {
-                func((&mut self.#field as *mut #field_type).cast::<u8>());
-                std::mem::forget(self.#field);
-            });
-            field_from_components.push(quote! {
-                #field: func().cast::<#field_type>().read(),
-            });
-        }
-    }
-    let field_len = field.len();
     let generics = ast.generics;
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
     let struct_name = &ast.ident;
+    // 组件集合的实现委托给字段类型组成的元组
+    let tuple = quote! { <(#(#field_type,)*) as #ecs_path::bundle::Bundle> };
+    let value = quote! { (#(self.#field,)*) };
 
     TokenStream::from(quote! {
-        /// SAFE: TypeInfo is returned in field-definition-order. [from_components] and [get_components] use field-definition-order
-        unsafe impl #impl_generics #ecs_path::bundle::Bundle for #struct_name #ty_generics #where_clause {
-            fn type_info() -> Vec<#ecs_path::component::TypeInfo> {
-                let mut type_info = Vec::with_capacity(#field_len);
-                #(#field_type_infos)*
-                type_info
+        impl #impl_generics #ecs_path::bundle::Bundle for #struct_name #ty_generics #where_clause {
+            type Containers = #tuple::Containers;
+
+            fn register(info: #ecs_path::world::ArchetypeInfo) -> #ecs_path::world::ArchetypeInfo {
+                #tuple::register(info)
             }
 
-            #[allow(unused_variables, unused_mut, non_snake_case)]
-            unsafe fn from_components(mut func: impl FnMut() -> *mut u8) -> Self {
-                Self {
-                    #(#field_from_components)*
-                }
+            fn insert_entity<BundleArchetype: #ecs_path::archetype::ArchetypeIdent>(self, entity: &mut #ecs_path::world::EntityRef<BundleArchetype>) {
+                #tuple::insert_entity(#value, entity)
             }
 
-            #[allow(unused_variables, unused_mut, forget_copy, forget_ref)]
-            fn get_components(mut self, mut func: impl FnMut(*mut u8)) {
-                #(#field_get_components)*
+            fn insert_world(self, world: &mut #ecs_path::world::WorldInner, entity: #ecs_path::entity::Entity) -> #ecs_path::entity::Entity {
+                #tuple::insert_world(#value, world, entity)
+            }
+
+            fn containers<BundleArchetype: #ecs_path::archetype::ArchetypeIdent>(
+                archetypes: &mut #ecs_path::archetype::Archetypes,
+                components: &mut #ecs_path::component::Components,
+                archetype_id: #ecs_path::archetype::ArchetypeId,
+                additional: usize,
+            ) -> Self::Containers {
+                #tuple::containers::<BundleArchetype>(archetypes, components, archetype_id, additional)
+            }
+
+            fn insert(self, containers: &Self::Containers, local: #ecs_path::storage::LocalVersion, tick: u32) {
+                #tuple::insert(#value, containers, local, tick)
+            }
+
+            fn create_event(containers: &Self::Containers, entity: #ecs_path::entity::Entity) {
+                #tuple::create_event(containers, entity)
             }
         }
     })
 }
 
+/// 为组件生成字段级的setter，修改字段并发出携带字段名的修改事件
+/// 生成的trait名为`{组件名}Fields`，为`WriteItem<组件>`实现
+/// 每个字段生成`set_{字段名}`，数组和Vec字段额外生成`set_{字段名}_at`，事件携带元素索引
+/// 组件不存在时，setter不做任何操作
+/// SoA存储的组件不能整体借用，不能生成setter，应通过`FieldMut`修改字段
+/// example:
+/// 	#[derive(ComponentFields)]
+/// 	pub struct Color {
+/// 		r: f32,
+/// 		g: f32,
+/// 	}
+/// 	// 只在字段r被修改时触发，字段标记类型生成在模块`color_fields`中
+/// 	#[listen(component = (Node, Color, ModifyField<color_fields::r>))]
+#[proc_macro_derive(ComponentFields)]
+pub fn derive_component_fields(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+    let ecs_path = pi_ecs_path();
+
+    // setter通过整体借用组件修改字段，SoA组件不支持
+    if let Some(storage) = storage_attribute(&ast).filter(|r| r.is_ident("soa")) {
+        return syn::Error::new_spanned(
+            storage,
+            "ComponentFields cannot be derived for a component with #[storage(soa)], write its fields with FieldMut",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let fields = match &ast.data {
+        Data::Struct(DataStruct { fields, .. }) => fields,
+        _ => panic!("Expected a struct."),
+    };
+
+    let mut signatures = Vec::new();
+    let mut setters = Vec::new();
+    let mut markers = Vec::new();
+    for (i, field) in fields.iter().enumerate() {
+        let (member, name) = match &field.ident {
+            Some(ident) => (quote! { #ident }, ident.to_string()),
+            None => {
+                let index = Index::from(i);
+                (quote! { #index }, i.to_string())
+            }
+        };
+        markers.push(name.clone());
+        let ty = &field.ty;
+        let set = format_ident!("set_{}", name);
+        signatures.push(quote! { fn #set(&mut self, value: #ty); });
+        setters.push(quote! {
+            fn #set(&mut self, value: #ty) {
+                if let Some(c) = self.get_mut() {
+                    c.#member = value;
+                    self.notify_modify_field(#name, 0);
+                }
+            }
+        });
+
+        if let Some(elem) = element_type(ty) {
+            let set_at = format_ident!("set_{}_at", name);
+            signatures.push(quote! { fn #set_at(&mut self, index: usize, value: #elem); });
+            setters.push(quote! {
+                fn #set_at(&mut self, index: usize, value: #elem) {
+                    if let Some(c) = self.get_mut() {
+                        c.#member[index] = value;
+                        self.notify_modify_field(#name, index);
+                    }
+                }
+            });
+        }
+    }
+
+    let vis = &ast.vis;
+    let struct_name = &ast.ident;
+    let trait_name = format_ident!("{}Fields", struct_name);
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    let markers = field_markers(&ecs_path, vis, struct_name, &markers);
+
+    TokenStream::from(quote! {
+        #markers
+
+        #vis trait #trait_name {
+            #(#signatures)*
+        }
+
+        impl #impl_generics #trait_name for #ecs_path::query::WriteItem<'_, #struct_name #ty_generics> #where_clause {
+            #(#setters)*
+        }
+    })
+}
+
+/// 生成字段标记类型的模块`{组件名蛇形}_fields`，每个字段一个标记类型，元组字段名为`_{索引}`
+/// 用于`ModifyField`、`Field`、`FieldMut`，由`#[derive(ComponentFields)]`和SoA组件的`#[derive(Component)]`生成
+fn field_markers(ecs_path: &Path, vis: &syn::Visibility, struct_name: &Ident, names: &[String]) -> TokenStream2 {
+    let module = format_ident!("{}_fields", snake_case(&struct_name.to_string()));
+    let idents = names.iter().map(|name| match name.parse::<usize>() {
+        Ok(_) => format_ident!("_{}", name),
+        Err(_) => format_ident!("{}", name),
+    });
+    let doc = format!("`{}`各字段的标记类型", struct_name);
+    quote! {
+        #[doc = #doc]
+        #[allow(non_camel_case_types)]
+        #vis mod #module {
+            #(
+                pub struct #idents;
+                impl #ecs_path::component::FieldName for #idents {
+                    const NAME: &'static str = #names;
+                }
+            )*
+        }
+    }
+}
+
+/// 大驼峰转蛇形，如`ParticleColor`转为`particle_color`
+fn snake_case(name: &str) -> String {
+    let mut r = String::with_capacity(name.len() + 4);
+    let mut prev_lower = false;
+    for c in name.chars() {
+        if c.is_uppercase() {
+            if prev_lower {
+                r.push('_');
+            }
+            r.extend(c.to_lowercase());
+            prev_lower = false;
+        } else {
+            r.push(c);
+            prev_lower = c.is_lowercase() || c.is_ascii_digit();
+        }
+    }
+    r
+}
+
+/// 未限定路径的事件类型（Create、Modify、Delete、Migrate、ModifyField<..>）补全为`pi_ecs::monitor::`下的路径
+fn qualify_event(p: &mut TypePath) {
+    if p.qself.is_some() || p.path.leading_colon.is_some() || p.path.segments.len() != 1 {
+        return;
+    }
+    let segment = &p.path.segments[0];
+    let ident = segment.ident.to_string();
+    let is_event = match &segment.arguments {
+        PathArguments::None => ident == "Create" || ident == "Modify" || ident == "Delete" || ident == "Migrate",
+        PathArguments::AngleBracketed(_) => ident == "ModifyField",
+        PathArguments::Parenthesized(_) => false,
+    };
+    if is_event {
+        p.path = syn::parse_quote!(pi_ecs::monitor::#segment);
+    }
+}
+
+/// 数组或Vec的元素类型
+fn element_type(ty: &Type) -> Option<&Type> {
+    match ty {
+        Type::Array(r) => Some(&r.elem),
+        Type::Path(r) => {
+            let last = r.path.segments.last()?;
+            match &last.arguments {
+                PathArguments::AngleBracketed(args) if last.ident == "Vec" => match args.args.first() {
+                    Some(GenericArgument::Type(elem)) => Some(elem),
+                    _ => None,
+                },
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
 fn get_idents(fmt_string: fn(usize) -> String, count: usize) -> Vec<Ident> {
     (0..count)
         .map(|i| Ident::new(&fmt_string(i), Span::call_site()))
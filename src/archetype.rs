@@ -2,10 +2,10 @@
 use std::{any::type_name, marker::PhantomData};
 
 use crate::{
-    component::{ComponentId, CellMultiCase, MultiCase, Component, MultiCaseImpl},
+    component::{ComponentId, CellMultiCase, MultiCase, Component, MultiCaseImpl, insert_cell},
     entity::{Entity, Entities},
-    storage::{Offset, LocalVersion, Local},
-	monitor::{Listener, ListenType}, 
+    storage::{Offset, LocalVersion, Local, secondary_mem_size},
+	monitor::{Listener, ListenType, OldListener, OldListenType, ListenerDepth}, 
 	resource::{Singles, Resource, ResourceId},
 	prelude::FilteredAccessSet,
 };
@@ -14,6 +14,7 @@ use std::{
     hash::Hash,
     ops::{Index, IndexMut},
 	any::TypeId,
+	mem::size_of,
 	sync::Arc,
 };
 
@@ -29,7 +30,8 @@ pub struct Archetype {
 	archetype_component_id: ArchetypeComponentId, // 实体访问id
 	archetype_component_delete_id: ArchetypeComponentId, // 实体删除访问id
 	// 该原型下的实体
-    pub(crate) entities: Entities,
+	// 放在堆上，新建原型时原型列表重新分配内存，实体容器的地址不变（查询、命令等持有其指针）
+    pub(crate) entities: Box<Entities>,
 
 	// 组件（每个ComponentId对应一个MultiCase）
 	// MultiCase是某个类型的组件的容器
@@ -44,6 +46,12 @@ pub struct Archetype {
 	archetype_component_ids: SecondaryMap<ComponentId, ArchetypeComponentId>,
 
 	pub delete_list: Vec<LocalVersion>,
+
+	// 是否为动态原型（由组件集合标识，组件集合创建后不再改变）
+	dynamic: bool,
+
+	// 原型类型的名称，动态原型为DynamicArchetype
+	name: &'static str,
 }
 
 impl Archetype {
@@ -53,16 +61,30 @@ impl Archetype {
 			id,
 			archetype_component_id,
 			archetype_component_delete_id,
-			entities: Entities::new(id),
+			entities: Box::new(Entities::new(id)),
 
 			components: SecondaryMap::with_capacity(0),
 
 			archetype_component_ids: SecondaryMap::with_capacity(0),
 			component_ids: Vec::default(),
 			delete_list: Vec::new(),
+			dynamic: false,
+			name: "",
 		}
 	}
 
+	/// 原型类型的名称
+	#[inline]
+	pub fn name(&self) -> &'static str {
+		self.name
+	}
+
+	/// 是否为动态原型
+	#[inline]
+	pub fn is_dynamic(&self) -> bool {
+		self.dynamic
+	}
+
 	pub fn reserve_entity(&mut self) -> LocalVersion {
 		self.entities.reserve_entity()
 	}
@@ -89,17 +111,24 @@ impl Archetype {
 		self.archetype_component_ids.insert(id, archetype_component_id);
 	}
 
+	/// 为原型注册组件容器（容器类型由调用者保证与id对应）
+	pub(crate) fn register_component_container(&mut self, id: ComponentId, container: Arc<dyn MultiCase>, archetype_component_id: ArchetypeComponentId) {
+		self.components.insert(id, container);
+		self.component_ids.push(id);
+		self.archetype_component_ids.insert(id, archetype_component_id);
+	}
+
 	/// 创建实体
 	pub fn create_entity(&mut self) -> Entity {
 		Entity::new(self.id, self.entities.insert())
 	}
 
 	/// 移除实体
-	/// 移除实体时，会连带将其拥有的组件页删除，会发出实体删除的事件，但不会发出组件销毁的事件，
+	/// 移除实体时，会连带将其拥有的组件删除，先发出实体删除的事件，再逐个发出组件删除的事件
 	pub fn remove_entity(&mut self, local: LocalVersion) {
 		if self.entities.remove(local).is_some() {
 			for i in self.component_ids.iter() {
-				self.components[*i].delete(local);
+				self.components[*i].remove(local);
 			}
 		};
 	}
@@ -121,6 +150,31 @@ impl Archetype {
 		}
 	}
 
+	/// 释放组件容器中未使用的容量
+	/// 实体槽位不会收缩（已释放的槽位需要保留版本，避免旧的实体id失效后又被认为有效），需要收缩时使用renumber
+	pub fn shrink(&mut self) {
+		for id in self.component_ids.iter() {
+			MultiCase::shrink(self.components[*id].as_ref());
+		}
+		self.delete_list.shrink_to_fit();
+	}
+
+	/// 将实体重新编号为连续的槽位，组件随实体移动，不发出事件，随后释放所有容器中未使用的容量
+	/// 重新编号前会先flush（插入预留的实体、删除待删除的实体）
+	/// 返回编号发生变化的实体（旧实体, 新实体），保存在组件中的旧实体id需要按此修正
+	/// 新实体的版本高于槽位上曾经分配过的版本，旧的实体id不会与新实体相同
+	pub fn renumber(&mut self) -> Vec<(LocalVersion, LocalVersion)> {
+		self.flush();
+		let remap = self.entities.renumber();
+		if !remap.is_empty() {
+			for id in self.component_ids.iter() {
+				self.components[*id].remap(&remap);
+			}
+		}
+		self.shrink();
+		remap
+	}
+
 	/// 为指定实体添加组件
 	pub fn insert_component<C: Component>(&mut self, local: LocalVersion, value: C, id: ComponentId, tick: u32) -> Option<C> {
 		if self.components.get(id).is_none() {
@@ -138,8 +192,7 @@ impl Archetype {
 		match container.clone().downcast() {
 			Ok(r) => {
 				let r: Arc<CellMultiCase<C>> = r;
-				let mut r_ref = r.borrow_mut();
-				r_ref.insert(local, value, tick)
+				insert_cell(&r, local, value, tick)
 			},
 			Err(_) => panic!("downcast err"),
 		}
@@ -156,10 +209,10 @@ impl Archetype {
 		}
 	}
 
-	/// 移除组件
+	/// 移除组件，并发出组件删除的事件
 	pub unsafe fn remove_component_unsafe(&mut self, local: LocalVersion, id: ComponentId) {
 		let container = self.components.get_unchecked(id);
-		container.delete(local)
+		container.remove(local)
 	}
 
 	/// 添加组件监听器
@@ -179,6 +232,22 @@ impl Archetype {
 		T::add(&self.entities.entity_listners, listener);
 	}
 
+	/// 移除组件监听器
+	pub fn remove_component_listener<T: ListenType, C: Component>(&mut self, listener: &Listener, id: ComponentId) {
+		if let Some(container) = self.components.get(id) {
+			match container.clone().downcast_ref::<CellMultiCase<C>>() {
+				Some(r) => T::remove(r, listener),
+				None => panic!("downcast err"),
+			}
+		}
+	}
+
+	/// 移除实体监听器
+	#[inline]
+	pub fn remove_entity_listener<T: ListenType>(&mut self, listener: &Listener) {
+		T::remove(&self.entities.entity_listners, listener);
+	}
+
 	/// 取到原型id
     #[inline]
     pub fn id(&self) -> ArchetypeId {
@@ -218,6 +287,13 @@ impl Archetype {
 	pub unsafe fn archetype_component_id(&self, component_id: ComponentId) -> ArchetypeComponentId {
 		self.archetype_component_ids[component_id]
 	}
+
+	/// 组件索引（组件容器表、组件id列表等）占用的字节数，不包括组件容器本身
+	pub fn index_mem_size(&self) -> usize {
+		secondary_mem_size(&self.components)
+			+ secondary_mem_size(&self.archetype_component_ids)
+			+ self.component_ids.capacity() * size_of::<ComponentId>()
+	}
 }
 
 /// 原型ID
@@ -228,7 +304,8 @@ pub trait ArchetypeIdent : ThreadSync + 'static {}
 
 impl<C: ThreadSync + 'static> ArchetypeIdent for C  {}
 
-/// 原型id生成器
+/// 原型世代
+/// 原型或原型上的组件发生增加时，世代随之增长，用于判断缓存的原型匹配结果是否需要更新
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub struct ArchetypeGeneration(usize);
 
@@ -244,8 +321,9 @@ impl ArchetypeGeneration {
     }
 }
 
-/// 原型唯一标识，目前只用到的Identity
-/// 补充： 是否支持Components（动态原型，向beay一样）
+/// 原型唯一标识
+/// * Identity: 由原型类型标识
+/// * Components: 由组件集合标识（动态原型），组件id升序排列
 #[derive(Hash, PartialEq, Eq)]
 pub enum ArchetypeIdentity {
 	Identity(TypeId),
@@ -265,6 +343,8 @@ pub struct Archetypes {
 	// pub(crate) archetype_component_count: usize,
 	pub(crate) archetype_component_info: Vec<&'static str>,
 	pub(crate) data_mark: FixedBitSet, // archetype_component_info中的数据标记（archetype_component_info也包含system，这里排除了system）
+	/// 原型世代，每分配一个数据的原型组件id（新建原型、原型注册组件、注册资源），增长1
+	generation: usize,
 
 	/// 资源map， 通过资源id查询到资源
 	pub(crate) resources: Singles,
@@ -274,12 +354,17 @@ pub struct Archetypes {
 
 	/// todo, 手机监听器的资源访问，设置再system上，以便有正确的数据访问依赖
 	pub listener_component_access: XHashMap<ArchetypeComponentId, Vec<FilteredAccessSet<ArchetypeComponentId>>>,
+
+	/// 监听器级联的最大深度，由各事件源共享
+	pub(crate) listener_depth: ListenerDepth,
 }
 
 pub struct EntityDeleteType<A: ThreadSync + 'static>(PhantomData<A>);
 pub struct EntityType<A: ThreadSync + 'static>(PhantomData<A>);
 pub struct EntityComponentType<A: ThreadSync + 'static, C>(PhantomData<(A, C)>);
 pub struct ResourceType<R: ThreadSync + 'static>(PhantomData<R>);
+/// 动态原型的标记类型，仅用于生成访问信息的名称
+pub struct DynamicArchetype;
 
 impl Archetypes {
 	/// 构造方法
@@ -293,6 +378,8 @@ impl Archetypes {
 			listener_component_access: XHashMap::default(),
 			archetype_component_info: Vec::default(),
 			data_mark: FixedBitSet::default(),
+			generation: 0,
+			listener_depth: ListenerDepth::default(),
 		}
 	}
 
@@ -307,11 +394,12 @@ impl Archetypes {
 
 		let id = ArchetypeId::new(self.archetypes.len());
 
-		let archetype = Archetype::new(
+		let mut archetype = Archetype::new(
 			id, 
 			Local::new(self.archetype_component_grow(type_name::<EntityType<A>>(), true)),
 			Local::new(self.archetype_component_grow(type_name::<EntityDeleteType<A>>(), true)),
 		);
+		archetype.name = type_name::<A>();
 		archetype
     }
 
@@ -343,6 +431,44 @@ impl Archetypes {
 		self.archetypes.push(archetype);
 	}
 
+	/// 根据组件集合，取到动态原型id
+	/// * `components`必须为升序
+	pub fn get_id_by_components(&self, components: &[ComponentId]) -> Option<ArchetypeId> {
+		self.archetype_ids.get(&ArchetypeIdentity::Components(Cow::Owned(components.to_vec()))).cloned()
+	}
+
+	/// 创建动态原型，创建的原型中还未注册组件容器，由调用者按照`components`逐个注册
+	/// * `components`必须为升序
+	pub(crate) fn create_archetype_by_components(&mut self, components: Vec<ComponentId>) -> ArchetypeId {
+		let id = ArchetypeId::new(self.archetypes.len());
+		let mut archetype = Archetype::new(
+			id,
+			Local::new(self.archetype_component_grow(type_name::<EntityType<DynamicArchetype>>(), true)),
+			Local::new(self.archetype_component_grow(type_name::<EntityDeleteType<DynamicArchetype>>(), true)),
+		);
+		archetype.dynamic = true;
+		archetype.name = type_name::<DynamicArchetype>();
+		self.archetype_ids.insert(ArchetypeIdentity::Components(Cow::Owned(components)), id);
+		self.archetypes.push(archetype);
+		id
+	}
+
+	/// 将实体从原型`src`移动到原型`dst`，返回实体在`dst`中的LocalVersion
+	/// 两个原型都有的组件会被移动（不发出事件），`dst`中没有的组件被删除，并发出删除事件
+	pub(crate) fn move_entity(&mut self, src: ArchetypeId, local: LocalVersion, dst: ArchetypeId) -> LocalVersion {
+		let dst_local = self[dst].entities.insert_no_notify();
+		let (src_archetype, dst_archetype) = (&self[src], &self[dst]);
+		for id in src_archetype.component_ids.iter() {
+			let container = &src_archetype.components[*id];
+			match dst_archetype.components.get(*id) {
+				Some(dst_container) => container.move_to(local, dst_container, dst_local),
+				None => container.remove(local),
+			}
+		}
+		self[src].entities.remove_no_notify(local);
+		dst_local
+	}
+
 	/// 创建实体
 	pub(crate) fn spawn<E: ArchetypeIdent>(&mut self, id: ArchetypeId) -> Entity {
 		self.archetypes[id.offset()].create_entity()
@@ -370,6 +496,11 @@ impl Archetypes {
 		self.resources.get(id)
 	}
 
+	/// 移除资源
+	pub(crate) unsafe fn remove_resource<T: Resource>(&mut self, id: ResourceId) -> Option<T> {
+		self.resources.remove(id)
+	}
+
 	/// 根据资源的id, 取到资源的只读引用
 	pub unsafe fn get_resource_mut<T: Resource>(&self, id: ResourceId) -> Option<&mut T> {
 		self.resources.get_mut(id)
@@ -399,38 +530,92 @@ impl Archetypes {
 
 		// 如果是数据类型，则标记该位置
 		if is_data {
-			self.data_mark.insert(index)
+			self.data_mark.insert(index);
+			self.generation += 1;
 		}
 
 		index
 	}
 
-	/// 添加组件监听器
-	pub fn add_component_listener<T: ListenType, A: ArchetypeIdent, C: Component>(&mut self, listener: Listener, id: ComponentId) {
+	/// 取到原型，如果原型中不存在该组件，为其注册组件类型
+	fn get_or_register_component<A: ArchetypeIdent, C: Component>(&mut self, id: ComponentId) -> ArchetypeId {
 		let archetype_id = self.get_or_create_archetype::<A>();
 		if self.archetypes[archetype_id.offset()].components.get(id).is_none() {
 			let archetype_component_id = self.archetype_component_grow(std::any::type_name::<EntityComponentType<A, C>>(), true);
 			self.archetypes[archetype_id.offset()].register_component_type::<C>(id, ArchetypeComponentId::new(archetype_component_id))
 		}
-		
-		self.archetypes[archetype_id.offset()].add_component_listener::<T, C>(listener, id)
+		archetype_id
+	}
+
+	/// 添加接收旧值的组件监听器
+	pub fn add_component_old_listener<T: OldListenType, A: ArchetypeIdent, C: Component>(&mut self, listener: OldListener<C>, id: ComponentId) {
+		let archetype_id = self.get_or_register_component::<A, C>(id);
+		let archetype = &self.archetypes[archetype_id.offset()];
+		let container = unsafe { archetype.get_component(id) };
+		match container.downcast_ref::<CellMultiCase<C>>() {
+			Some(r) => T::old_listeners(&mut r.borrow_mut()).push(listener),
+			None => panic!("downcast err"),
+		}
+		unsafe {
+			container.set_notify_source(self.archetype_component_info[archetype.archetype_component_id(id).offset()], &self.listener_depth);
+		}
+	}
+
+	/// 移除接收旧值的组件监听器
+	pub fn remove_component_old_listener<T: OldListenType, A: ArchetypeIdent, C: Component>(&mut self, token: &Listener, id: ComponentId) {
+		let archetype_id = self.get_or_create_archetype::<A>();
+		if let Some(container) = self.archetypes[archetype_id.offset()].components.get(id) {
+			match container.downcast_ref::<CellMultiCase<C>>() {
+				Some(r) => T::old_listeners(&mut r.borrow_mut()).retain(|l| &l.0 != token),
+				None => panic!("downcast err"),
+			}
+		}
+	}
+
+	/// 添加组件监听器
+	pub fn add_component_listener<T: ListenType, A: ArchetypeIdent, C: Component>(&mut self, listener: Listener, id: ComponentId) {
+		let archetype_id = self.get_or_register_component::<A, C>(id);
+		self.archetypes[archetype_id.offset()].add_component_listener::<T, C>(listener, id);
+
+		let archetype = &self.archetypes[archetype_id.offset()];
+		unsafe {
+			let name = self.archetype_component_info[archetype.archetype_component_id(id).offset()];
+			archetype.get_component(id).set_notify_source(name, &self.listener_depth);
+		}
 	}
 
 	/// 添加原型监听器
 	pub fn add_entity_listener<T: ListenType, A: ArchetypeIdent>(&mut self, listener: Listener) {
 		let archetype_id = self.get_or_create_archetype::<A>();
-		self.archetypes[archetype_id.offset()].add_entity_listener::<T>(listener);
+		let archetype = &mut self.archetypes[archetype_id.offset()];
+		archetype.add_entity_listener::<T>(listener);
+		archetype.entities.entity_listners.set_source(self.archetype_component_info[archetype.entity_archetype_component_id().offset()], &self.listener_depth);
+	}
+
+	/// 移除组件监听器
+	pub fn remove_component_listener<T: ListenType, A: ArchetypeIdent, C: Component>(&mut self, listener: &Listener, id: ComponentId) {
+		let archetype_id = self.get_or_create_archetype::<A>();
+		self.archetypes[archetype_id.offset()].remove_component_listener::<T, C>(listener, id)
+	}
+
+	/// 移除原型监听器
+	pub fn remove_entity_listener<T: ListenType, A: ArchetypeIdent>(&mut self, listener: &Listener) {
+		let archetype_id = self.get_or_create_archetype::<A>();
+		self.archetypes[archetype_id.offset()].remove_entity_listener::<T>(listener);
 	}
 
 	/// 添加资源监听器
 	pub fn add_resource_listener<T: ListenType, R: Component>(&mut self, listener: Listener, id: ComponentId) {
 		self.resources.add_listener::<T, R>(id, listener);
+		if let Some(a_c_id) = self.archetype_resource_indices.get(&TypeId::of::<R>()) {
+			self.resources.set_notify_source(id, self.archetype_component_info[a_c_id.offset()], &self.listener_depth);
+		}
 	}
 
-	/// 取到当前原型id
+	/// 取到当前原型世代
     #[inline]
     pub fn generation(&self) -> ArchetypeGeneration {
-        ArchetypeGeneration(self.archetypes.len())
+        ArchetypeGeneration(self.generation)
     }
 
 	/// 原型数量
@@ -469,6 +654,14 @@ impl Archetypes {
         self.archetype_ids.get(&ArchetypeIdentity::Identity(type_id))
     }
 
+	/// 迭代由原型类型标识的原型（原型类型TypeId, 原型id）
+	pub(crate) fn ident_ids(&self) -> impl Iterator<Item = (TypeId, ArchetypeId)> + '_ {
+		self.archetype_ids.iter().filter_map(|(identity, id)| match identity {
+			ArchetypeIdentity::Identity(type_id) => Some((*type_id, *id)),
+			ArchetypeIdentity::Components(_) => None,
+		})
+	}
+
 	pub fn archetype_component_info(&self)  -> &Vec<&'static str>{
 		&self.archetype_component_info
 	}
@@ -0,0 +1,96 @@
+/// 组件集合
+/// 元组和`#[derive(Bundle)]`的结构体都是组件集合，可一次注册、插入一组组件，
+/// 通过World::spawn_batch批量创建实体时，每个实体插入一组组件
+use std::{any::type_name, sync::Arc};
+
+use pi_ecs_macros::all_tuples;
+use pi_share::ThreadSync;
+
+use crate::{
+	archetype::{ArchetypeId, ArchetypeIdent, Archetypes, EntityComponentType},
+	component::{CellMultiCase, Component, Components},
+	entity::Entity,
+	monitor::Notify,
+	storage::{Local, LocalVersion},
+	world::{ArchetypeInfo, EntityRef, WorldInner},
+};
+
+/// 组件集合，为元组实现，结构体可通过`#[derive(Bundle)]`实现
+pub trait Bundle: ThreadSync + 'static {
+	/// 每个组件的容器
+	type Containers;
+
+	/// 为原型注册每个组件
+	fn register(info: ArchetypeInfo) -> ArchetypeInfo;
+
+	/// 为实体插入每个组件
+	fn insert_entity<A: ArchetypeIdent>(self, entity: &mut EntityRef<A>);
+
+	/// 通过World::insert_component_dynamic逐个插入组件，返回插入后的实体
+	fn insert_world(self, world: &mut WorldInner, entity: Entity) -> Entity;
+
+	/// 为原型注册组件类型，取到组件容器，并为容器预留容量
+	fn containers<A: ArchetypeIdent>(archetypes: &mut Archetypes, components: &mut Components, archetype_id: ArchetypeId, additional: usize) -> Self::Containers;
+
+	/// 插入组件，不发出事件
+	fn insert(self, containers: &Self::Containers, local: LocalVersion, tick: u32);
+
+	/// 发出每个组件的创建事件
+	fn create_event(containers: &Self::Containers, entity: Entity);
+}
+
+/// 取到原型中组件的容器，原型未注册该组件时，为其注册
+fn container<A: ArchetypeIdent, C: Component>(archetypes: &mut Archetypes, components: &mut Components, archetype_id: ArchetypeId, additional: usize) -> Arc<CellMultiCase<C>> {
+	let id = components.get_or_insert_id::<C>();
+	if !archetypes[archetype_id].contains(id) {
+		let archetype_component_id = archetypes.archetype_component_grow(type_name::<EntityComponentType<A, C>>(), true);
+		archetypes[archetype_id].register_component_type::<C>(id, Local::new(archetype_component_id));
+	}
+	let container: Arc<CellMultiCase<C>> = match unsafe { archetypes[archetype_id].get_component(id) }.clone().downcast() {
+		Ok(r) => r,
+		Err(_) => panic!("downcast err"),
+	};
+	container.borrow_mut().reserve(additional);
+	container
+}
+
+macro_rules! impl_bundle {
+	($(($c: ident, $container: ident)),*) => {
+		#[allow(non_snake_case, unused_variables, clippy::unused_unit)]
+		impl<$($c: Component),*> Bundle for ($($c,)*) {
+			type Containers = ($(Arc<CellMultiCase<$c>>,)*);
+
+			fn register(info: ArchetypeInfo) -> ArchetypeInfo {
+				info$(.register::<$c>())*
+			}
+
+			fn insert_entity<A: ArchetypeIdent>(self, entity: &mut EntityRef<A>) {
+				let ($($c,)*) = self;
+				$(entity.insert($c);)*
+			}
+
+			fn insert_world(self, world: &mut WorldInner, entity: Entity) -> Entity {
+				let ($($c,)*) = self;
+				$(let entity = world.insert_component_dynamic(entity, $c);)*
+				entity
+			}
+
+			fn containers<A: ArchetypeIdent>(archetypes: &mut Archetypes, components: &mut Components, archetype_id: ArchetypeId, additional: usize) -> Self::Containers {
+				($(container::<A, $c>(archetypes, components, archetype_id, additional),)*)
+			}
+
+			fn insert(self, containers: &Self::Containers, local: LocalVersion, tick: u32) {
+				let ($($c,)*) = self;
+				let ($($container,)*) = containers;
+				$($container.borrow_mut().insert_no_notify(local, $c, tick);)*
+			}
+
+			fn create_event(containers: &Self::Containers, entity: Entity) {
+				let ($($container,)*) = containers;
+				$($container.create_event(entity);)*
+			}
+		}
+	};
+}
+
+all_tuples!(impl_bundle, 0, 16, C, c);
@@ -0,0 +1,105 @@
+/// 压缩
+/// 大量实体销毁（如关卡卸载）后，组件容器、ticks及实体槽位仍保持峰值时的容量
+/// `World::compact`释放组件容器中未使用的容量；`World::compact_with_remap`还会将实体重新编号为连续的槽位，
+/// 并通过资源`EntityRemap`报告旧实体到新实体的映射，组件中保存的`Id<A>`可据此修正
+use std::any::TypeId;
+
+use pi_hash::XHashMap;
+
+use crate::{
+	archetype::ArchetypeId,
+	entity::{Entity, Id},
+	query::filter::DirtyLists,
+	storage::LocalVersion,
+	world::WorldInner,
+};
+
+/// 实体重新编号的映射表（旧实体 → 新实体），按原型分表，编号未变化的实体不在表中
+/// `World::compact_with_remap`有实体被重新编号时插入一次该资源，包含所有原型的映射表，
+/// 可通过`#[listen(resource = (EntityRemap, (Create, Modify)))]`监听，并在监听器中修正组件中的实体id
+#[derive(Debug, Default)]
+pub struct EntityRemap {
+	maps: XHashMap<ArchetypeId, XHashMap<LocalVersion, LocalVersion>>,
+	/// 重新编号的原型中，由原型类型标识的原型（用于`get_id`）
+	idents: XHashMap<TypeId, ArchetypeId>,
+}
+
+impl EntityRemap {
+	/// 迭代重新编号的原型
+	pub fn archetypes(&self) -> impl Iterator<Item = ArchetypeId> + '_ {
+		self.maps.keys().copied()
+	}
+
+	/// 取到原型中旧实体对应的新实体
+	#[inline]
+	pub fn get(&self, archetype_id: ArchetypeId, local: LocalVersion) -> Option<LocalVersion> {
+		self.maps.get(&archetype_id).and_then(|map| map.get(&local).copied())
+	}
+
+	/// 取到旧实体id对应的新实体id，`A`不是重新编号的原型时返回None
+	#[inline]
+	pub fn get_id<A: 'static>(&self, id: Id<A>) -> Option<Id<A>> {
+		let archetype_id = self.idents.get(&TypeId::of::<A>())?;
+		self.get(*archetype_id, id.0).map(|r| unsafe { Id::new(r) })
+	}
+
+	/// 取到旧实体对应的新实体
+	#[inline]
+	pub fn get_entity(&self, entity: Entity) -> Option<Entity> {
+		self.get(entity.archetype_id(), entity.local()).map(|r| Entity::new(entity.archetype_id(), r))
+	}
+
+	/// 迭代所有原型的（旧实体, 新实体）
+	pub fn iter(&self) -> impl Iterator<Item = (Entity, Entity)> + '_ {
+		self.maps.iter().flat_map(|(archetype_id, map)| {
+			map.iter().map(move |(old, new)| (Entity::new(*archetype_id, *old), Entity::new(*archetype_id, *new)))
+		})
+	}
+
+	/// 所有原型中重新编号的实体数量
+	pub fn len(&self) -> usize {
+		self.maps.values().map(|map| map.len()).sum()
+	}
+
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.maps.is_empty()
+	}
+}
+
+impl WorldInner {
+	/// 释放所有原型的组件容器、ticks，以及查询脏列表中未使用的容量，实体编号不变
+	pub fn compact(&mut self) {
+		for archetype in self.archetypes.archetypes.iter_mut() {
+			archetype.shrink();
+		}
+		self.shrink_dirty_lists();
+	}
+
+	/// 将所有原型的实体重新编号为连续的槽位，并释放所有容器中未使用的容量，重新编号不发出实体和组件事件
+	/// 有实体被重新编号时，插入一次包含所有原型映射表的资源`EntityRemap`（发出资源的创建或修改事件）
+	/// 应在一帧结束、查询已处理完变化之后调用：重新编号前记录在脏列表中的实体不会随之修正
+	pub fn compact_with_remap(&mut self) {
+		let maps: XHashMap<ArchetypeId, XHashMap<LocalVersion, LocalVersion>> = self.archetypes.archetypes
+			.iter_mut()
+			.map(|archetype| (archetype.id(), archetype.renumber()))
+			.filter(|(_, remap)| !remap.is_empty())
+			.map(|(archetype_id, remap)| (archetype_id, remap.into_iter().collect()))
+			.collect();
+		self.shrink_dirty_lists();
+		if maps.is_empty() {
+			return;
+		}
+
+		let idents = self.archetypes.ident_ids()
+			.filter(|(_, archetype_id)| maps.contains_key(archetype_id))
+			.collect();
+		self.insert_resource(EntityRemap { maps, idents });
+	}
+
+	fn shrink_dirty_lists(&mut self) {
+		if let Some(dirty_lists) = self.get_resource_mut::<DirtyLists>() {
+			dirty_lists.shrink();
+		}
+	}
+}
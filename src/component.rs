@@ -1,6 +1,7 @@
 /// 组件
-use std::{any::{TypeId, type_name}, ops::Index, ops::IndexMut};
+use std::{any::{TypeId, type_name}, mem::size_of, ops::Index, ops::IndexMut};
 use std::collections::hash_map::Entry;
+use std::sync::Arc;
 
 use pi_map::{Map, vecmap::VecMap};
 use pi_share::cell::TrustCell;
@@ -12,8 +13,9 @@ use pi_share::ThreadSync;
 
 
 use crate::{
-	storage::{LocalVersion, Local, Offset, SecondaryMap},
-	monitor::{Notify, NotifyImpl, Listener}, entity::Entity,
+	storage::{LocalVersion, Local, Offset, Reserve, SecondaryMap, Shrink, StorageKind},
+	monitor::{Notify, NotifyImpl, Listener, OldListener, EventType, ListenerDepth}, entity::Entity,
+	archetype::{DynamicArchetype, EntityComponentType},
 };
 
 pub trait ComponentStorage {
@@ -32,8 +34,61 @@ impl<C> Component for C where C: ComponentStorage + ThreadSync + 'static{
 	type Storage = <C as ComponentStorage>::Type;
 }
 
+/// 标记组件不记录ticks（`#[component(no_ticks)]`），插入、修改时不写入节拍，也不为节拍分配内存
+/// 这类组件不能用于依赖节拍的查询（Changed、Added、Modifyed、ChangeTrackers）
+pub trait NoTicks {}
+
+/// 组件是否记录ticks，实现了NoTicks的组件为false
+pub trait TrackTicks {
+	const TRACK_TICKS: bool;
+}
+
+impl<T> TrackTicks for T {
+	default const TRACK_TICKS: bool = true;
+}
+
+impl<T: NoTicks> TrackTicks for T {
+	const TRACK_TICKS: bool = false;
+}
+
+/// 组件能否整体借用
+/// SoA存储的组件按字段分列存放，只能通过`Field`、`FieldMut`访问字段
+pub fn is_whole_value<C: Component>() -> bool {
+	<C::Storage as StorageKind>::STORAGE_TYPE != StorageType::SoA
+}
+
+/// 组件字段的标记类型，由`#[derive(ComponentFields)]`生成，NAME为字段名
+pub trait FieldName: ThreadSync + 'static {
+	const NAME: &'static str;
+}
+
 pub trait MultiCase: ArcAny {
+    /// 删除组件，不发出事件
     fn delete(&self, id: LocalVersion);
+
+	/// 删除组件，并发出删除事件（事件发出时，组件仍可读取）
+	fn remove(&self, id: LocalVersion);
+
+	/// 创建一个同类型的空容器（用于动态原型，无需知道组件的具体类型）
+	fn new_container(&self, archetype_id: Local) -> Arc<dyn MultiCase>;
+
+	/// 将实体的组件（包括其ticks）移动到另一个同类型容器中，不发出任何事件
+	fn move_to(&self, id: LocalVersion, dst: &Arc<dyn MultiCase>, dst_id: LocalVersion);
+
+	/// 检查容器中所有组件的节拍，截断过旧的节拍
+	fn check_ticks(&self, change_tick: u32);
+
+	/// 设置事件源的名称（用于监听器级联出错时的提示）和所在世界的级联最大深度
+	fn set_notify_source(&self, name: &'static str, max_depth: &ListenerDepth);
+
+	/// 容器占用的字节数
+	fn mem_size(&self) -> usize;
+
+	/// 释放未使用的容量
+	fn shrink(&self);
+
+	/// 按映射表（旧实体, 新实体）移动组件，不发出事件（实体重新编号时使用）
+	fn remap(&self, remap: &[(LocalVersion, LocalVersion)]);
 }
 
 pub type CellMultiCase<C> = TrustCell<MultiCaseImpl<C>>;
@@ -43,6 +98,10 @@ pub struct MultiCaseImpl<C: Component> {
     notify: NotifyImpl,
 	archetype_id: Local,
 	ticks: VecMap<ComponentTicks>,
+	/// 接收旧值的修改监听器
+	pub(crate) modify_old: Vec<OldListener<C>>,
+	/// 接收旧值的删除监听器
+	pub(crate) delete_old: Vec<OldListener<C>>,
 }
 
 unsafe impl<C: Component> Send for MultiCaseImpl<C> {}
@@ -79,12 +138,19 @@ impl< C: Component> MultiCaseImpl<C> {
             notify: NotifyImpl::default(),
 			archetype_id,
 			ticks: VecMap::default(),
+			modify_old: Vec::new(),
+			delete_old: Vec::new(),
         }
 	}
 
-    // pub fn mem_size(&self) -> usize {
-    //     self.map.mem_size() + self.notify.mem_size()
-    // }
+    /// 容器占用的字节数（组件存储、ticks及监听器）
+    pub fn mem_size(&self) -> usize {
+        self.map.mem_size()
+			+ self.ticks.capacity() * size_of::<Option<ComponentTicks>>()
+			+ self.notify.mem_size()
+			+ (self.modify_old.capacity() + self.delete_old.capacity()) * size_of::<OldListener<C>>()
+    }
+
     pub fn get(&self, id: LocalVersion) -> Option<&C> {
         self.map.get(&id)
     }
@@ -99,28 +165,37 @@ impl< C: Component> MultiCaseImpl<C> {
     }
     
     pub fn insert(&mut self, id: LocalVersion, c: C, tick: u32) -> Option<C> {
-        let r = self.map.insert(id, c);
-        match r {
-            Some(_) => {
-				self.ticks[id.offset()].changed = tick;
-				self.notify.modify_event(Entity::new(self.archetype_id, id), "", 0)
-			}
-            _ => {
-				self.ticks.insert(id.offset(), ComponentTicks::new(tick));
-                self.notify.create_event(Entity::new(self.archetype_id, id));
-            }
-        }
+        let r = self.insert_no_notify(id, c, tick);
+		Self::send_insert_event(&self.notify, &self.modify_old, Entity::new(self.archetype_id, id), r.as_ref());
         r
     }
 
+	// 发出插入事件，old为被替换的旧值
+	fn send_insert_event(notify: &NotifyImpl, modify_old: &[OldListener<C>], e: Entity, old: Option<&C>) {
+		match old {
+			Some(old) => {
+				notify.modify_event(e, "", 0);
+				for l in modify_old.iter() {
+					l.listen(notify, e, EventType::Modify, old);
+				}
+			}
+			None => notify.create_event(e),
+		}
+	}
+
 	// 通知修改
 	pub fn notify_modify(&mut self, id: LocalVersion, tick: u32) {
-		self.ticks[id.offset()].changed = tick;
-		self.notify.modify_event(Entity::new(self.archetype_id, id), "", 0)
+		self.notify_modify_field(id, tick, "", 0)
+	}
+
+	// 通知字段修改，index为数组或向量字段中被修改元素的索引
+	pub fn notify_modify_field(&mut self, id: LocalVersion, tick: u32, field: &'static str, index: usize) {
+		self.set_changed(id, tick);
+		self.notify.modify_event(Entity::new(self.archetype_id, id), field, index)
 	}
 
 	pub fn notify_delete(&mut self, id: LocalVersion, tick: u32) {
-		self.ticks[id.offset()].changed = tick;
+		self.set_changed(id, tick);
 		self.notify.delete_event(Entity::new(self.archetype_id, id))
 	}
 
@@ -136,17 +211,47 @@ impl< C: Component> MultiCaseImpl<C> {
         let r = self.map.insert(id, c);
 		match r {
             Some(_) => {
-				self.ticks[id.offset()].changed = tick;
+				self.set_changed(id, tick);
 			},
-            _ => {self.ticks.insert(id.offset(), ComponentTicks::new(tick));},
+            _ => self.set_ticks(id, ComponentTicks::new(tick)),
         };
         r
     }
 
+	/// 插入组件并设置其ticks，不发出事件（快照恢复时使用）
+	pub(crate) fn insert_with_ticks(&mut self, id: LocalVersion, c: C, ticks: ComponentTicks) {
+		self.map.insert(id, c);
+		self.set_ticks(id, ticks);
+	}
+
+	// 更新组件的修改节拍，组件不记录ticks时忽略
+	#[inline]
+	fn set_changed(&mut self, id: LocalVersion, tick: u32) {
+		if C::TRACK_TICKS {
+			self.ticks[id.offset()].changed = tick;
+		}
+	}
+
+	// 设置组件的节拍，组件不记录ticks时忽略
+	#[inline]
+	fn set_ticks(&mut self, id: LocalVersion, ticks: ComponentTicks) {
+		if C::TRACK_TICKS {
+			self.ticks.insert(id.offset(), ticks);
+		}
+	}
+
     pub fn delete(&mut self, id: LocalVersion) -> Option<C> {
-		if self.map.get(&id).is_some() {
-			self.notify.delete_event(Entity::new( self.archetype_id, id));
-			self.map.remove(&id)
+		if self.map.contains(&id) {
+			let e = Entity::new( self.archetype_id, id);
+			self.notify.delete_event(e);
+			self.ticks.remove(id.offset());
+			let r = self.map.remove(&id);
+			if let Some(old) = &r {
+				for l in self.delete_old.iter() {
+					l.listen(&self.notify, e, EventType::Delete, old);
+				}
+			}
+			r
 		} else {
 			None
 		}
@@ -163,15 +268,140 @@ impl< C: Component> MultiCaseImpl<C> {
 	pub fn contains_key(&self, local: &LocalVersion) -> bool {
 		self.map.contains(local)
 	}
+
+	/// 为组件及其ticks预留容量
+	pub fn reserve(&mut self, additional: usize) {
+		Reserve::reserve(&mut self.map, additional);
+		self.ticks.reserve(additional);
+	}
+
+	/// 释放组件存储及ticks中未使用的容量
+	pub fn shrink(&mut self) {
+		Shrink::shrink(&mut self.map);
+		// VecMap没有收缩容量的方法，按最大的槽位重建
+		let len = self.ticks.iter().rposition(|r| r.is_some()).map_or(0, |r| r + 1);
+		let mut ticks = VecMap::with_capacity(len);
+		for (i, tick) in self.ticks.iter().enumerate() {
+			if let Some(tick) = tick {
+				ticks.insert(i, *tick);
+			}
+		}
+		self.ticks = ticks;
+		self.modify_old.shrink_to_fit();
+		self.delete_old.shrink_to_fit();
+	}
+
+	/// 按映射表（旧实体, 新实体）将组件及其ticks移动到新的实体上，不发出事件
+	pub(crate) fn remap(&mut self, remap: &[(LocalVersion, LocalVersion)]) {
+		let mut moved = Vec::with_capacity(self.map.len());
+		for (old, new) in remap.iter() {
+			if let Some(value) = self.map.remove(old) {
+				moved.push((*new, value, self.ticks.remove(old.offset())));
+			}
+		}
+		for (new, value, tick) in moved {
+			self.map.insert(new, value);
+			if let Some(tick) = tick {
+				self.ticks.insert(new.offset(), tick);
+			}
+		}
+	}
 }
 
 impl_downcast_arc!(MultiCase);
 
+/// 插入组件，发出事件时不持有容器的借用，监听器中可以读写该容器
+pub(crate) fn insert_cell<C: Component>(cell: &CellMultiCase<C>, id: LocalVersion, c: C, tick: u32) -> Option<C> {
+	let (r, notify, e, modify_old) = {
+		let mut container = cell.borrow_mut();
+		let r = container.insert_no_notify(id, c, tick);
+		let modify_old = match r {
+			Some(_) => container.modify_old.clone(),
+			None => Vec::new(),
+		};
+		(r, container.get_notify(), Entity::new(container.archetype_id, id), modify_old)
+	};
+	MultiCaseImpl::send_insert_event(&notify, &modify_old, e, r.as_ref());
+	r
+}
+
 impl<C: Component> MultiCase for CellMultiCase<C> {
     fn delete(&self, id: LocalVersion) {
-        // 实体删除，组件不再监听删除事件
-        self.borrow_mut().map.remove(&id);
+        let mut container = self.borrow_mut();
+        container.ticks.remove(id.offset());
+        container.map.remove(&id);
     }
+
+	fn remove(&self, id: LocalVersion) {
+		let (notify, archetype_id, delete_old) = {
+			let container = self.borrow();
+			if !container.map.contains(&id) {
+				return;
+			}
+			(container.get_notify(), container.archetype_id, container.delete_old.clone())
+		};
+		// 发出事件时不持有容器的借用，监听器中可以读取该容器
+		let e = Entity::new(archetype_id, id);
+		notify.delete_event(e);
+		let old = {
+			let mut container = self.borrow_mut();
+			container.ticks.remove(id.offset());
+			container.map.remove(&id)
+		};
+		if let Some(old) = &old {
+			for l in delete_old.iter() {
+				l.listen(&notify, e, EventType::Delete, old);
+			}
+		}
+	}
+
+	fn new_container(&self, archetype_id: Local) -> Arc<dyn MultiCase> {
+		Arc::new(TrustCell::new(MultiCaseImpl::<C>::with_capacity(0, archetype_id)))
+	}
+
+	fn move_to(&self, id: LocalVersion, dst: &Arc<dyn MultiCase>, dst_id: LocalVersion) {
+		let mut src = self.borrow_mut();
+		let value = match src.map.remove(&id) {
+			Some(r) => r,
+			None => return,
+		};
+		let tick = src.ticks.remove(id.offset());
+		match dst.downcast_ref::<CellMultiCase<C>>() {
+			Some(dst) => {
+				let mut dst = dst.borrow_mut();
+				dst.map.insert(dst_id, value);
+				if let Some(tick) = tick {
+					dst.ticks.insert(dst_id.offset(), tick);
+				}
+			},
+			None => panic!("downcast err"),
+		}
+	}
+
+	fn set_notify_source(&self, name: &'static str, max_depth: &ListenerDepth) {
+		self.borrow().notify.set_source(name, max_depth);
+	}
+
+	fn mem_size(&self) -> usize {
+		self.borrow().mem_size()
+	}
+
+	fn shrink(&self) {
+		MultiCaseImpl::shrink(&mut self.borrow_mut());
+	}
+
+	fn remap(&self, remap: &[(LocalVersion, LocalVersion)]) {
+		self.borrow_mut().remap(remap);
+	}
+
+	fn check_ticks(&self, change_tick: u32) {
+		let mut container = self.borrow_mut();
+		for i in 0..container.ticks.iter().len() {
+			if let Some(tick) = container.ticks.get_mut(i) {
+				tick.check_ticks(change_tick);
+			}
+		}
+	}
 }
 
 impl<C: Component> Notify for CellMultiCase<C>{
@@ -184,6 +414,15 @@ impl<C: Component> Notify for CellMultiCase<C>{
     fn add_modify(&self, listener: Listener) {
         self.borrow_mut().notify.add_modify(listener)
     }
+    fn add_migrate(&self, listener: Listener) {
+        self.borrow_mut().notify.add_migrate(listener)
+    }
+    fn add_restore(&self, listener: Listener) {
+        self.borrow_mut().notify.add_restore(listener)
+    }
+    fn add_modify_field(&self, field: &'static str, listener: Listener) {
+        self.borrow_mut().notify.add_modify_field(field, listener)
+    }
     fn create_event(&self, id: Entity) {
         self.borrow().notify.create_event(id);
     }
@@ -193,6 +432,12 @@ impl<C: Component> Notify for CellMultiCase<C>{
     fn modify_event(&self, id: Entity, field: &'static str, index: usize) {
         self.borrow().notify.modify_event(id, field, index);
     }
+    fn migrate_event(&self, id: Entity, from: Entity) {
+        self.borrow().notify.migrate_event(id, from);
+    }
+    fn restore_event(&self, id: Entity) {
+        self.borrow().notify.restore_event(id);
+    }
     fn remove_create(&self, listener: &Listener) {
         self.borrow_mut().notify.remove_create(listener);
     }
@@ -202,6 +447,15 @@ impl<C: Component> Notify for CellMultiCase<C>{
     fn remove_modify(&self, listener: &Listener) {
         self.borrow_mut().notify.remove_modify(listener);
     }
+    fn remove_migrate(&self, listener: &Listener) {
+        self.borrow_mut().notify.remove_migrate(listener);
+    }
+    fn remove_restore(&self, listener: &Listener) {
+        self.borrow_mut().notify.remove_restore(listener);
+    }
+    fn remove_modify_field(&self, field: &'static str, listener: &Listener) {
+        self.borrow_mut().notify.remove_modify_field(field, listener);
+    }
 }
 
 
@@ -211,6 +465,8 @@ pub struct ComponentInfo {
 	pub(crate) storage_type: StorageType,
 	pub(crate) id: ComponentId,
 	pub(crate) name: &'static str,
+	/// 组件在动态原型中的访问名称（`EntityComponentType<DynamicArchetype, C>`）
+	pub(crate) dynamic_name: &'static str,
 }
 
 impl ComponentInfo {
@@ -257,8 +513,9 @@ impl Components {
 				let index = ComponentId::new(index);
 				self.infos.push(ComponentInfo{
 					id: index, 
-					storage_type: StorageType::Table,
+					storage_type: StorageType::Resource,
 					name: type_name::<T>(),
+					dynamic_name: type_name::<T>(),
 				});
 				r.insert(index);
 				index
@@ -272,7 +529,12 @@ impl Components {
 
     #[inline]
     pub fn get_or_insert_id<T: Component>(&mut self) -> ComponentId {
-        self.get_or_insert_with(TypeId::of::<T>(), std::any::type_name::<T>())
+        self.get_or_insert_with(
+			TypeId::of::<T>(),
+			std::any::type_name::<T>(),
+			std::any::type_name::<EntityComponentType<DynamicArchetype, T>>(),
+			<T::Storage as StorageKind>::STORAGE_TYPE,
+		)
     }
 
     #[inline]
@@ -315,14 +577,17 @@ impl Components {
         &mut self,
         type_id: TypeId,
 		name: &'static str,
+		dynamic_name: &'static str,
+		storage_type: StorageType,
     ) -> ComponentId {
         let components = &mut self.infos;
         let index = self.indices.entry(type_id).or_insert_with(|| {
             let index = components.len();
             components.push(ComponentInfo{
 				id: ComponentId::new(index), 
-				storage_type: StorageType::Table,
+				storage_type,
 				name,
+				dynamic_name,
 			});
             index
         });
@@ -331,18 +596,53 @@ impl Components {
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+/// 组件的存储类型，可通过`#[derive(Component)] #[storage(dense)]`选择
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
 pub enum StorageType {
-    Table,
-    SparseSet,
+    /// 按实体槽位索引的数组（`SecondaryMap`），默认的存储
+    #[default]
+    Dense,
+    /// 稀疏数组（`SparseSecondaryMap`）
+    Sparse,
+    /// 哈希表（`HashSecondaryMap`）
+    Hash,
+    /// 零大小的标记组件，只记录是否存在（`TagMap`）
+    Tag,
+    /// 结构数组，各字段分列存放（`SoAMap`）
+    SoA,
+    /// 值连续存放，删除时交换到末尾（`PackedMap`）
+    Packed,
+    /// 自定义的存储容器
+    Custom,
+    /// 资源
+    Resource,
 }
 
-impl Default for StorageType {
-    fn default() -> Self {
-        StorageType::Table
-    }
+#[allow(non_upper_case_globals)]
+impl StorageType {
+    /// 旧名称，即`Dense`
+    #[deprecated(note = "use StorageType::Dense")]
+    pub const Table: StorageType = StorageType::Dense;
+    /// 旧名称，即`Sparse`
+    #[deprecated(note = "use StorageType::Sparse")]
+    pub const SparseSet: StorageType = StorageType::Sparse;
 }
 
+/// 节拍检查的间隔，世界节拍每前进该值，检查一次所有节拍
+pub const CHECK_TICK_THRESHOLD: u32 = 518_400_000;
+
+/// 节拍的最大年龄，比该年龄更旧的节拍会被截断
+/// 节拍是会回绕的u32，若不截断，回绕后过旧的节拍会被误判为新增或修改
+pub const MAX_CHANGE_AGE: u32 = u32::MAX - (2 * CHECK_TICK_THRESHOLD - 1);
+
+/// 截断节拍，使其年龄不超过MAX_CHANGE_AGE
+#[inline]
+pub fn check_tick(tick: &mut u32, change_tick: u32) {
+    let age = change_tick.wrapping_sub(*tick);
+    if age > MAX_CHANGE_AGE {
+        *tick = change_tick.wrapping_sub(MAX_CHANGE_AGE);
+    }
+}
 
 #[derive(Copy, Clone, Debug)]
 pub struct ComponentTicks {
@@ -383,23 +683,25 @@ impl ComponentTicks {
         component_delta < system_delta
     }
 
-	/// todo
+	/// 是否已被删除
+	/// 组件被删除时其ticks一并被移除（与`Deleted`过滤器一致，以组件是否存在判断），持有ticks的组件总是未被删除
+	#[deprecated(note = "use the Deleted filter or RemovedComponents")]
 	#[inline]
     pub fn is_deleted(&self, _last_change_tick: u32, _change_tick: u32) -> bool {
-        true
+        false
     }
 
-    pub(crate) fn new(change_tick: u32) -> Self {
+    pub fn new(change_tick: u32) -> Self {
         Self {
             added: change_tick,
             changed: 0,
         }
     }
 
-	#[allow(dead_code)]
-    pub(crate) fn check_ticks(&mut self, _change_tick: u32) {
-        // check_tick(&mut self.added, change_tick);
-        // check_tick(&mut self.changed, change_tick);
+	/// 截断过旧的节拍
+    pub fn check_ticks(&mut self, change_tick: u32) {
+        check_tick(&mut self.added, change_tick);
+        check_tick(&mut self.changed, change_tick);
     }
 
     /// Manually sets the change tick.
@@ -560,7 +560,7 @@ fn write_depend(w: &World, read_writes: &FixedBitSet, writes: &FixedBitSet, modi
 		// 取到对应监听器的写入，判断冲突
 		let v = w.listener_access.get(Local::new(write));
 		if let Some(r) = v {
-			for access in r.iter() {
+			for (_, access) in r.iter() {
 				// 收集监听器的写入是否与访问冲突
 				let mut conflict = read_writes.clone();
 				conflict.intersect_with(access.combined_access().get_writes());
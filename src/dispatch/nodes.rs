@@ -5,7 +5,7 @@ use crate::{
         param::SystemParam,
         system::{
             func_sys::{FunctionSystem, SystemParamFunction},
-            System,
+            CheckChangeTick, System,
         },
     },
 	query::Access,
@@ -57,6 +57,15 @@ where
     }
 }
 
+impl<Param: SystemParam + 'static, Out: ThreadSend + 'static, F> CheckChangeTick for SyncRun<Param, Out, F>
+where
+    F: SystemParamFunction<(), Out, Param, ()> + ThreadSend + 'static,
+{
+    fn check_change_tick(&self, change_tick: u32) {
+        self.0.check_change_tick(change_tick);
+    }
+}
+
 impl<Param, Out, F> Operate for AsyncRun<Param, Out, F>
 where
     F: SystemParamFunction<(), Out, Param, ()> + ThreadSend + 'static,
@@ -91,13 +100,15 @@ where
         let id = self.id();
 		let name = self.name().to_string();
         let component_access = self.archetype_component_access().clone();
+        let mut world = self.world.clone();
 
-        let sys = TrustCell::new(self);
+        let sys = Share::new(SyncRun(TrustCell::new(self)));
+        world.add_system_tick(&sys);
         GraphNode {
             id: id.id(),
             // reads,
             // writes,
-            node: ExecNode::Sync(Run(Share::new(SyncRun(sys)))),
+            node: ExecNode::Sync(Run(sys)),
 			access: component_access,
 			label: name,
         }
@@ -115,8 +126,10 @@ where
         let id = self.id();
 		let name = self.name().to_string();
         let component_access = self.archetype_component_access().clone();
+        let mut world = self.world.clone();
 
         let sys = Share::new(TrustCell::new(self));
+        world.add_system_tick(&sys);
         GraphNode {
             id: id.id(),
             node: ExecNode::Async(super::interface::AsyncRun(Share::new(AsyncRun(sys)))),
@@ -131,10 +144,10 @@ impl Arrange for World {
         let mut w = self.clone();
         let id = w.archetype_component_grow("arrange", false);
         let sys = move || {
-			for l in w.listeners.iter() {
-				l.apply();
-			}
+			w.clone().apply_listeners();
+			w.clone().update_events();
             w.increment_change_tick();
+            w.clone().try_check_change_ticks();
         };
         Some(GraphNode {
             id,
@@ -3,6 +3,7 @@ use std::{
 	hash::Hash,
 	cmp::{Eq,  Ord, PartialEq, PartialOrd},
 	fmt::Debug,
+	mem::size_of,
 };
 use std::ops::{Deref, DerefMut};
 
@@ -173,6 +174,13 @@ impl Entities {
 		self.storage.contains_key(key)
 	}
 
+	/// 实体槽位占用的字节数
+	/// DelaySlotMap的键数组和槽位数组（每槽位为版本、索引两个u32）均按键的容量估算
+	pub fn mem_size(&self) -> usize {
+		self.storage.capacity() * (size_of::<LocalVersion>() + 2 * size_of::<u32>())
+			+ self.entity_listners.mem_size()
+	}
+
 	pub fn remove(&mut self, local: LocalVersion) -> Option<()> {
 		if self.storage.contains_key(local) {
 			self.entity_listners.delete_event(Entity::new(self.arch_id, local));
@@ -186,6 +194,85 @@ impl Entities {
 		local
 	}
 
+	/// 插入实体，不发出事件（实体在动态原型之间移动、批量创建实体时使用）
+	pub(crate) fn insert_no_notify(&mut self) -> LocalVersion {
+		self.storage.insert(())
+	}
+
+	/// 移除实体，不发出事件（实体在动态原型之间移动时使用）
+	pub(crate) fn remove_no_notify(&mut self, local: LocalVersion) -> Option<()> {
+		self.storage.remove(local)
+	}
+
+	/// 将实体重新编号为连续的槽位，并释放多余的容量，不发出事件
+	/// 返回编号发生变化的实体（旧实体, 新实体），调用前必须先flush
+	/// 新槽位的版本高于该槽位上曾经分配过的所有版本，重新编号前的实体不会与新实体混淆（ABA）
+	pub(crate) fn renumber(&mut self) -> Vec<(LocalVersion, LocalVersion)> {
+		let mut olds: Vec<LocalVersion> = self.storage.keys().collect();
+		olds.sort_by_key(|r| r.offset());
+		let floors = self.version_floors();
+
+		let mut storage = DelaySlotMap::default();
+		storage.reserve(olds.len());
+		let remap = olds.into_iter()
+			.map(|old| {
+				// 新容器中没有空闲槽位，反复移除、插入会复用同一槽位，每次版本加2
+				let mut new = storage.insert(());
+				while version(new) <= floors[new.offset()] {
+					storage.remove(new);
+					new = storage.insert(());
+				}
+				(old, new)
+			})
+			.filter(|(old, new)| old != new)
+			.collect();
+		self.storage = storage;
+		remap
+	}
+
+	/// 将实体容器恢复为快照中的容器，不发出事件
+	/// 恢复后空闲槽位的版本高于该槽位上曾经分配过的所有版本，快照之后的实体不会与恢复后创建的实体混淆（ABA）
+	pub(crate) fn restore(&mut self, snapshot: &DelaySlotMap<LocalVersion, ()>) {
+		let floors = self.version_floors();
+		let mut storage = snapshot.clone();
+		// 反复插入，版本不高于下限的槽位移除后回到空闲队列末尾（版本加2），
+		// 直到分配出新的槽位，此时曾经分配过的槽位都已高于下限
+		let mut temps = Vec::new();
+		loop {
+			let local = storage.insert(());
+			if local.offset() >= floors.len() {
+				storage.remove(local);
+				break;
+			}
+			if version(local) > floors[local.offset()] {
+				temps.push(local);
+			} else {
+				storage.remove(local);
+			}
+		}
+		for local in temps {
+			storage.remove(local);
+		}
+		self.storage = storage;
+	}
+
+	/// 每个槽位曾经分配过的最大版本（按槽位索引）：存活槽位取其当前版本，
+	/// 空闲槽位通过预留取到下次分配的版本（调用后容器会被替换，预留无需flush）
+	fn version_floors(&self) -> Vec<u32> {
+		let mut floors = Vec::new();
+		for local in self.storage.keys() {
+			floor(&mut floors, local);
+		}
+		loop {
+			let local = self.storage.reserve_entity();
+			if version(local) == 1 {
+				break;
+			}
+			floor(&mut floors, local);
+		}
+		floors
+	}
+
 	pub fn flush(&mut self) {
 		let (storage, entity_listners) = (
 			unsafe{&mut *(&self.storage as *const DelaySlotMap<LocalVersion, ()> as usize as *mut DelaySlotMap<LocalVersion, ()>)}, 
@@ -199,4 +286,18 @@ impl Entities {
 	}
 }
 
-pub enum Never {}
\ No newline at end of file
+pub enum Never {}
+
+#[inline]
+fn version(local: LocalVersion) -> u32 {
+	(local.data().as_ffi() >> 32) as u32
+}
+
+/// 记录槽位的最大版本
+#[inline]
+fn floor(floors: &mut Vec<u32>, local: LocalVersion) {
+	if floors.len() <= local.offset() {
+		floors.resize(local.offset() + 1, 0);
+	}
+	floors[local.offset()] = floors[local.offset()].max(version(local));
+}
\ No newline at end of file
@@ -20,6 +20,13 @@ pub mod pointer;
 pub mod resource;
 pub mod dispatch;
 pub mod monitor;
+#[cfg(feature = "serde")]
+pub mod serialize;
+pub mod snapshot;
+pub mod subscribe;
+pub mod memory;
+pub mod compact;
+pub mod bundle;
 mod setup;
 
 pub use world::WorldInner;
@@ -34,10 +41,11 @@ pub mod prelude {
 			param::*,
 		},
 		setup::Setup,
-		monitor::{Listeners, Monitor, Event, ListenSetup, ComponentListen, ResourceListen, EntityListen, Create, Modify, Delete, EventType},
+		monitor::{Listeners, Monitor, Event, ListenSetup, ComponentListen, ResourceListen, EntityListen, WorldListen, Create, Modify, ModifyField, Delete, Migrate, Restore, EventType},
         world::{World, FromWorld},
 		dispatch::interface::*,
 		component::Component,
+		bundle::Bundle,
 		archetype::{ArchetypeId, Archetype},
 		entity::{Entities, Id, Entity},
 		storage::{LocalVersion, Offset},
@@ -0,0 +1,144 @@
+/// 内存统计
+/// 按原型 → 组件统计世界占用的字节数，用于在内存受限的设备上排查内存占用
+/// 容器按容量统计（包括已分配但未使用的空间），哈希表按桶数估算
+/// 组件、资源只统计值本身的大小，不包括值内部持有的堆内存（如`Vec`、`String`的内容）
+use std::{fmt, mem::size_of};
+
+use crate::{
+	archetype::ArchetypeId,
+	component::{ComponentId, StorageType},
+	query::filter::DirtyLists,
+	storage::{LocalVersion, Offset},
+	world::WorldInner,
+};
+
+/// 世界的内存报告
+#[derive(Debug, Clone)]
+pub struct MemoryReport {
+	pub archetypes: Vec<ArchetypeMemory>,
+	/// 各资源
+	pub resources: Vec<ComponentMemory>,
+	/// 资源元信息表
+	pub resource_index: usize,
+	/// 所有查询的脏列表（`DirtyLists`）
+	pub dirty_lists: usize,
+}
+
+/// 原型的内存统计
+#[derive(Debug, Clone)]
+pub struct ArchetypeMemory {
+	pub id: ArchetypeId,
+	/// 原型类型的名称
+	pub name: &'static str,
+	/// 实体槽位、实体监听器及待删除列表
+	pub entities: usize,
+	/// 组件容器表等索引
+	pub index: usize,
+	pub components: Vec<ComponentMemory>,
+}
+
+/// 组件容器（或资源）的内存统计
+#[derive(Debug, Clone)]
+pub struct ComponentMemory {
+	pub id: ComponentId,
+	/// 类型名称，取自`ComponentInfo::name`
+	pub name: &'static str,
+	pub storage_type: StorageType,
+	/// 存储、ticks及监听器占用的字节数
+	pub bytes: usize,
+}
+
+impl MemoryReport {
+	/// 总字节数
+	pub fn total(&self) -> usize {
+		self.archetypes.iter().map(ArchetypeMemory::total).sum::<usize>()
+			+ self.resources.iter().map(|r| r.bytes).sum::<usize>()
+			+ self.resource_index
+			+ self.dirty_lists
+	}
+
+	/// 按名称查找原型
+	pub fn archetype(&self, name: &str) -> Option<&ArchetypeMemory> {
+		self.archetypes.iter().find(|r| r.name == name)
+	}
+}
+
+impl ArchetypeMemory {
+	/// 原型的总字节数
+	pub fn total(&self) -> usize {
+		self.entities + self.index + self.components.iter().map(|r| r.bytes).sum::<usize>()
+	}
+
+	/// 按名称查找组件
+	pub fn component(&self, name: &str) -> Option<&ComponentMemory> {
+		self.components.iter().find(|r| r.name == name)
+	}
+}
+
+impl fmt::Display for MemoryReport {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		writeln!(f, "world: {} bytes", self.total())?;
+		for archetype in self.archetypes.iter() {
+			writeln!(
+				f,
+				"  {}({:?}): {} bytes (entities: {}, index: {})",
+				archetype.name, archetype.id, archetype.total(), archetype.entities, archetype.index
+			)?;
+			for component in archetype.components.iter() {
+				writeln!(f, "    {}({:?}): {} bytes", component.name, component.storage_type, component.bytes)?;
+			}
+		}
+		writeln!(
+			f,
+			"  resources: {} bytes (index: {})",
+			self.resources.iter().map(|r| r.bytes).sum::<usize>() + self.resource_index,
+			self.resource_index
+		)?;
+		for resource in self.resources.iter() {
+			writeln!(f, "    {}: {} bytes", resource.name, resource.bytes)?;
+		}
+		writeln!(f, "  dirty lists: {} bytes", self.dirty_lists)
+	}
+}
+
+impl WorldInner {
+	/// 统计世界占用的内存，返回原型 → 组件 → 字节数的报告
+	pub fn memory_report(&self) -> MemoryReport {
+		let archetypes = self.archetypes.iter().map(|archetype| {
+			let components = archetype.component_ids().iter().map(|id| {
+				let info = &self.components.infos[**id];
+				ComponentMemory {
+					id: *id,
+					name: info.name(),
+					storage_type: info.storage_type(),
+					bytes: unsafe { archetype.get_component(*id) }.mem_size(),
+				}
+			}).collect();
+			ArchetypeMemory {
+				id: archetype.id(),
+				name: archetype.name(),
+				entities: archetype.entities.mem_size() + archetype.delete_list.capacity() * size_of::<LocalVersion>(),
+				index: archetype.index_mem_size(),
+				components,
+			}
+		}).collect();
+
+		let (resources, resource_index) = self.archetypes.resources.mem_size();
+		let resources = resources.into_iter().map(|(id, bytes)| {
+			let info = &self.components.infos[id.offset()];
+			ComponentMemory {
+				id: info.id(),
+				name: info.name(),
+				storage_type: info.storage_type(),
+				bytes,
+			}
+		}).collect();
+
+		MemoryReport {
+			archetypes,
+			resources,
+			resource_index,
+			dirty_lists: self.get_resource::<DirtyLists>().map_or(0, DirtyLists::mem_size),
+		}
+	}
+}
@@ -1,17 +1,22 @@
 use pi_listener::{Listener as LibListener, Listeners as LibListeners};
 use pi_map::Map;
 use pi_ecs_macros::all_tuples;
-use pi_share::{cell::TrustCell, ThreadSync};
-use std::{ops::Deref, sync::Arc, marker::PhantomData};
+use pi_hash::{XHashMap, XHashSet};
+use pi_share::{cell::TrustCell, Share, ShareMutex, ThreadSync};
+use std::{cell::RefCell, ops::Deref, sync::{Arc, atomic::{AtomicUsize, Ordering}}, marker::PhantomData, mem::{discriminant, take, transmute, Discriminant}};
+use thiserror::Error;
 use crate::{
 	world::World, 
 	entity::Entity, 
-	component::Component,
+	component::{Component, MultiCaseImpl, FieldName},
+	storage::hash_mem_size,
 	sys::{system::{System, IntoSystem, SystemState, InputMarker, func_sys::{FunctionSystem, SystemParamFunction, SysInput}, runner::{ShareSystem, RunnerSystem, RunnerInner}}, 
 	param::{SystemParam, SystemParamFetch, SystemParamState, NotApply}}, archetype::{ArchetypeComponentId, ArchetypeIdent}, prelude::{FilteredAccessSet}};
 
 
 impl SysInput for Event {}
+impl SysInput for &[Event] {}
+impl<T> SysInput for Event<Old<T>> {}
 pub trait Listeners<P, ListenerType> {
 	fn listeners(&self) -> ListenerType;
 }
@@ -21,13 +26,45 @@ pub struct FunctionListeners<L: ListenInit, F, P> {
 	mark: PhantomData<( P, L)>,
 }
 
+/// 缓冲监听器，由输入为`&[Event]`的函数产生
+/// 事件先缓存在监听器中，在阶段的应用点（`World::apply_listeners`）批量投递
+pub struct BufferedListeners<L: ListenInit, F, P> {
+	f: F,
+	mark: PhantomData<( P, L)>,
+}
+
+/// 接收旧值的监听器，由输入为`Event<Old<..>>`的函数产生
+pub struct OldListeners<L: OldListenInit, F, P> {
+	f: F,
+	mark: PhantomData<( P, L)>,
+}
+
 pub struct ShareListener<L: ListenInit, P, R> {
 	v: R,
 	mark: PhantomData<( P, L)>,
 }
 
 pub trait ListenSetup {
-	fn setup(self, world: &mut World);
+	fn setup(self, world: &mut World) -> ListenerHandle;
+}
+
+/// 监听器句柄，由ListenSetup::setup返回，用于移除已安装的监听器
+/// 丢弃句柄不会移除监听器
+pub struct ListenerHandle {
+	listener: Listener,
+	remove: fn(&mut World, &Listener),
+}
+
+impl ListenerHandle {
+	fn new<L: ListenInit>(listener: Listener) -> Self {
+		ListenerHandle { listener, remove: L::remove }
+	}
+
+	/// 从世界中移除监听器，并释放监听器持有的系统
+	/// 同时移除监听器的数据访问，之后的依赖分析将不再考虑该监听器
+	pub fn remove(self, world: &mut World) {
+		(self.remove)(world, &self.listener);
+	}
 }
 
 pub trait Apply {
@@ -43,6 +80,68 @@ where
 	}
 }
 
+/// 缓冲监听器的事件队列，同一实体的同一类事件只保留第一个（字段、索引、迁移来源不参与去重）
+struct EventBuffer<S> {
+	events: TrustCell<Vec<Event>>,
+	set: TrustCell<XHashSet<(Entity, Discriminant<EventType>)>>,
+	sys: Share<TrustCell<S>>,
+}
+
+impl<S> EventBuffer<S> {
+	fn push(&self, e: Event) {
+		if self.set.borrow_mut().insert((e.id, discriminant(&e.ty))) {
+			self.events.borrow_mut().push(e);
+		}
+	}
+}
+
+impl<S> Apply for EventBuffer<S>
+where
+	S: System<In = &'static [Event], Out = ()>,
+{
+	fn apply(&self) {
+		let events = take(&mut *self.events.borrow_mut());
+		if events.is_empty() {
+			return;
+		}
+		self.set.borrow_mut().clear();
+
+		let mut sys = self.sys.borrow_mut();
+		// 系统的输入对生命周期是泛型的，切片不会在本次运行之后被持有
+		sys.run(unsafe { transmute::<&[Event], &'static [Event]>(events.as_slice()) });
+		sys.apply_buffers();
+	}
+}
+
+impl<L: ListenInit, P: SystemParam + 'static, F> ListenSetup for BufferedListeners<L, F, P>
+where
+	F: 
+		IntoSystem<P, FunctionSystem<&'static [Event], (), P, InputMarker, F>> +
+		SystemParamFunction<&'static [Event], (), P, InputMarker> + ThreadSync + 'static,
+	P::Fetch: NotApply
+{
+	fn setup(self, world: &mut World) -> ListenerHandle {
+		let sys = self.f.system(world);
+		let name = sys.name();
+
+		let sys = Share::new(TrustCell::new(sys));
+		world.add_system_tick(&sys);
+		let buffer = Share::new(EventBuffer {
+			events: TrustCell::new(Vec::new()),
+			set: TrustCell::new(XHashSet::default()),
+			sys,
+		});
+		world.add_apply(&buffer);
+		let listener = Listener::new(name, move |e: Event| {
+			buffer.push(e);
+		});
+		L::init(world, listener.clone());
+
+		// 缓冲监听器在应用点单独运行，不会让产生事件的系统依赖其数据访问
+		ListenerHandle::new::<L>(listener)
+	}
+}
+
 impl<L: ListenInit, P: SystemParam + 'static, F> ListenSetup for FunctionListeners<L, F, P>
 where
     // F: System<In=Event, Out=()>,
@@ -51,18 +150,48 @@ where
 		SystemParamFunction<Event, (), P, InputMarker> + ThreadSync + 'static,
 	P::Fetch: NotApply
 {
-	fn setup(self, world: &mut World) {
+	fn setup(self, world: &mut World) -> ListenerHandle {
 		let sys = self.f.system(world);
 
 		let access = sys.system_state.archetype_component_access.clone();
+		let name = sys.name();
+
+		let sys = Share::new(TrustCell::new(sys));
+		world.add_system_tick(&sys);
+		let listener = Listener::new(name, move |e: Event| {
+			run_listener(&sys, e);
+		});
+		L::init(world, listener.clone());
 
-		let sys = TrustCell::new(sys);
-		let listener = Listener(Arc::new(move |e: Event| {
-			sys.borrow_mut().run(e);
+		L::add_access(world, access, &listener);
+		ListenerHandle::new::<L>(listener)
+	}
+}
+
+impl<L: OldListenInit, P: SystemParam + 'static, F> ListenSetup for OldListeners<L, F, P>
+where
+	F: 
+		IntoSystem<P, FunctionSystem<Event<Old<L::Value<'static>>>, (), P, InputMarker, F>> +
+		SystemParamFunction<Event<Old<L::Value<'static>>>, (), P, InputMarker> + ThreadSync + 'static,
+	P::Fetch: NotApply
+{
+	fn setup(self, world: &mut World) -> ListenerHandle {
+		let sys = self.f.system(world);
+
+		let access = sys.system_state.archetype_component_access.clone();
+		let name = sys.name();
+		let sys = Share::new(TrustCell::new(sys));
+		world.add_system_tick(&sys);
+
+		// 旧值监听器不在通知列表中，token只用于标识监听器
+		let token = Listener::new(name, |_e: Event| {});
+		let listener = OldListener(token.clone(), Arc::new(move |e: Event<Old<&'static L::Component>>| {
+			run_listener(&sys, Event { id: e.id, ty: e.ty, field: e.field, index: e.index, old: Old(L::value(e.old.0)) });
 		}));
-		L::init(world, listener);
+		L::init_old(world, listener);
 
-		L::add_access(world, access);
+		L::add_access(world, access, &token);
+		ListenerHandle { listener: token, remove: L::remove_old }
 	}
 }
 
@@ -82,17 +211,20 @@ where S:
 	Monitor<L, Param = P>,
 	P::Fetch: NotApply
 	{
-	fn setup(self, world: &mut World) {
+	fn setup(self, world: &mut World) -> ListenerHandle {
 		let sys =  IntoSystem::<P, RunnerSystem<Event, (), P, InputMarker, ShareListener<L, P, ShareSystem<S>>>>::system(self, world);
 
 		let access = sys.system_state.archetype_component_access.clone();
-		let sys = TrustCell::new(sys);
-		let listener = Listener(Arc::new(move |e: Event| {
-			sys.borrow_mut().run(e);
-		}));
-		L::init(world, listener);
+		let name = sys.name();
+		let sys = Share::new(TrustCell::new(sys));
+		world.add_system_tick(&sys);
+		let listener = Listener::new(name, move |e: Event| {
+			run_listener(&sys, e);
+		});
+		L::init(world, listener.clone());
 
-		L::add_access(world, access);
+		L::add_access(world, access, &listener);
+		ListenerHandle::new::<L>(listener)
 	}
 }
 
@@ -109,10 +241,12 @@ where S:
 
 pub trait ListenInit: ThreadSync + 'static {
 	fn init(world: &mut World, listener: Listener);
-	fn add_access(world: &mut World, access: FilteredAccessSet<ArchetypeComponentId>);
+	fn add_access(world: &mut World, access: FilteredAccessSet<ArchetypeComponentId>, listener: &Listener);
+	/// 移除init安装的监听器及add_access添加的访问
+	fn remove(world: &mut World, listener: &Listener);
 }
 
-pub fn add_access(world: &mut World, access: FilteredAccessSet<ArchetypeComponentId>, a_c_id: ArchetypeComponentId) {
+pub fn add_access(world: &mut World, access: FilteredAccessSet<ArchetypeComponentId>, a_c_id: ArchetypeComponentId, listener: &Listener) {
 	let arr = world.listener_access.get_mut(&a_c_id);
 	let arr = match arr {
 		Some(r) => r,
@@ -121,7 +255,13 @@ pub fn add_access(world: &mut World, access: FilteredAccessSet<ArchetypeComponen
 			&mut world.listener_access[a_c_id]
 		}
 	};
-	arr.push(access);
+	arr.push((listener.clone(), access));
+}
+
+pub fn remove_access(world: &mut World, a_c_id: ArchetypeComponentId, listener: &Listener) {
+	if let Some(arr) = world.listener_access.get_mut(&a_c_id) {
+		arr.retain(|(l, _)| l != listener);
+	}
 }
 
 pub struct ComponentListen<A, C, T>(PhantomData<(A, C, T)>);
@@ -132,12 +272,63 @@ impl<A, C, T> ListenInit for ComponentListen<A, C, T> where
 	fn init(world: &mut World, listener: Listener) {
 		world.add_component_listener::<T, A, C>(listener);
 	}
-	fn add_access(world: &mut World, access: FilteredAccessSet<ArchetypeComponentId>) {
+	fn add_access(world: &mut World, access: FilteredAccessSet<ArchetypeComponentId>, listener: &Listener) {
+		let a_c_id = Self::archetype_component_id(world);
+		add_access(world, access, a_c_id, listener);
+	}
+	fn remove(world: &mut World, listener: &Listener) {
+		world.remove_component_listener::<T, A, C>(listener);
+		let a_c_id = Self::archetype_component_id(world);
+		remove_access(world, a_c_id, listener);
+	}
+}
+
+impl<A: ArchetypeIdent, C: Component, T> ComponentListen<A, C, T> {
+	fn archetype_component_id(world: &mut World) -> ArchetypeComponentId {
 		let arch_id = world.archetypes_mut().get_or_create_archetype::<A>();
 		let c_id = world.components.get_or_insert_id::<C>();
-		let a_c_id = unsafe{world.archetypes()[arch_id.clone()].archetype_component_id(c_id)};
+		unsafe{world.archetypes()[arch_id.clone()].archetype_component_id(c_id)}
+	}
+}
 
-		add_access(world, access, a_c_id);
+/// 可以接收旧值的监听，只支持单个组件的修改（`Old<&C>`）或删除（`Old<C>`）
+pub trait OldListenInit: ListenInit {
+	type Component: Component;
+	/// 监听器收到的旧值
+	type Value<'a>;
+	fn value(old: &Self::Component) -> Self::Value<'_>;
+	fn init_old(world: &mut World, listener: OldListener<Self::Component>);
+	fn remove_old(world: &mut World, token: &Listener);
+}
+
+impl<A: ArchetypeIdent, C: Component> OldListenInit for (ComponentListen<A, C, Modify>,) {
+	type Component = C;
+	type Value<'a> = &'a C;
+	fn value(old: &C) -> &C {
+		old
+	}
+	fn init_old(world: &mut World, listener: OldListener<C>) {
+		world.add_component_old_listener::<Modify, A, C>(listener);
+	}
+	fn remove_old(world: &mut World, token: &Listener) {
+		world.remove_component_old_listener::<Modify, A, C>(token);
+		<Self as ListenInit>::remove(world, token);
+	}
+}
+
+/// 每个删除监听器都收到旧值的一份克隆
+impl<A: ArchetypeIdent, C: Component + Clone> OldListenInit for (ComponentListen<A, C, Delete>,) {
+	type Component = C;
+	type Value<'a> = C;
+	fn value(old: &C) -> C {
+		old.clone()
+	}
+	fn init_old(world: &mut World, listener: OldListener<C>) {
+		world.add_component_old_listener::<Delete, A, C>(listener);
+	}
+	fn remove_old(world: &mut World, token: &Listener) {
+		world.remove_component_old_listener::<Delete, A, C>(token);
+		<Self as ListenInit>::remove(world, token);
 	}
 }
 
@@ -148,10 +339,15 @@ impl<R, T> ListenInit for ResourceListen<R, T> where
 	fn init(world: &mut World, listener: Listener) {
 		world.add_resource_listener::<T, R>(listener);
 	}
-	fn add_access(world: &mut World, access: FilteredAccessSet<ArchetypeComponentId>) {
+	fn add_access(world: &mut World, access: FilteredAccessSet<ArchetypeComponentId>, listener: &Listener) {
 		let a_c_id = world.archetypes().get_archetype_resource_id::<R>().unwrap().clone();
 
-		add_access(world, access, a_c_id);
+		add_access(world, access, a_c_id, listener);
+	}
+	fn remove(world: &mut World, listener: &Listener) {
+		world.remove_resource_listener::<T, R>(listener);
+		let a_c_id = *world.archetypes().get_archetype_resource_id::<R>().unwrap();
+		remove_access(world, a_c_id, listener);
 	}
 }
 
@@ -163,11 +359,33 @@ impl<A, T> ListenInit for EntityListen<A, T> where
 		world.add_entity_listener::<T, A>(listener);
 	}
 	
-	fn add_access(world: &mut World, access: FilteredAccessSet<ArchetypeComponentId>) {
+	fn add_access(world: &mut World, access: FilteredAccessSet<ArchetypeComponentId>, listener: &Listener) {
 		let arch_id = world.archetypes_mut().get_or_create_archetype::<A>();	
 		let a_c_id = world.archetypes()[arch_id.clone()].entity_archetype_component_id();
 
-		add_access(world, access, a_c_id);
+		add_access(world, access, a_c_id, listener);
+	}
+
+	fn remove(world: &mut World, listener: &Listener) {
+		world.remove_entity_listener::<T, A>(listener);
+		let arch_id = world.archetypes_mut().get_or_create_archetype::<A>();
+		let a_c_id = world.archetypes()[arch_id].entity_archetype_component_id();
+		remove_access(world, a_c_id, listener);
+	}
+}
+
+pub struct WorldListen<T>(PhantomData<T>);
+impl<T> ListenInit for WorldListen<T> where 
+	T: ListenType{
+	fn init(world: &mut World, listener: Listener) {
+		world.add_world_listener::<T>(listener);
+	}
+
+	// 世界事件不由system产生，不需要参与依赖分析
+	fn add_access(_world: &mut World, _access: FilteredAccessSet<ArchetypeComponentId>, _listener: &Listener) {}
+
+	fn remove(world: &mut World, listener: &Listener) {
+		world.remove_world_listener::<T>(listener);
 	}
 }
 
@@ -209,6 +427,7 @@ impl<'w, 's, T: ListenInit> SystemParamFetch<'w, 's> for ListenState<T> {
 
 pub trait ListenType: ThreadSync + 'static {
 	fn add(notify: &dyn Notify, listener: Listener);
+	fn remove(notify: &dyn Notify, listener: &Listener);
 }
 
 pub struct Create;
@@ -217,6 +436,9 @@ impl ListenType for Create {
 	fn add(notify: &dyn Notify, listener: Listener) {
 		notify.add_create(listener);
 	}
+	fn remove(notify: &dyn Notify, listener: &Listener) {
+		notify.remove_create(listener);
+	}
 }
 
 pub struct Delete;
@@ -225,6 +447,9 @@ impl ListenType for Delete {
 	fn add(notify: &dyn Notify, listener: Listener) {
 		notify.add_delete(listener);
 	}
+	fn remove(notify: &dyn Notify, listener: &Listener) {
+		notify.remove_delete(listener);
+	}
 }
 
 pub struct Modify;
@@ -233,32 +458,293 @@ impl ListenType for Modify {
 	fn add(notify: &dyn Notify, listener: Listener) {
 		notify.add_modify(listener);
 	}
+	fn remove(notify: &dyn Notify, listener: &Listener) {
+		notify.remove_modify(listener);
+	}
+}
+
+/// 指定字段的修改，只在修改事件携带该字段名时触发
+/// 字段标记类型`F`由`#[derive(ComponentFields)]`生成，如`ModifyField<color_fields::r>`
+pub struct ModifyField<F: FieldName>(PhantomData<F>);
+
+impl<F: FieldName> ListenType for ModifyField<F> {
+	fn add(notify: &dyn Notify, listener: Listener) {
+		notify.add_modify_field(F::NAME, listener);
+	}
+	fn remove(notify: &dyn Notify, listener: &Listener) {
+		notify.remove_modify_field(F::NAME, listener);
+	}
+}
+
+/// 可以携带旧值的事件类型
+pub trait OldListenType: ListenType {
+	fn old_listeners<C: Component>(container: &mut MultiCaseImpl<C>) -> &mut Vec<OldListener<C>>;
+}
+
+impl OldListenType for Modify {
+	fn old_listeners<C: Component>(container: &mut MultiCaseImpl<C>) -> &mut Vec<OldListener<C>> {
+		&mut container.modify_old
+	}
+}
+
+impl OldListenType for Delete {
+	fn old_listeners<C: Component>(container: &mut MultiCaseImpl<C>) -> &mut Vec<OldListener<C>> {
+		&mut container.delete_old
+	}
+}
+
+/// 实体迁移，只对实体监听有效（组件和资源不会产生迁移事件）
+pub struct Migrate;
+
+impl ListenType for Migrate {
+	fn add(notify: &dyn Notify, listener: Listener) {
+		notify.add_migrate(listener);
+	}
+	fn remove(notify: &dyn Notify, listener: &Listener) {
+		notify.remove_migrate(listener);
+	}
+}
+
+/// 世界被恢复（World::restore），只对世界监听有效
+pub struct Restore;
+
+impl ListenType for Restore {
+	fn add(notify: &dyn Notify, listener: Listener) {
+		notify.add_restore(listener);
+	}
+	fn remove(notify: &dyn Notify, listener: &Listener) {
+		notify.remove_restore(listener);
+	}
 }
 
 
 
-#[derive(Clone, Debug)]
-pub struct Event {
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Event<T = ()> {
     pub id: Entity,
 	pub ty: EventType,
     pub field: &'static str,
     pub index: usize, // 一般无意义。 只有在数组或向量的元素被修改时，才有意义
+	/// 旧值，只有`Event<Old<..>>`形式的监听器才有
+	pub old: T,
+}
+
+/// 组件被替换或删除前的值
+/// 修改监听器收到`Old<&C>`（只有通过insert、write替换组件时才会发出，原地修改没有旧值），删除监听器收到`Old<C>`
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Old<T>(pub T);
+
+impl<T> Deref for Old<T> {
+	type Target = T;
+
+	fn deref(&self) -> &T {
+		&self.0
+	}
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum EventType {
 	Create,
 	Modify,
 	Delete,
+	/// 实体从其它原型迁移而来，携带迁移前的实体，事件的id为迁移后的实体
+	Migrate(Entity),
+	/// 世界被恢复到某个快照，事件的id无意义
+	Restore,
 }
 
+/// 监听器，第二个字段为监听器的名称，级联跟踪时只增加其引用计数
 #[derive(Clone)]
-pub struct Listener(pub(crate) Arc<dyn Fn(Event)>);
+pub struct Listener(pub(crate) Arc<dyn Fn(Event)>, pub(crate) Arc<str>);
+
+impl Listener {
+	/// 创建监听器，name为监听器的名称（一般为监听系统的名称）
+	pub fn new<F: Fn(Event) + 'static>(name: impl Into<Arc<str>>, f: F) -> Self {
+		Listener(Arc::new(f), name.into())
+	}
+
+	pub fn name(&self) -> &str {
+		&self.1
+	}
+}
  
 unsafe impl Send for Listener {}
 unsafe impl Sync for Listener {}
 pub type ListenerList = LibListeners<Listener>;
 
+/// 接收旧值的监听器，第一个字段用于标识监听器
+pub struct OldListener<C: 'static>(pub(crate) Listener, pub(crate) Arc<dyn Fn(Event<Old<&'static C>>)>);
+
+impl<C: 'static> Clone for OldListener<C> {
+	fn clone(&self) -> Self {
+		OldListener(self.0.clone(), self.1.clone())
+	}
+}
+
+impl<C: 'static> OldListener<C> {
+	pub(crate) fn listen(&self, notify: &NotifyImpl, id: Entity, ty: EventType, old: &C) {
+		if let Some(_guard) = CascadeGuard::enter(notify, &self.0) {
+			// 监听函数对旧值的生命周期是泛型的，旧值不会在本次调用之后被持有
+			let old = unsafe { &*(old as *const C) };
+			(self.1)(Event { id, ty, field: "", index: 0, old: Old(old) });
+		}
+	}
+}
+
+/// 同一次安装产生的监听器（及其克隆）相等
+impl PartialEq for Listener {
+	fn eq(&self, other: &Self) -> bool {
+		Arc::as_ptr(&self.0) as *const () == Arc::as_ptr(&other.0) as *const ()
+	}
+}
+
+/// 监听器级联的默认最大深度
+pub const DEFAULT_MAX_LISTENER_DEPTH: usize = 256;
+
+/// 级联深度超过该值后，才检查级联中是否存在循环
+/// 循环的级联会不断加深，最终一定会超过该值；监听系统在自身的级联中被再次触发时，不论深度，立即报告循环
+pub const CYCLE_CHECK_DEPTH: usize = 16;
+
+/// 监听器级联的最大深度（监听器中修改数据，触发其它监听器，称为级联），及级联中发生的错误
+/// 每个世界一份，由世界中的事件源共享，通过`WorldInner::set_max_listener_depth`设置
+#[derive(Clone)]
+pub struct ListenerDepth(Arc<AtomicUsize>, Share<ShareMutex<Vec<ListenerError>>>);
+
+impl Default for ListenerDepth {
+	fn default() -> Self {
+		ListenerDepth(Arc::new(AtomicUsize::new(DEFAULT_MAX_LISTENER_DEPTH)), Share::new(ShareMutex::new(Vec::new())))
+	}
+}
+
+impl ListenerDepth {
+	pub(crate) fn get(&self) -> usize {
+		self.0.load(Ordering::Relaxed)
+	}
+
+	pub(crate) fn set(&self, depth: usize) {
+		self.0.store(depth, Ordering::Relaxed);
+	}
+
+	/// 记录级联错误
+	pub(crate) fn record(&self, err: ListenerError) {
+		self.1.lock().push(err);
+	}
+
+	/// 取出记录的级联错误
+	pub(crate) fn take_errors(&self) -> Vec<ListenerError> {
+		take(&mut *self.1.lock())
+	}
+}
+
+#[derive(Debug, Error)]
+pub enum ListenerError {
+	#[error("listener cascade depth exceeded {0}, chain: {1}")]
+	DepthExceeded(usize, String),
+	#[error("listener cascade cycle detected, chain: {0}")]
+	Cycle(String),
+}
+
+/// 级联中的一次监听器调用
+struct CascadeFrame {
+	notify: *const NotifyImpl1,
+	listener: *const (),
+	source: &'static str,
+	system: Arc<str>,
+	depth: Option<ListenerDepth>,
+}
+
+thread_local! {
+	static CASCADE: RefCell<Vec<CascadeFrame>> = const { RefCell::new(Vec::new()) };
+}
+
+/// 级联帧的守卫，离开作用域（包括panic展开）时弹出对应的帧
+struct CascadeGuard;
+
+impl Drop for CascadeGuard {
+	fn drop(&mut self) {
+		CASCADE.with(|c| {
+			c.borrow_mut().pop();
+		});
+	}
+}
+
+impl CascadeGuard {
+	/// 进入一次监听器调用，返回None表示级联出错，错误已记录到世界中，应跳过本次调用
+	/// 同一事件源和同一监听器在级联中再次出现，视为循环，深度超过CYCLE_CHECK_DEPTH后才检查
+	/// 最大深度取事件源所在世界的设置，未添加到世界的事件源取默认值
+	fn enter(notify: &NotifyImpl, listener: &Listener) -> Option<Self> {
+		let frame = CascadeFrame {
+			notify: notify.0.as_ptr() as *const NotifyImpl1,
+			listener: Arc::as_ptr(&listener.0) as *const (),
+			source: notify.name,
+			system: listener.1.clone(),
+			depth: notify.max_depth.clone(),
+		};
+		let max_depth = match &notify.max_depth {
+			Some(r) => r.get(),
+			None => DEFAULT_MAX_LISTENER_DEPTH,
+		};
+		let err = CASCADE.with(|c| {
+			let mut c = c.borrow_mut();
+			let cycle = c.len() >= CYCLE_CHECK_DEPTH
+				&& c.iter().any(|f| f.notify == frame.notify && f.listener == frame.listener);
+			c.push(frame);
+			if cycle {
+				Some(ListenerError::Cycle(cascade_chain(&c)))
+			} else if c.len() > max_depth {
+				Some(ListenerError::DepthExceeded(max_depth, cascade_chain(&c)))
+			} else {
+				None
+			}
+		});
+		let guard = CascadeGuard;
+		match err {
+			Some(err) => {
+				record_error(notify.max_depth.as_ref(), err);
+				None
+			},
+			None => Some(guard),
+		}
+	}
+}
+
+/// 记录级联错误，事件源未添加到世界时只输出日志
+fn record_error(depth: Option<&ListenerDepth>, err: ListenerError) {
+	match depth {
+		Some(r) => r.record(err),
+		None => log::warn!("{}", err),
+	}
+}
+
+/// 运行监听系统
+/// 系统正在运行时再次被触发，说明监听器在自身的级联中形成了循环，记录循环并跳过，而不是重复借用
+fn run_listener<S: System<Out = ()>>(sys: &TrustCell<S>, input: S::In) {
+	match sys.try_borrow_mut() {
+		Ok(mut r) => {
+			r.run(input);
+		},
+		Err(_) => CASCADE.with(|c| {
+			let c = c.borrow();
+			record_error(c.last().and_then(|f| f.depth.as_ref()), ListenerError::Cycle(cascade_chain(&c)));
+		}),
+	}
+}
+
+/// 级联链的描述，如：`component1 -> system1 -> component2 -> system2`
+fn cascade_chain(frames: &[CascadeFrame]) -> String {
+	frames.iter()
+		.map(|f| format!("{} -> {}", f.source, f.system))
+		.collect::<Vec<String>>()
+		.join(" -> ")
+}
+
+/// 从监听器列表中移除监听器，保持其余监听器的顺序
+fn remove_listener(list: &mut ListenerList, listener: &Listener) {
+	if let Some(index) = list.iter().position(|l| l == listener) {
+		list.remove(index);
+	}
+}
+
 impl LibListener<Event> for Listener {
 	fn listen(&self, e: &Event) {
 		let r = unsafe{&mut *(self as *const Listener as usize as *mut Listener)};
@@ -266,69 +752,124 @@ impl LibListener<Event> for Listener {
 	}
 }
 
+/// 事件源，持有各类监听器列表
+/// 监听器列表只在安装、移除监听器时修改，修改通过TrustCell的内部可变性进行，发出事件时不加锁
 #[derive(Default, Clone)]
-pub struct NotifyImpl(pub Arc<NotifyImpl1>);
+pub struct NotifyImpl(pub Arc<TrustCell<NotifyImpl1>>);
 
 impl Notify for NotifyImpl {
 	#[inline]
     fn add_create(&self, listener: Listener ) {
-        unsafe { &mut *(self.0.as_ref() as *const NotifyImpl1 as *mut NotifyImpl1) }
-            .create
-            .push(listener)
+        self.lists_mut().create.push(listener)
     }
 	#[inline]
     fn add_delete(&self, listener: Listener) {
-        unsafe { &mut *(self.0.as_ref() as *const NotifyImpl1 as *mut NotifyImpl1) }
-            .delete
-            .push(listener)
+        self.lists_mut().delete.push(listener)
     }
 	#[inline]
     fn add_modify(&self, listener: Listener) {
-        unsafe { &mut *(self.0.as_ref() as *const NotifyImpl1 as *mut NotifyImpl1) }
-            .modify
+        self.lists_mut().modify.push(listener)
+    }
+	#[inline]
+    fn add_migrate(&self, listener: Listener) {
+        self.lists_mut().migrate.push(listener)
+    }
+	#[inline]
+    fn add_restore(&self, listener: Listener) {
+        self.lists_mut().restore.push(listener)
+    }
+
+	#[inline]
+    fn add_modify_field(&self, field: &'static str, listener: Listener) {
+        self.lists_mut()
+            .modify_field
+            .entry(field)
+            .or_default()
             .push(listener)
     }
 
-    fn remove_create(&self, _listener: &Listener) {
-        // unsafe { &mut *(self.0.as_ref() as *const NotifyImpl1 as *mut NotifyImpl1) }
-        //     .create
-        //     .delete(listener);
+    fn remove_create(&self, listener: &Listener) {
+        remove_listener(&mut self.lists_mut().create, listener);
     }
-    fn remove_delete(&self, _listener: &Listener) {
-        // unsafe { &mut *(self.0.as_ref() as *const NotifyImpl1 as *mut NotifyImpl1) }
-        //     .delete
-        //     .delete(listener);
+    fn remove_delete(&self, listener: &Listener) {
+        remove_listener(&mut self.lists_mut().delete, listener);
     }
-    fn remove_modify(&self, _listener: &Listener) {
-        // unsafe { &mut *(self.0.as_ref() as *const NotifyImpl1 as *mut NotifyImpl1) }
-        //     .modify
-        //     .delete(listener);
+    fn remove_modify(&self, listener: &Listener) {
+        remove_listener(&mut self.lists_mut().modify, listener);
+    }
+    fn remove_migrate(&self, listener: &Listener) {
+        remove_listener(&mut self.lists_mut().migrate, listener);
+    }
+    fn remove_restore(&self, listener: &Listener) {
+        remove_listener(&mut self.lists_mut().restore, listener);
+    }
+    fn remove_modify_field(&self, field: &'static str, listener: &Listener) {
+        if let Some(list) = self.lists_mut().modify_field.get_mut(field) {
+            remove_listener(list, listener);
+        }
     }
 
 	fn create_event(&self, id: Entity) {
-        let e = Event { id, field: "", index:0, ty: EventType::Create };
-        self.create.listen(&e);
+        let e = Event { id, field: "", index:0, ty: EventType::Create, old: () };
+        self.listen(&self.create, &e);
     }
     fn delete_event(&self, id: Entity) {
-        let e = Event { id, field: "", index:0, ty: EventType::Delete };
-        self.delete.listen(&e);
+        let e = Event { id, field: "", index:0, ty: EventType::Delete, old: () };
+        self.listen(&self.delete, &e);
     }
     fn modify_event(&self, id: Entity, field: &'static str, index: usize) {
         let e = Event {
             id,
             field,
             index,
-			ty: EventType::Modify
+			ty: EventType::Modify,
+			old: (),
         };
-        self.modify.listen(&e);
+        self.listen(&self.modify, &e);
+        if let Some(list) = self.modify_field.get(field) {
+            self.listen(list, &e);
+        }
     }
+    fn migrate_event(&self, id: Entity, from: Entity) {
+        let e = Event { id, field: "", index:0, ty: EventType::Migrate(from), old: () };
+        self.listen(&self.migrate, &e);
+    }
+    fn restore_event(&self, id: Entity) {
+        let e = Event { id, field: "", index:0, ty: EventType::Restore, old: () };
+        self.listen(&self.restore, &e);
+    }
+}
+
+impl NotifyImpl {
+	/// 取到监听器列表的可变引用
+	/// 调用者需保证修改期间没有正在迭代同一列表的事件派发
+	#[allow(clippy::mut_from_ref)]
+	fn lists_mut(&self) -> &mut NotifyImpl1 {
+		unsafe { &mut *self.0.as_ptr() }
+	}
+
+	/// 设置事件源的名称（一般为archetype_component_info中的名称），以及所在世界的级联最大深度
+	pub(crate) fn set_source(&self, name: &'static str, max_depth: &ListenerDepth) {
+		let lists = self.lists_mut();
+		lists.name = name;
+		lists.max_depth = Some(max_depth.clone());
+	}
+
+	/// 依次调用监听器，并跟踪监听器的级联
+	fn listen(&self, list: &ListenerList, e: &Event) {
+		for l in list.iter() {
+			if let Some(_guard) = CascadeGuard::enter(self, l) {
+				l.listen(e);
+			}
+		}
+	}
 }
 
 impl Deref for NotifyImpl {
-    type Target = Arc<NotifyImpl1>;
+    type Target = NotifyImpl1;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        self.0.get()
     }
 }
 
@@ -337,12 +878,23 @@ pub struct NotifyImpl1 {
     pub create: ListenerList,
     pub delete: ListenerList,
     pub modify: ListenerList,
+    pub migrate: ListenerList,
+    pub restore: ListenerList,
+    /// 按字段名分组的修改监听器
+    pub modify_field: XHashMap<&'static str, ListenerList>,
+    /// 事件源的名称
+    pub name: &'static str,
+    /// 所在世界的级联最大深度及级联错误
+    pub(crate) max_depth: Option<ListenerDepth>,
 }
 
 impl NotifyImpl1 {
+    /// 监听器列表占用的字节数
     pub fn mem_size(&self) -> usize {
-        // self.create.mem_size() + self.delete.mem_size() + self.modify.mem_size()
-		0
+        self.create.mem_size() + self.delete.mem_size() + self.modify.mem_size()
+            + self.migrate.mem_size() + self.restore.mem_size()
+            + hash_mem_size::<(&'static str, ListenerList)>(self.modify_field.capacity())
+            + self.modify_field.values().map(|r| r.mem_size()).sum::<usize>()
     }
 }
 
@@ -350,12 +902,20 @@ pub trait Notify {
     fn add_create(&self, f: Listener);
     fn add_delete(&self, f: Listener);
     fn add_modify(&self, f: Listener);
+    fn add_migrate(&self, f: Listener);
+    fn add_restore(&self, f: Listener);
+    fn add_modify_field(&self, field: &'static str, f: Listener);
 	fn remove_create(&self, f: &Listener);
     fn remove_delete(&self, f: &Listener);
     fn remove_modify(&self, f: &Listener);
+    fn remove_migrate(&self, f: &Listener);
+    fn remove_restore(&self, f: &Listener);
+    fn remove_modify_field(&self, field: &'static str, f: &Listener);
     fn create_event(&self, id: Entity);
     fn delete_event(&self, id: Entity);
     fn modify_event(&self, id: Entity, field: &'static str, index: usize);
+    fn migrate_event(&self, id: Entity, from: Entity);
+    fn restore_event(&self, id: Entity);
 }
 
 /// 为元素满足ListenType的元组，实现ListenType（最多三个）
@@ -366,6 +926,9 @@ macro_rules! impl_event_function {
 			fn add(notify: &dyn Notify, listener: Listener) {
 				$($param::add(notify, listener.clone());)*
 			}
+			fn remove(notify: &dyn Notify, listener: &Listener) {
+				$($param::remove(notify, listener);)*
+			}
 		}
     };
 }
@@ -379,8 +942,12 @@ macro_rules! impl_event_init {
 				$($param::init(world, listener.clone());)*
 			}
 
-			fn add_access(world: &mut World, access: FilteredAccessSet<ArchetypeComponentId>) {
-				$($param::add_access(world, access.clone());)*
+			fn add_access(world: &mut World, access: FilteredAccessSet<ArchetypeComponentId>, listener: &Listener) {
+				$($param::add_access(world, access.clone(), listener);)*
+			}
+
+			fn remove(world: &mut World, listener: &Listener) {
+				$($param::remove(world, listener);)*
 			}
 		}
     };
@@ -446,6 +1013,34 @@ macro_rules! impl_event_setup {
 				// L::init(world, listener.clone());
 			}
 		}
+
+		#[allow(non_snake_case)]
+		impl<L: ListenInit, S, $($param: SystemParam + 'static),*> Listeners<(Listen<L>, $($param,)*), BufferedListeners<L, S, (Listen<L>, $($param,)*)>> for S 
+			where S: 
+				IntoSystem<(Listen<L>, $($param,)*), FunctionSystem<&'static [Event], (), (Listen<L>, $($param,)*), InputMarker, S>> +
+				SystemParamFunction<&'static [Event], (), (Listen<L>, $($param,)*), InputMarker> + ThreadSync + 'static +
+				for<'a> FnMut(&'a [Event], Listen<L>, $($param,)*) -> () +
+				Clone,
+				$($param::Fetch: NotApply),*
+				{
+			fn listeners(&self) -> BufferedListeners<L, S, (Listen<L>, $($param,)*)> {
+				BufferedListeners{f: self.clone(), mark: PhantomData}
+			}
+		}
+
+		#[allow(non_snake_case)]
+		impl<L: OldListenInit, S, $($param: SystemParam + 'static),*> Listeners<(Listen<L>, $($param,)*), OldListeners<L, S, (Listen<L>, $($param,)*)>> for S 
+			where S: 
+				IntoSystem<(Listen<L>, $($param,)*), FunctionSystem<Event<Old<L::Value<'static>>>, (), (Listen<L>, $($param,)*), InputMarker, S>> +
+				SystemParamFunction<Event<Old<L::Value<'static>>>, (), (Listen<L>, $($param,)*), InputMarker> + ThreadSync + 'static +
+				for<'a> FnMut(Event<Old<L::Value<'a>>>, Listen<L>, $($param,)*) -> () +
+				Clone,
+				$($param::Fetch: NotApply),*
+				{
+			fn listeners(&self) -> OldListeners<L, S, (Listen<L>, $($param,)*)> {
+				OldListeners{f: self.clone(), mark: PhantomData}
+			}
+		}
     };
 }
 
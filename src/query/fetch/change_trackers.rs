@@ -6,7 +6,7 @@ use std::{
 use pi_share::cell::TrustCell;
 
 use super::{
-	interface::{WorldQuery, ReadOnlyFetch, Fetch},
+	interface::{WorldQuery, ReadOnlyFetch, Fetch, require_ticks},
 	ref_ty::ReadState,
 };
 
@@ -14,6 +14,7 @@ use crate::{
 	archetype::{Archetype},
 	storage::LocalVersion,
 	component::{Component, ComponentTicks, MultiCaseImpl},
+	query::QueryError,
 	world::{World, WorldInner},
 };
 
@@ -74,6 +75,10 @@ impl<'s, T: Component> Fetch<'s> for ChangeTrackersFetch<T> {
 		self.change_tick = change_tick;
 	}
 
+	fn validate() -> Result<(), QueryError> {
+		require_ticks::<T>("ChangeTrackers")
+	}
+
     #[inline]
     unsafe fn set_archetype(
         &mut self,
@@ -0,0 +1,216 @@
+use std::{
+	marker::PhantomData,
+	ops::{Deref, DerefMut},
+	sync::Arc,
+};
+
+use pi_share::cell::TrustCell;
+
+use super::interface::{WorldQuery, ReadOnlyFetch, Fetch};
+use super::{ReadState, MutState};
+
+use crate::{
+	archetype::Archetype,
+	storage::{LocalVersion, Offset, Map, SoAMap, SoAField},
+	component::{Component, ComponentId, MultiCaseImpl, FieldName},
+	world::{World, WorldInner},
+};
+
+/// 查询SoA组件`T`中标记为`F`的字段，得到字段的只读引用
+pub struct Field<T, F: FieldName>(PhantomData<(T, F)>);
+
+/// 查询SoA组件`T`中标记为`F`的字段，得到字段的可变引用`FieldMutItem`
+pub struct FieldMut<T, F: FieldName>(PhantomData<(T, F)>);
+
+/// SoA组件字段的可变引用
+/// 可变借用过字段时，释放时更新组件的修改节拍，并发出字段`F`的修改事件（同时触发`Modify`和`ModifyField<F>`监听器）
+pub struct FieldMutItem<'s, T: Component + SoAField<F>, F: FieldName> {
+	value: &'s mut <T as SoAField<F>>::Type,
+	container: usize,
+	local: LocalVersion,
+	change_tick: u32,
+	changed: bool,
+}
+
+impl<'s, T: Component + SoAField<F>, F: FieldName> FieldMutItem<'s, T, F> {
+	/// 修改字段，释放时通知修改
+	#[inline]
+	pub fn write(&mut self, value: <T as SoAField<F>>::Type) {
+		*self.deref_mut() = value;
+	}
+}
+
+impl<'s, T: Component + SoAField<F>, F: FieldName> Deref for FieldMutItem<'s, T, F> {
+	type Target = <T as SoAField<F>>::Type;
+
+	#[inline]
+	fn deref(&self) -> &Self::Target {
+		self.value
+	}
+}
+
+impl<'s, T: Component + SoAField<F>, F: FieldName> DerefMut for FieldMutItem<'s, T, F> {
+	#[inline]
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		self.changed = true;
+		self.value
+	}
+}
+
+impl<'s, T: Component + SoAField<F>, F: FieldName> Drop for FieldMutItem<'s, T, F> {
+	fn drop(&mut self) {
+		if self.changed {
+			// 字段已写完，监听器可以读到新值
+			let c = unsafe { &mut *(self.container as *mut MultiCaseImpl<T>) };
+			c.notify_modify_field(self.local, self.change_tick, F::NAME, 0);
+		}
+	}
+}
+
+impl<T, F: FieldName> WorldQuery for Field<T, F>
+where
+	T: Component<Storage = SoAMap<LocalVersion, T>> + SoAField<F>,
+{
+    type Fetch = FieldFetch<T, F>;
+    type State = ReadState<T>;
+}
+
+impl<T, F: FieldName> WorldQuery for FieldMut<T, F>
+where
+	T: Component<Storage = SoAMap<LocalVersion, T>> + SoAField<F>,
+{
+    type Fetch = FieldMutFetch<T, F>;
+    type State = MutState<T>;
+}
+
+/// 取得原型中组件`T`的容器指针
+unsafe fn container<T: Component>(archetype: &Archetype, component_id: ComponentId) -> usize {
+	let c = archetype.get_component(component_id);
+	match c.clone().downcast() {
+		Ok(r) => {
+			let r: Arc<TrustCell<MultiCaseImpl<T>>> = r;
+			r.as_ptr() as usize
+		},
+		Err(_) => panic!("downcast fail")
+	}
+}
+
+pub struct FieldFetch<T, F: FieldName> {
+	container: usize,
+	mark: PhantomData<(T, F)>,
+}
+
+/// SAFE: access is read only
+unsafe impl<T, F: FieldName> ReadOnlyFetch for FieldFetch<T, F> {}
+
+impl<'s, T, F: FieldName> Fetch<'s> for FieldFetch<T, F>
+where
+	T: Component<Storage = SoAMap<LocalVersion, T>> + SoAField<F>,
+{
+    type Item = &'s <T as SoAField<F>>::Type;
+    type State = ReadState<T>;
+
+    unsafe fn init(
+        _world: &World,
+        _state: &Self::State
+    ) -> Self {
+        Self {
+			container: 0,
+			mark: PhantomData,
+        }
+    }
+
+    #[inline]
+    unsafe fn set_archetype(
+        &mut self,
+        state: &Self::State,
+        archetype: &Archetype,
+		_world: &World,
+    ) {
+		self.container = container::<T>(archetype, state.component_id);
+    }
+
+    #[inline]
+    unsafe fn archetype_fetch(&mut self, local: LocalVersion) -> Option<Self::Item> {
+		let storage = (&*(self.container as *const MultiCaseImpl<T>)).get_storage();
+		if storage.contains(&local) {
+			Some(&storage.column::<F>()[local.offset()])
+		} else {
+			None
+		}
+    }
+
+	#[inline]
+	unsafe fn archetype_fetch_unchecked(&mut self, local: LocalVersion) -> Self::Item {
+		let storage = (&*(self.container as *const MultiCaseImpl<T>)).get_storage();
+		storage.column::<F>().get_unchecked(local.offset())
+	}
+}
+
+pub struct FieldMutFetch<T, F: FieldName> {
+	container: usize,
+	change_tick: u32,
+	mark: PhantomData<(T, F)>,
+}
+
+impl<'s, T, F: FieldName> Fetch<'s> for FieldMutFetch<T, F>
+where
+	T: Component<Storage = SoAMap<LocalVersion, T>> + SoAField<F>,
+{
+    type Item = FieldMutItem<'s, T, F>;
+    type State = MutState<T>;
+
+    unsafe fn init(
+        _world: &World,
+        _state: &Self::State
+    ) -> Self {
+        Self {
+			container: 0,
+			change_tick: 0,
+			mark: PhantomData,
+        }
+    }
+
+	unsafe fn setting(&mut self, _world: &WorldInner, _last_change_tick: u32, change_tick: u32) {
+		self.change_tick = change_tick;
+	}
+
+    #[inline]
+    unsafe fn set_archetype(
+        &mut self,
+        state: &Self::State,
+        archetype: &Archetype,
+		_world: &World,
+    ) {
+		self.container = container::<T>(archetype, state.component_id);
+    }
+
+    #[inline]
+    unsafe fn archetype_fetch(&mut self, local: LocalVersion) -> Option<Self::Item> {
+		let storage = (&mut *(self.container as *mut MultiCaseImpl<T>)).get_storage_mut();
+		if storage.contains(&local) {
+			Some(self.item(&mut storage.column_mut::<F>()[local.offset()], local))
+		} else {
+			None
+		}
+    }
+
+	#[inline]
+	unsafe fn archetype_fetch_unchecked(&mut self, local: LocalVersion) -> Self::Item {
+		let storage = (&mut *(self.container as *mut MultiCaseImpl<T>)).get_storage_mut();
+		self.item(storage.column_mut::<F>().get_unchecked_mut(local.offset()), local)
+	}
+}
+
+impl<T: Component + SoAField<F>, F: FieldName> FieldMutFetch<T, F> {
+	#[inline]
+	fn item<'s>(&self, value: &'s mut <T as SoAField<F>>::Type, local: LocalVersion) -> FieldMutItem<'s, T, F> {
+		FieldMutItem {
+			value,
+			container: self.container,
+			local,
+			change_tick: self.change_tick,
+			changed: false,
+		}
+	}
+}
@@ -70,7 +70,7 @@ impl<'s, T: ArchetypeIdent> Fetch<'s> for IdFetch<T> {
 		_world: &World,
     ) {
 		self.archetype_id = archetype.id();
-		self.container = &archetype.entities as *const Entities as usize;
+		self.container = &*archetype.entities as *const Entities as usize;
     }
 
     #[inline]
@@ -5,8 +5,8 @@ use crate::{
 	world::{WorldInner, World},
 	archetype::{Archetype, ArchetypeId, ArchetypeComponentId, ArchetypeIdent},
 	storage::LocalVersion,
-	component::{ComponentId, Component},
-	query::access::FilteredAccess,
+	component::{ComponentId, Component, TrackTicks, is_whole_value},
+	query::{access::FilteredAccess, QueryError},
 };
 use pi_share::ThreadSync;
 
@@ -54,7 +54,47 @@ pub trait Fetch<'s>: Sized + ThreadSync + 'static {
 	unsafe fn main_fetch<'a>(&'a self, _state: &Self::State, _last_change_tick: u32, _change_tick: u32) -> Option<MianFetch<'a>> {
 		None
 	}
+
+	/// 拥有所查询组件的全部实体（只有组件紧凑存放时才能列出）
+	/// 查询迭代时，若该列表比原型的实体少，则由它驱动迭代
+	/// 返回列表本身而不是切片：迭代中组件可能被删除或插入，迭代器每次按索引重新读取列表
+	///
+	/// # Safety
+	/// Must always be called _after_ [Fetch::set_archetype]
+	unsafe fn dense_keys(&self) -> Option<&Vec<LocalVersion>> {
+		None
+	}
+
+	/// 检查该查询能否用于所查询的组件，在创建QueryState时调用
+	fn validate() -> Result<(), QueryError> {
+		Ok(())
+	}
 }
+
+/// 依赖节拍的查询调用，要求组件T记录ticks
+pub(crate) fn require_ticks<T: Component>(query: &'static str) -> Result<(), QueryError> {
+	if T::TRACK_TICKS {
+		Ok(())
+	} else {
+		Err(QueryError::NoTicks {
+			component: std::any::type_name::<T>(),
+			query,
+		})
+	}
+}
+
+/// 整体借用组件的查询调用，要求组件不是SoA存储
+pub(crate) fn require_whole_value<T: Component>(query: &'static str) -> Result<(), QueryError> {
+	if is_whole_value::<T>() {
+		Ok(())
+	} else {
+		Err(QueryError::SoA {
+			component: std::any::type_name::<T>(),
+			query,
+		})
+	}
+}
+
 /// State used to construct a Fetch. This will be cached inside QueryState, so it is best to move as
 /// much data / computation here as possible to reduce the cost of constructing Fetch.
 /// SAFETY:
@@ -113,6 +153,24 @@ macro_rules! impl_tuple_fetch {
 				}
 			}
 
+			fn validate() -> Result<(), QueryError> {
+				$($name::validate()?;)*
+				Ok(())
+			}
+
+			unsafe fn dense_keys(&self) -> Option<&Vec<LocalVersion>> {
+				let ($($name,)*) = self;
+				let r: Option<&Vec<LocalVersion>> = None;
+				$(
+					let r = match ($name.dense_keys(), r) {
+						(Some(keys), Some(min)) if keys.len() >= min.len() => Some(min),
+						(Some(keys), _) => Some(keys),
+						(None, r) => r,
+					};
+				)*
+				r
+			}
+
 			#[allow(unused_variables)]
 			#[inline]
 			unsafe fn setting(&mut self, world: &WorldInner, last_change_tick: u32, change_tick: u32) {
@@ -7,12 +7,13 @@ use crate::{
 	query::{
 		access::FilteredAccess,
 		filter::FilterFetch,
+		QueryError,
 	},
     storage::LocalVersion,
     world::World,
 };
 
-use super::interface::{WorldQuery, Fetch, FetchState, ReadOnlyFetch};
+use super::interface::{WorldQuery, Fetch, FetchState, ReadOnlyFetch, require_whole_value};
 
 use std::{
     marker::PhantomData,
@@ -61,6 +62,12 @@ impl<'s, C: Component + Deref<Target = Id<A>>, A: ArchetypeIdent, Q: Fetch<'s>,
         }
     }
 
+	fn validate() -> Result<(), QueryError> {
+		require_whole_value::<C>("Join")?;
+		Q::validate()?;
+		<F as Fetch<'s>>::validate()
+	}
+
     #[inline]
     unsafe fn set_archetype(
         &mut self,
@@ -8,6 +8,7 @@ mod option;
 mod or_default;
 mod id;
 mod join;
+mod field;
 
 pub use interface::*;
 pub use entity::*;
@@ -18,4 +19,5 @@ pub use ref_ty_mut::*;
 pub use write::*;
 pub use option::*;
 pub use or_default::*;
-pub use join::*;
\ No newline at end of file
+pub use join::*;
+pub use field::*;
\ No newline at end of file
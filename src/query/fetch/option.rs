@@ -3,7 +3,7 @@ use super::interface::{WorldQuery, FetchState, Fetch, ReadOnlyFetch};
 use crate::{
 	archetype::{Archetype, ArchetypeId, ArchetypeComponentId},
 	storage::LocalVersion,
-	query::access::FilteredAccess,
+	query::{access::FilteredAccess, QueryError},
 	world::World,
 };
 
@@ -60,6 +60,10 @@ impl<'s, T: Fetch<'s>> Fetch<'s> for OptionFetch<T> {
         }
     }
 
+	fn validate() -> Result<(), QueryError> {
+		T::validate()
+	}
+
     #[inline]
     unsafe fn set_archetype(
         &mut self,
@@ -3,7 +3,7 @@ use std::{
 };
 
 use super::{
-	interface::{WorldQuery, FetchState, Fetch, DefaultComponent, ReadOnlyFetch},
+	interface::{WorldQuery, FetchState, Fetch, DefaultComponent, ReadOnlyFetch, require_whole_value},
 	ref_ty::{ReadState, ReadFetch},
 };
 
@@ -11,7 +11,7 @@ use crate::{
 	archetype::{Archetype, ArchetypeId, ArchetypeComponentId},
 	storage::LocalVersion,
 	component::{ComponentId, Component},
-	query::access::FilteredAccess,
+	query::{access::FilteredAccess, QueryError},
 	world::World,
 };
 
@@ -70,6 +70,10 @@ impl<'s, T: Component + Default> Fetch<'s> for OrDefaultFetch<T> {
     type Item = &'s T;
     type State = OrDefaultState<T>;
 
+	fn validate() -> Result<(), QueryError> {
+		require_whole_value::<T>("OrDefault")
+	}
+
     unsafe fn init(
         world: &World,
         state: &Self::State
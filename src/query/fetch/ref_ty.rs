@@ -5,13 +5,13 @@ use std::{
 
 use pi_share::cell::TrustCell;
 
-use super::interface::{WorldQuery, ReadOnlyFetch, FetchState, Fetch};
+use super::interface::{WorldQuery, ReadOnlyFetch, FetchState, Fetch, require_whole_value};
 
 use crate::{
 	archetype::{Archetype, ArchetypeId, ArchetypeComponentId},
-	storage::LocalVersion,
+	storage::{LocalVersion, DenseKeys},
 	component::{ComponentId, Component, MultiCaseImpl},
-	query::access::FilteredAccess,
+	query::{access::FilteredAccess, QueryError},
 	world::World,
 };
 
@@ -65,6 +65,10 @@ impl<'s, T: Component> Fetch<'s> for ReadFetch<T> {
     type Item = &'s T;
     type State = ReadState<T>;
 
+	fn validate() -> Result<(), QueryError> {
+		require_whole_value::<T>("&T")
+	}
+
     unsafe fn init(
         _world: &World,
         _state: &Self::State
@@ -100,4 +104,11 @@ impl<'s, T: Component> Fetch<'s> for ReadFetch<T> {
 	unsafe fn archetype_fetch_unchecked(&mut self, local: LocalVersion) -> Self::Item {
 		std::mem::transmute((&mut *(self.container as *mut MultiCaseImpl<T>)).get_unchecked(local))
 	}
+
+	unsafe fn dense_keys(&self) -> Option<&Vec<LocalVersion>> {
+		if self.container == 0 {
+			return None;
+		}
+		(&*(self.container as *const MultiCaseImpl<T>)).get_storage().dense_keys()
+	}
 }
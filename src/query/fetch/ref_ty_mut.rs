@@ -5,13 +5,13 @@ use std::{
 
 use pi_share::cell::TrustCell;
 
-use super::interface::{WorldQuery, FetchState, Fetch};
+use super::interface::{WorldQuery, FetchState, Fetch, require_whole_value};
 
 use crate::{
 	archetype::{Archetype, ArchetypeId, ArchetypeComponentId},
-	storage::LocalVersion,
+	storage::{LocalVersion, DenseKeys},
 	component::{ComponentId, Component, MultiCaseImpl},
-	query::access::FilteredAccess,
+	query::{access::FilteredAccess, QueryError},
 	world::World,
 	pointer::Mut,
 };
@@ -29,6 +29,10 @@ impl<'s, T: Component> Fetch<'s> for MutFetch<T> {
     type Item = Mut<'s, T>;
     type State = MutState<T>;
 
+	fn validate() -> Result<(), QueryError> {
+		require_whole_value::<T>("&mut T")
+	}
+
     unsafe fn init(
         _world: &World,
         _state: &Self::State
@@ -74,10 +78,17 @@ impl<'s, T: Component> Fetch<'s> for MutFetch<T> {
 			value,
 		}
     }
+
+	unsafe fn dense_keys(&self) -> Option<&Vec<LocalVersion>> {
+		if self.container == 0 {
+			return None;
+		}
+		(&*(self.container as *const MultiCaseImpl<T>)).get_storage().dense_keys()
+	}
 }
 
 pub struct MutState<T> {
-    component_id: ComponentId,
+    pub(crate) component_id: ComponentId,
     marker: PhantomData<T>,
 }
 
@@ -6,13 +6,13 @@ use std::{
 
 use pi_share::cell::TrustCell;
 
-use super::{interface::{WorldQuery, FetchState, Fetch, DefaultComponent}, ChangeTrackers};
+use super::{interface::{WorldQuery, FetchState, Fetch, DefaultComponent, require_whole_value}, ChangeTrackers};
 
 use crate::{
 	archetype::{Archetype, ArchetypeId, ArchetypeComponentId},
 	storage::LocalVersion,
 	component::{ComponentId, Component, MultiCaseImpl, ComponentTicks},
-	query::access::FilteredAccess,
+	query::{access::FilteredAccess, QueryError},
 	world::{World, WorldInner}, resource::ResourceId,
 };
 
@@ -51,6 +51,12 @@ impl<'s, T: Component> WriteItem<'s, T> {
 		c.notify_modify(self.local, self.ticker.change_tick);
 	}
 
+	/// 通知字段修改，index为数组或向量字段中被修改元素的索引，其它字段为0
+	pub fn notify_modify_field(&mut self, field: &'static str, index: usize) {
+		let c = unsafe{&mut *(self.container as *mut MultiCaseImpl<T>)};
+		c.notify_modify_field(self.local, self.ticker.change_tick, field, index);
+	}
+
 	pub fn notify_delete(&mut self) {
 		let c = unsafe{&mut *(self.container as *mut MultiCaseImpl<T>)};
 		c.notify_delete(self.local, self.ticker.change_tick);
@@ -138,6 +144,10 @@ impl<'s, T: Component> Fetch<'s> for WriteFetch<T> {
     type Item = WriteItem<'s, T>;
     type State = WriteState<T>;
 
+	fn validate() -> Result<(), QueryError> {
+		require_whole_value::<T>("Write")
+	}
+
     unsafe fn init(
         world: &World,
         state: &Self::State
@@ -10,10 +10,11 @@ use crate::{
     component::{Component, ComponentId, MultiCaseImpl},
     query::{
 		access::FilteredAccess,
-		fetch::{Fetch, FetchState, WorldQuery, MianFetch},
+		fetch::{Fetch, FetchState, WorldQuery, MianFetch, interface::require_ticks},
 		filter::FilterFetch,
+		QueryError,
 	},
-    storage::{SecondaryMap, Local, LocalVersion},
+    storage::{SecondaryMap, Local, LocalVersion, Shrink},
     world::{World, WorldInner},
 };
 use std::{marker::PhantomData};
@@ -29,6 +30,22 @@ pub struct DirtyList {
 	pub(crate) value: SecondaryMap<LocalVersion,()>,
 }
 
+impl DirtyLists {
+	/// 所有查询的脏列表占用的字节数
+	pub fn mem_size(&self) -> usize {
+		self.list.mem_size() + self.list.values().map(|r| r.init_list.mem_size() + r.value.mem_size()).sum::<usize>()
+	}
+
+	/// 释放所有脏列表中未使用的容量
+	pub fn shrink(&mut self) {
+		for list in self.list.values_mut() {
+			list.init_list.shrink();
+			list.value.shrink();
+		}
+		self.list.shrink();
+	}
+}
+
 impl Default for DirtyLists {
     fn default() -> Self {
 		DirtyLists { 
@@ -40,7 +57,7 @@ impl Default for DirtyLists {
 macro_rules! impl_tick_filter {
     (
         $(#[$meta:meta])*
-        $name: ident, $state_name: ident, $fetch_name: ident, $is_detected: expr, $listen: ty) => {
+        $name: ident, $state_name: ident, $fetch_name: ident, $is_detected: expr, $listen: ty, $use_ticks: expr) => {
         $(#[$meta])*
         pub struct $name<T>(PhantomData<T>);
 
@@ -170,6 +187,14 @@ macro_rules! impl_tick_filter {
                 value
             }
 
+			fn validate() -> Result<(), QueryError> {
+				if $use_ticks {
+					require_ticks::<T>(stringify!($name))
+				} else {
+					Ok(())
+				}
+			}
+
 			unsafe fn setting(&mut self, _world: &WorldInner, last_change_tick: u32, change_tick: u32) {
 				self.last_change_tick = last_change_tick;
 				self.change_tick = change_tick;
@@ -200,40 +225,40 @@ macro_rules! impl_tick_filter {
             }
 
             unsafe fn archetype_fetch(&mut self, archetype_index: LocalVersion) -> Option<bool> {
-				let value = (& *(self.container as *mut MultiCaseImpl<T>)).tick(archetype_index);
-				match value {
-					Some(r) => Some(r.is_changed(self.last_change_tick, self.change_tick) || r.is_added(self.last_change_tick, self.change_tick)),
-					None => None,
-				}
+				$is_detected(& *(self.container as *mut MultiCaseImpl<T>), archetype_index, self.last_change_tick, self.change_tick)
             }
 
 			unsafe fn archetype_fetch_unchecked(&mut self, archetype_index: LocalVersion) -> bool {
-				let value = (& *(self.container as *mut MultiCaseImpl<T>)).tick(archetype_index);
-				match value {
-					Some(r) => r.is_changed(self.last_change_tick, self.change_tick) || r.is_added(self.last_change_tick, self.change_tick),
-					None => false,
-				}
+				$is_detected(& *(self.container as *mut MultiCaseImpl<T>), archetype_index, self.last_change_tick, self.change_tick).unwrap_or(false)
             }
         }
 
 		impl<T: Component> FilterFetch for $fetch_name<T> {
 			unsafe fn archetype_filter_fetch(&mut self, local: LocalVersion) -> bool {
-				let value = (& *(self.container as *mut MultiCaseImpl<T>)).tick(local);
-				match value {
-					Some(r) => r.is_changed(self.last_change_tick, self.change_tick) || r.is_added(self.last_change_tick, self.change_tick),
-					None => false,
-				}
+				$is_detected(& *(self.container as *mut MultiCaseImpl<T>), local, self.last_change_tick, self.change_tick).unwrap_or(false)
 			}
 		}
     };
 }
 
+/// 组件是否新增或修改，组件不存在时返回None
+fn is_changed<T: Component>(container: &MultiCaseImpl<T>, local: LocalVersion, last_change_tick: u32, change_tick: u32) -> Option<bool> {
+	container.tick(local).map(|r| r.is_changed(last_change_tick, change_tick) || r.is_added(last_change_tick, change_tick))
+}
+
+/// 组件是否已被删除
+/// 脏列表只记录发生过删除事件的实体，删除后又重新插入了组件的实体不算被删除
+fn is_deleted<T: Component>(container: &MultiCaseImpl<T>, local: LocalVersion, _last_change_tick: u32, _change_tick: u32) -> Option<bool> {
+	Some(!container.contains_key(&local))
+}
+
 impl_tick_filter!(
     Changed,
     ChangedState,
     ChangedFetch,
-    ComponentTicks::is_changed,
-	(Create, Modify)
+    is_changed,
+	(Create, Modify),
+	true
 
 );
 
@@ -241,22 +266,28 @@ impl_tick_filter!(
     Added,
     AddedState,
     AddedFetch,
-    ComponentTicks::is_added,
-	Create
+    is_changed,
+	Create,
+	true
 );
 
 impl_tick_filter!(
     Modifyed,
     ModifyedState,
     ModifyedFetch,
-    ComponentTicks::is_modifyed,
-	Modify
+    is_changed,
+	Modify,
+	true
 );
 
 impl_tick_filter!(
+	/// 自上次运行以来，被删除了组件T的实体（组件被移除或实体被销毁）
+	/// 被销毁的实体无法再通过查询取到，如需得到被销毁实体的id，使用RemovedComponents
     Deleted,
     DeletedState,
     DeletedFetch,
-    ComponentTicks::is_deleted,
-	Delete
+    is_deleted,
+	Delete,
+	// 删除过滤器只检查组件是否存在，不读取节拍
+	false
 );
@@ -10,8 +10,9 @@ use crate::{
     component::{Component, ComponentId, MultiCaseImpl},
     query::{
 		access::FilteredAccess,
-		fetch::{Fetch, FetchState, WorldQuery, MianFetch},
+		fetch::{Fetch, FetchState, WorldQuery, MianFetch, interface::require_ticks},
 		filter::FilterFetch,
+		QueryError,
 	},
     storage::{SecondaryMap, Local, LocalVersion},
 	sys::param::{ResMut, Tick},
@@ -130,6 +131,10 @@ macro_rules! impl_tick_filter {
                 value
             }
 
+			fn validate() -> Result<(), QueryError> {
+				require_ticks::<T>(stringify!($name))
+			}
+
 			unsafe fn setting(&mut self, _world: &WorldInner, last_change_tick: u32, change_tick: u32) {
 				self.last_change_tick = last_change_tick;
 				self.change_tick = change_tick;
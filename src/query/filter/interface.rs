@@ -5,6 +5,7 @@ use crate::{
 	query::{
 		fetch::interface::{Fetch, WorldQuery, MianFetch, FetchState},
 		access::FilteredAccess,
+		QueryError,
 	},
 	archetype::{Archetype, ArchetypeId, ArchetypeComponentId, ArchetypeIdent},
 	storage::LocalVersion,
@@ -61,6 +62,11 @@ macro_rules! impl_query_filter_tuple {
                 },)*))
             }
 
+			fn validate() -> Result<(), QueryError> {
+				$(<$filter as Fetch<'s>>::validate()?;)*
+				Ok(())
+			}
+
 			unsafe fn setting(&mut self, world: &WorldInner, last_change_tick: u32, change_tick: u32) {
 				let ($($filter,)*) = &mut self.0;
 				$(
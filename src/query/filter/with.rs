@@ -83,6 +83,10 @@ impl<'s, T: Component> Fetch<'s> for WithFetch<T> {
     unsafe fn archetype_fetch_unchecked(&mut self, _local: LocalVersion) -> Self::Item {
         true
     }
+
+	unsafe fn dense_keys(&self) -> Option<&Vec<LocalVersion>> {
+		self.read_fetch.dense_keys()
+	}
 }
 
 impl<T: Component> FilterFetch for WithFetch<T> {
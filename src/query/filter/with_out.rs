@@ -43,7 +43,10 @@ unsafe impl<T: Component> FetchState for WithOutState<T> {
 
     #[inline]
     fn update_archetype_component_access(&self, archetype: &Archetype, access: &mut FilteredAccess<ArchetypeComponentId>) {
-		self.read_state.update_archetype_component_access(archetype, access);
+		// 动态原型可能不包含该组件，此时没有需要读取的数据
+		if archetype.contains(self.read_state.component_id) {
+			self.read_state.update_archetype_component_access(archetype, access);
+		}
     }
 	
     fn matches_archetype(&self, _archetype: &Archetype,) -> bool {
@@ -27,6 +27,28 @@ impl<'a> EntityIter<'a> {
 	}
 }
 
+/// 由紧凑存放的组件的实体列表驱动的迭代
+/// 迭代中组件可能被删除（列表交换删除）或插入（列表扩容），因此持有列表本身，每次按索引重新读取
+struct DenseIter {
+	keys: usize,
+	index: usize,
+	last: LocalVersion,
+}
+
+impl DenseIter {
+	unsafe fn next(&mut self) -> Option<LocalVersion> {
+		let keys = &*(self.keys as *const Vec<LocalVersion>);
+		// 上次取出的实体已被删除，其位置换成了列表末尾尚未迭代的实体，重新迭代该位置
+		if self.index > 0 && keys.get(self.index - 1) != Some(&self.last) {
+			self.index -= 1;
+		}
+		let r = *keys.get(self.index)?;
+		self.index += 1;
+		self.last = r;
+		Some(r)
+	}
+}
+
 pub struct QueryIter<'w, 's,  A: ArchetypeIdent, Q: WorldQuery, F: WorldQuery>
 where
     F::Fetch: FilterFetch,
@@ -37,6 +59,8 @@ where
     fetch: &'s mut Q::Fetch,
     filter:&'s mut F::Fetch,
 	entities_iter: Option<EntityIter<'s>>,
+	/// 紧凑存放的组件的实体列表，比原型的实体少时，由它驱动迭代
+	dense_iter: Option<DenseIter>,
 	all_entities_iter: pi_slotmap::dense::Keys<'s, LocalVersion, ()>,
 	mark: PhantomData<A>,
 }
@@ -91,7 +115,16 @@ where
 			},
 			None => None,
 		};
-		let all_entities = std::mem::transmute(world.archetypes()[query_state.archetype_id].entities.keys());
+		let entities = &world.archetypes()[query_state.archetype_id].entities;
+		let dense_iter = match (&iter, fetch.dense_keys(), filter.dense_keys()) {
+			(Some(_), _, _) => None,
+			(None, Some(a), Some(b)) => Some(if a.len() <= b.len() { a } else { b }),
+			(None, a, b) => a.or(b),
+		};
+		let dense_iter = dense_iter
+			.filter(|keys| keys.len() < entities.len())
+			.map(|keys| DenseIter { keys: keys as *const Vec<LocalVersion> as usize, index: 0, last: LocalVersion::default() });
+		let all_entities = std::mem::transmute(entities.keys());
 		
 		#[allow(mutable_transmutes)]
         QueryIter {
@@ -101,6 +134,7 @@ where
             fetch: std::mem::transmute(fetch),
             filter: std::mem::transmute(filter),
 			entities_iter: iter,
+			dense_iter,
 			archetype_id: query_state.archetype_id,
 			all_entities_iter: all_entities,
             mark: PhantomData,
@@ -135,6 +169,20 @@ where
 					}
 					return item;
 				}
+			} else if let Some(iter) = &mut self.dense_iter {
+				loop {
+					let entity = iter.next()?;
+
+					if !self.filter.archetype_filter_fetch(entity) {
+						continue;
+					}
+
+					let item = self.fetch.archetype_fetch(entity);
+					if item.is_none() {
+						continue;
+					}
+					return item;
+				}
 			} else {
 				loop {
 					let entity = self.all_entities_iter.next()?;
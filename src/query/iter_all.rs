@@ -0,0 +1,76 @@
+use crate::{
+    query::{Fetch, FilterFetch, WorldQuery, state_all::MatchedArchetype},
+    storage::LocalVersion,
+    world::WorldInner,
+};
+
+/// QueryAllState的迭代器，依次迭代每个已匹配原型中的所有实体
+pub struct QueryAllIter<'w, 's, Q: WorldQuery, F: WorldQuery>
+where
+    F::Fetch: FilterFetch,
+{
+    world: &'w WorldInner,
+	matched_archetypes: &'s mut [MatchedArchetype<Q, F>],
+	// 当前迭代的原型在matched_archetypes中的位置
+	cursor: usize,
+	entities_iter: Option<pi_slotmap::delay::Keys<'s, LocalVersion, ()>>,
+}
+
+impl<'w, 's, Q: WorldQuery, F: WorldQuery> QueryAllIter<'w, 's, Q, F>
+where
+    F::Fetch: FilterFetch,
+{
+    pub(crate) unsafe fn new(
+        world: &'w WorldInner,
+        matched_archetypes: &'s mut [MatchedArchetype<Q, F>],
+    ) -> Self {
+        QueryAllIter {
+            world,
+			matched_archetypes,
+			cursor: 0,
+			entities_iter: None,
+        }
+    }
+}
+
+impl<'w, 's, Q: WorldQuery, F: WorldQuery> Iterator for QueryAllIter<'w, 's, Q, F>
+where
+    F::Fetch: FilterFetch,
+{
+    type Item = <Q::Fetch as Fetch<'w>>::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        unsafe {
+			loop {
+				let entity = match self.entities_iter.as_mut().and_then(|iter| iter.next()) {
+					Some(r) => r,
+					None => {
+						// 当前原型迭代完毕，切换到下一个原型
+						if self.entities_iter.is_some() {
+							self.cursor += 1;
+						}
+						let matched = self.matched_archetypes.get(self.cursor)?;
+						let world: &'s WorldInner = &*(self.world as *const WorldInner);
+						self.entities_iter = Some(world.archetypes()[matched.archetype_id].entities.keys());
+						continue;
+					}
+				};
+
+				let matched = &mut self.matched_archetypes[self.cursor];
+				if !matched.filter.archetype_filter_fetch(entity) {
+					continue;
+				}
+
+				if let Some(item) = matched.fetch.archetype_fetch(entity) {
+					return Some(item);
+				}
+			}
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+		let max_size = self.matched_archetypes.iter().map(|r| self.world.archetypes[r.archetype_id].len()).sum();
+        (0, Some(max_size))
+    }
+}
@@ -2,14 +2,18 @@ mod access;
 pub mod fetch;
 pub mod filter;
 mod iter;
+mod iter_all;
 mod state;
+mod state_all;
 // pub mod filter_change1;
 
 pub use access::*;
 pub use fetch::*;
 pub use filter::*;
 pub use iter::*;
+pub use iter_all::*;
 pub use state::*;
+pub use state_all::QueryAllState;
 // pub use fetch1::*;
 
 // #[cfg(test)]
@@ -55,7 +55,19 @@ impl<A: ArchetypeIdent, Q: WorldQuery, F: WorldQuery> QueryState<A, Q, F>
 where
     F::Fetch: FilterFetch,
 {
+    /// 创建查询状态，查询不能用于所查询的组件时panic（见[QueryError]）
     pub fn new(world:  &mut World) -> Self {
+		match Self::try_new(world) {
+			Ok(r) => r,
+			Err(e) => panic!("{}: {}", std::any::type_name::<Self>(), e),
+		}
+    }
+
+	/// 创建查询状态，查询不能用于所查询的组件时返回错误
+	pub fn try_new(world:  &mut World) -> Result<Self, QueryError> {
+		<Q::Fetch as Fetch>::validate()?;
+		<F::Fetch as Fetch>::validate()?;
+
 		let archetype_id = world.archetypes_mut().get_or_create_archetype::<A>();
 		let q_id = world.gen_query_id();
 
@@ -85,7 +97,7 @@ where
 			mark: PhantomData
         };
 		state.validate_world_and_update_archetypes(world);
-        state
+        Ok(state)
     }
 
 	pub fn archetype_id(&self) -> ArchetypeId {
@@ -584,6 +596,21 @@ where
 	}
 }
 
+/// 创建查询时的错误
+#[derive(Error, Debug)]
+pub enum QueryError {
+    #[error("{query} requires change ticks, but component {component} opted out of change tracking with #[component(no_ticks)]")]
+    NoTicks {
+        component: &'static str,
+        query: &'static str,
+    },
+    #[error("{query} borrows component {component} as a whole, but it uses SoA storage; query its fields with Field or FieldMut")]
+    SoA {
+        component: &'static str,
+        query: &'static str,
+    },
+}
+
 /// An error that occurs when retrieving a specific [Entity]'s query result.
 #[derive(Error, Debug)]
 pub enum QueryEntityError {
@@ -0,0 +1,197 @@
+use crate::{
+    archetype::{ArchetypeId, ArchetypeComponentId, ArchetypeGeneration},
+    entity::Entity,
+    query::{
+        Fetch, FetchState, FilterFetch, FilteredAccess, QueryAllIter, ReadOnlyFetch,
+        WorldQuery
+    },
+	storage::{Key, Offset},
+    world::{World, WorldId, WorldInner},
+};
+use pi_share::cell::TrustCell;
+
+/// 一个已匹配的原型，及为该原型设置好的fetch
+pub(crate) struct MatchedArchetype<Q: WorldQuery, F: WorldQuery> {
+	pub(crate) archetype_id: ArchetypeId,
+	pub(crate) fetch: Q::Fetch,
+	pub(crate) filter: F::Fetch,
+}
+
+/// 不绑定原型的查询状态
+/// 与QueryState不同，QueryAllState会匹配所有组件满足fetch和filter的原型（包括动态原型）
+/// 新的原型或原型上新注册的组件，需要调用update_archetypes才能被匹配（原型世代未变化时，不会重新检查）
+/// 注意：该查询不使用脏列表（脏列表中的实体在不同原型中会冲突），变化过滤器通过ticks逐个检查实体
+pub struct QueryAllState<Q: WorldQuery, F: WorldQuery = ()>
+where
+    F::Fetch: FilterFetch,
+{
+    world_id: WorldId,
+    pub(crate) archetype_component_access: FilteredAccess<ArchetypeComponentId>,
+    pub(crate) fetch_state: Q::State,
+    pub(crate) filter_state: F::State,
+
+	pub(crate) archetype_generation: ArchetypeGeneration,
+	// 迭代时需要可变地使用每个原型的fetch，而迭代只持有状态的共享引用
+	pub(crate) matched_archetypes: TrustCell<Vec<MatchedArchetype<Q, F>>>,
+	// 每个原型的检查状态，None表示已匹配，Some(n)表示上次检查时该原型有n个组件，且不匹配
+	archetype_checked: Vec<Option<usize>>,
+}
+
+impl<Q: WorldQuery, F: WorldQuery> QueryAllState<Q, F>
+where
+    F::Fetch: FilterFetch,
+{
+    pub fn new(world: &mut World) -> Self {
+		if let Err(e) = <Q::Fetch as Fetch>::validate().and_then(|_| <F::Fetch as Fetch>::validate()) {
+			panic!("{}: {}", std::any::type_name::<Self>(), e);
+		}
+		let q_id = world.gen_query_id();
+
+        let fetch_state = <Q::State as FetchState>::init(world, q_id, ArchetypeId::null());
+        let filter_state = <F::State as FetchState>::init(world, q_id, ArchetypeId::null());
+
+        let mut state = Self {
+            world_id: world.id(),
+            fetch_state,
+            filter_state,
+            archetype_component_access: Default::default(),
+			archetype_generation: ArchetypeGeneration::new(usize::MAX),
+			matched_archetypes: TrustCell::new(Vec::new()),
+			archetype_checked: Vec::new(),
+        };
+		state.update_archetypes(world);
+        state
+    }
+
+	/// 检查新增的原型，以及上次检查后注册了新组件的原型，将匹配的原型加入匹配列表
+	pub fn update_archetypes(&mut self, world: &World) {
+        if world.id() != self.world_id {
+            panic!("Attempted to use {} with a mismatched WorldInner. QueryStates can only be used with the WorldInner they were created from.",
+                std::any::type_name::<Self>());
+        }
+		let archetypes = world.archetypes();
+		if self.archetype_generation == archetypes.generation() {
+			return;
+		}
+		self.archetype_generation = archetypes.generation();
+		// 新原型尚未检查，用一个不可能的组件数量标记
+		self.archetype_checked.resize(archetypes.len(), Some(usize::MAX));
+		for archetype in archetypes.iter() {
+			let checked = &mut self.archetype_checked[archetype.id().offset()];
+			// 已匹配（组件只增不减，匹配后不会再变为不匹配），或组件数量未变化，不需要重新检查
+			match checked {
+				None => continue,
+				Some(len) if *len == archetype.component_ids().len() => continue,
+				_ => (),
+			}
+			if !(self.fetch_state.matches_archetype(archetype) && self.filter_state.matches_archetype(archetype)) {
+				*checked = Some(archetype.component_ids().len());
+				continue;
+			}
+			*checked = None;
+
+			// 加入实体读
+			self.archetype_component_access.add_read(archetype.entity_archetype_component_id());
+			self.fetch_state.update_archetype_component_access(archetype, &mut self.archetype_component_access);
+			self.filter_state.update_archetype_component_access(archetype, &mut self.archetype_component_access);
+
+			let mut fetch = unsafe{ <Q::Fetch as Fetch>::init(world, &self.fetch_state) };
+			let mut filter = unsafe{ <F::Fetch as Fetch>::init(world, &self.filter_state) };
+			unsafe{ fetch.set_archetype(&self.fetch_state, archetype, world)};
+			unsafe{ filter.set_archetype(&self.filter_state, archetype, world)};
+			self.matched_archetypes.get_mut().push(MatchedArchetype {
+				archetype_id: archetype.id(),
+				fetch,
+				filter,
+			});
+		}
+	}
+
+	/// 已匹配的原型
+	pub fn matched_archetypes(&self) -> impl Iterator<Item = ArchetypeId> + '_ {
+		self.matched_archetypes.get().iter().map(|r| r.archetype_id)
+	}
+
+	pub fn setting(
+        &mut self,
+        world: &WorldInner,
+        last_change_tick: u32,
+        change_tick: u32,
+    ) {
+        unsafe {
+			for r in self.matched_archetypes.get_mut().iter_mut() {
+				r.fetch.setting(world, last_change_tick, change_tick);
+				r.filter.setting(world, last_change_tick, change_tick);
+			}
+		}
+    }
+
+    #[inline]
+    pub fn get<'w>(
+        &self,
+        world: &'w WorldInner,
+        entity: Entity,
+    ) -> Option<<Q::Fetch as Fetch<'w>>::Item>
+    where
+        Q::Fetch: ReadOnlyFetch,
+    {
+        // SAFE: query is read only
+        unsafe { self.get_unchecked_manual(world, entity) }
+    }
+
+    #[inline]
+    pub fn get_mut<'w>(
+        &mut self,
+        world: &'w mut WorldInner,
+        entity: Entity,
+    ) -> Option<<Q::Fetch as Fetch<'w>>::Item> {
+        // SAFE: query has unique world access
+        unsafe { self.get_unchecked_manual(world, entity) }
+    }
+
+    /// # Safety
+    /// This does not check for mutable query correctness. To be safe, make sure mutable queries
+    /// have unique access to the components they query.
+    pub unsafe fn get_unchecked_manual<'w>(
+        &self,
+        _world: &'w WorldInner,
+        entity: Entity,
+    ) -> Option<<Q::Fetch as Fetch<'w>>::Item> {
+		let matched = (*self.matched_archetypes.as_ptr()).iter_mut().find(|r| r.archetype_id == entity.archetype_id())?;
+        if matched.filter.archetype_filter_fetch(entity.local()) {
+            matched.fetch.archetype_fetch(entity.local())
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    pub fn iter<'w, 's>(&'s mut self, world: &'w WorldInner) -> QueryAllIter<'w, 's, Q, F>
+    where
+        Q::Fetch: ReadOnlyFetch,
+    {
+        // SAFE: query is read only
+        unsafe { self.iter_unchecked_manual(world) }
+    }
+
+    #[inline]
+    pub fn iter_mut<'w, 's>(&'s mut self, world: &'w mut WorldInner) -> QueryAllIter<'w, 's, Q, F> {
+        // SAFE: query has unique world access
+        unsafe { self.iter_unchecked_manual(world) }
+    }
+
+    /// # Safety
+    /// This does not check for mutable query correctness. To be safe, make sure mutable queries
+    /// have unique access to the components they query.
+    #[inline]
+    pub(crate) unsafe fn iter_unchecked_manual<'w, 's>(
+        &'s self,
+        world: &'w WorldInner,
+    ) -> QueryAllIter<'w, 's, Q, F> {
+        QueryAllIter::new(world, &mut *self.matched_archetypes.as_ptr())
+    }
+
+	pub fn apply(&self, world: &mut World) {
+		self.filter_state.apply(world);
+	}
+}
@@ -1,11 +1,13 @@
-use std::mem::forget;
+use std::{alloc::{alloc, dealloc, handle_alloc_error, Layout}, ptr::NonNull};
+#[cfg(debug_assertions)]
+use std::any::{TypeId, type_name};
 
 use pi_map::Map;
 use pi_null::Null;
 use pi_share::ThreadSync;
 
 use crate::{
-	monitor::{Notify, NotifyImpl, Listener, ListenType},
+	monitor::{Notify, NotifyImpl, Listener, ListenType, ListenerDepth},
 	entity::Entity, storage::{SecondaryMap, Local}, component::ComponentTicks
 };
 
@@ -27,6 +29,15 @@ impl Notify for SingleMeta {
     fn add_modify(&self, listener: Listener) {
         self.notify.add_modify(listener)
     }
+    fn add_migrate(&self, listener: Listener) {
+        self.notify.add_migrate(listener)
+    }
+    fn add_restore(&self, listener: Listener) {
+        self.notify.add_restore(listener)
+    }
+    fn add_modify_field(&self, field: &'static str, listener: Listener) {
+        self.notify.add_modify_field(field, listener)
+    }
     fn create_event(&self, id: Entity) {
         self.notify.create_event(id);
     }
@@ -36,6 +47,12 @@ impl Notify for SingleMeta {
     fn modify_event(&self, id: Entity, field: &'static str, index: usize) {
         self.notify.modify_event(id, field, index);
     }
+    fn migrate_event(&self, id: Entity, from: Entity) {
+        self.notify.migrate_event(id, from);
+    }
+    fn restore_event(&self, id: Entity) {
+        self.notify.restore_event(id);
+    }
     fn remove_create(&self, listener: &Listener) {
         self.notify.remove_create(listener);
     }
@@ -45,31 +62,106 @@ impl Notify for SingleMeta {
     fn remove_modify(&self, listener: &Listener) {
         self.notify.remove_modify(listener);
     }
+    fn remove_migrate(&self, listener: &Listener) {
+        self.notify.remove_migrate(listener);
+    }
+    fn remove_restore(&self, listener: &Listener) {
+        self.notify.remove_restore(listener);
+    }
+    fn remove_modify_field(&self, field: &'static str, listener: &Listener) {
+        self.notify.remove_modify_field(field, listener);
+    }
 }
 
+/// 资源的元信息及其存储
+/// 存储按资源类型的Layout分配（满足对齐要求），在资源注册时分配，之后地址不再改变
 pub(crate) struct SingleMeta {
-    // value: usize, // *mut T
-	buffer: Vec<u8>,
-	is_exsit: bool, // 标记组件是否存在（之一，曾经使用buffer的长度为0来表示组件不存在，但组件的大小可能就是0，因此这种方式是不可行的，因此单独使用一个bool字段来表示资源是否存在）
+	ptr: NonNull<u8>,
+	layout: Layout,
+	is_exsit: bool, // 标记资源是否存在（存储在注册时就已分配，因此单独使用一个bool字段来表示资源是否存在）
     notify: NotifyImpl,
 	tick: ComponentTicks,
-	drop_fn: fn(*const u8),
+	drop_fn: unsafe fn(*mut u8),
+	#[cfg(debug_assertions)]
+	type_id: TypeId,
+	#[cfg(debug_assertions)]
+	type_name: &'static str,
 }
 
+// 资源类型满足ThreadSync
+unsafe impl Send for SingleMeta {}
+unsafe impl Sync for SingleMeta {}
+
 impl SingleMeta {
-    pub fn new<T>(size: usize) -> Self {
+    pub fn new<T: 'static>() -> Self {
+		let layout = Layout::new::<T>();
+		let ptr = if layout.size() == 0 {
+			// 零大小类型不需要分配内存，只需要一个对齐的指针
+			unsafe { NonNull::new_unchecked(layout.align() as *mut u8) }
+		} else {
+			match NonNull::new(unsafe { alloc(layout) }) {
+				Some(r) => r,
+				None => handle_alloc_error(layout),
+			}
+		};
         Self {
-			buffer: Vec::with_capacity(size),
+			ptr,
+			layout,
 			is_exsit: false,
             notify: NotifyImpl::default(),
 			tick: ComponentTicks::new(0),
-			drop_fn: drop::<T>,
+			drop_fn: drop_value::<T>,
+			#[cfg(debug_assertions)]
+			type_id: TypeId::of::<T>(),
+			#[cfg(debug_assertions)]
+			type_name: type_name::<T>(),
         }
     }
 
     pub fn get_notify_ref(&self) -> &NotifyImpl {
         &self.notify
     }
+
+	/// 写入资源，原有的资源会被销毁
+	unsafe fn write<T: 'static>(&mut self, value: T) {
+		self.check::<T>();
+		if self.is_exsit {
+			(self.drop_fn)(self.ptr.as_ptr());
+		}
+		self.ptr.as_ptr().cast::<T>().write(value);
+		self.is_exsit = true;
+	}
+
+	#[inline]
+	unsafe fn value<T: 'static>(&self) -> *mut T {
+		self.check::<T>();
+		self.ptr.as_ptr().cast::<T>()
+	}
+
+	/// 检查访问的类型是否与资源类型一致（仅debug）
+	#[inline(always)]
+	fn check<T: 'static>(&self) {
+		#[cfg(debug_assertions)]
+		assert!(
+			self.type_id == TypeId::of::<T>(),
+			"resource type mismatch, expect {}, found {}",
+			self.type_name,
+			type_name::<T>()
+		);
+	}
+}
+
+impl Drop for SingleMeta {
+	fn drop(&mut self) {
+		unsafe {
+			if self.is_exsit {
+				(self.drop_fn)(self.ptr.as_ptr());
+			}
+			if self.layout.size() > 0 {
+				dealloc(self.ptr.as_ptr(), self.layout);
+			}
+		}
+	}
 }
 
 pub(crate) struct Singles {
@@ -83,14 +175,20 @@ impl Singles {
 		}
 	}
 
-	pub fn register<T>(&mut self, resource_id: ResourceId) {
+	pub fn register<T: 'static>(&mut self, resource_id: ResourceId) {
 		// 不存在元信息，插入元信息
 		if self.metas.get(&resource_id).is_none() {
-			let size = std::mem::size_of::<T>();
-			self.metas.insert(resource_id, SingleMeta::new::<T>(size));
+			self.metas.insert(resource_id, SingleMeta::new::<T>());
 		};
 	}
 
+	/// 设置资源事件源的名称和所在世界的级联最大深度
+	pub(crate) fn set_notify_source(&self, resource_id: ResourceId, name: &'static str, max_depth: &ListenerDepth) {
+		if let Some(meta) = self.metas.get(&resource_id) {
+			meta.notify.set_source(name, max_depth);
+		}
+	}
+
 	pub fn add_listener<E: ListenType, T>(&mut self, resource_id: ResourceId, listener: Listener) {
 		if let Some(meta) = self.metas.get(&resource_id) {
 			E::add(&meta.notify, listener);
@@ -99,26 +197,18 @@ impl Singles {
 		}
 	}
 
+	pub fn remove_listener<E: ListenType>(&mut self, resource_id: ResourceId, listener: &Listener) {
+		if let Some(meta) = self.metas.get(&resource_id) {
+			E::remove(&meta.notify, listener);
+		}
+	}
+
 	/// 安全： 确保T和resource_id的一致性, 同时存在meta
 	pub unsafe fn insert<T: Resource>(&mut self, resource_id: ResourceId, value: T, tick: u32) {
-		let size = std::mem::size_of::<T>();
 		let meta = &mut self.metas[resource_id];
 
-		if meta.is_exsit {
-			// 资源已经存在，销毁原有的
-			(meta.drop_fn)(meta.buffer.as_ptr());
-		}
-
-		// 写入资源
-		std::ptr::copy_nonoverlapping(
-			&value as *const T as *const u8,
-			meta.buffer.as_mut_ptr(),
-			size,
-		);
-		forget(value);
-
 		let is_exsit = meta.is_exsit;
-		meta.is_exsit = true;
+		meta.write(value);
 		meta.tick.changed = tick;
 		// 通知
 		if !is_exsit {
@@ -143,6 +233,30 @@ impl Singles {
 	// 	}
 	// }
 
+	/// 写入资源并设置其节拍，不发出事件（快照恢复时使用）
+	/// 安全： 确保T和resource_id的一致性, 同时存在meta
+	pub(crate) unsafe fn restore<T: Resource>(&mut self, resource_id: ResourceId, value: T, tick: ComponentTicks) {
+		let meta = &mut self.metas[resource_id];
+		meta.write(value);
+		meta.tick = tick;
+	}
+
+	/// 取到资源及其节拍
+	/// 安全： 确保T和resource_id的一致性
+	pub(crate) unsafe fn get_with_tick<T: 'static>(&self, resource_id: ResourceId) -> Option<(&T, &ComponentTicks)> {
+		match self.metas.get(&resource_id) {
+			Some(meta) if meta.is_exsit => Some((&*meta.value::<T>(), &meta.tick)),
+			_ => None,
+		}
+	}
+
+	/// 检查所有资源的节拍，截断过旧的节拍
+	pub(crate) fn check_ticks(&mut self, change_tick: u32) {
+		for meta in self.metas.values_mut() {
+			meta.tick.check_ticks(change_tick);
+		}
+	}
+
 	/// 取到资源当前节拍
 	/// 如果资源不存在，将panic
 	pub unsafe fn get_tick_unchecked(&self, resource_id: ResourceId) -> &ComponentTicks {
@@ -150,53 +264,49 @@ impl Singles {
 	}
 
 	/// 安全： 确保T和resource_id的一致性
-	pub unsafe fn get<T>(&self, resource_id: ResourceId) -> Option<&T> {
-		if let Some(meta) = self.metas.get(&resource_id) {
-			if meta.is_exsit {
-				Some(&*(meta.buffer.as_ptr() as usize as *mut T))
-			} else {
-				None
-			}
-		} else {
-			None
+	pub unsafe fn get<T: 'static>(&self, resource_id: ResourceId) -> Option<&T> {
+		match self.metas.get(&resource_id) {
+			Some(meta) if meta.is_exsit => Some(&*meta.value::<T>()),
+			_ => None,
 		}
 	}
 
-	pub unsafe fn get_unchecked<T>(&self, resource_id: ResourceId) -> &T {
-		let meta = self.metas.get_unchecked(&resource_id);
-		&*(meta.buffer.as_ptr() as usize as *mut T)
+	pub unsafe fn get_unchecked<T: 'static>(&self, resource_id: ResourceId) -> &T {
+		&*self.metas.get_unchecked(&resource_id).value::<T>()
 	}
 
-	pub unsafe fn get_unchecked_mut<T>(&self, resource_id: ResourceId) -> &mut T {
-		let meta = self.metas.get_unchecked(&resource_id);
-		&mut *(meta.buffer.as_ptr() as usize as *mut T)
+	pub unsafe fn get_unchecked_mut<T: 'static>(&self, resource_id: ResourceId) -> &mut T {
+		&mut *self.metas.get_unchecked(&resource_id).value::<T>()
 	}
 
 	/// 安全： 确保T和resource_id的一致性
-	pub unsafe fn get_mut<T>(&self, resource_id: ResourceId) -> Option<&mut T> {
-		if let Some(meta) = self.metas.get(&resource_id) {
-			if meta.is_exsit {
-				Some(&mut *(meta.buffer.as_ptr() as usize as *mut T))
-			} else {
-				None
-			}
-		} else {
-			None
+	pub unsafe fn get_mut<T: 'static>(&self, resource_id: ResourceId) -> Option<&mut T> {
+		match self.metas.get(&resource_id) {
+			Some(meta) if meta.is_exsit => Some(&mut *meta.value::<T>()),
+			_ => None,
 		}
 	}
 
-	// ///  确保T和resource_id的一致性
-	// pub unsafe fn remove<T>(&mut self, resource_id: ResourceId) -> Option<T> {
-	// 	if let Some(meta) = self.metas.get_mut(&resource_id) {
-	// 		if meta.size.is_null() {
-	// 			return None;
-	// 		}
-	// 		meta.size = usize::null();
-	// 		Some(self.buffer.as_ptr().add(meta.index).cast::<T>().read_unaligned())
-	// 	} else {
-	// 		None
-	// 	}
-	// }
+	/// 移除资源，资源存在时，先发出删除事件（监听器中仍可读取该资源），再将资源移出
+	/// 安全： 确保T和resource_id的一致性
+	pub unsafe fn remove<T: 'static>(&mut self, resource_id: ResourceId) -> Option<T> {
+		match self.metas.get_mut(&resource_id) {
+			Some(meta) if meta.is_exsit => {
+				meta.get_notify_ref().delete_event(Entity::null());
+				meta.is_exsit = false;
+				Some(meta.value::<T>().read())
+			},
+			_ => None,
+		}
+	}
+
+	/// 资源占用的字节数，返回值为各资源的id和字节数（资源值及其监听器），以及元信息表本身的字节数
+	pub(crate) fn mem_size(&self) -> (Vec<(ResourceId, usize)>, usize) {
+		let resources = self.metas.iter()
+			.map(|(id, meta)| (id, meta.layout.size() + meta.notify.mem_size()))
+			.collect();
+		(resources, self.metas.mem_size())
+	}
 
 	pub fn get_notify_ref(&self, resource_id: ResourceId) -> &NotifyImpl {
 		if let Some(meta) = self.metas.get(&resource_id) {
@@ -209,20 +319,6 @@ impl Singles {
 }
 
 /// 销毁T
-fn drop<T>(ptr: *const u8) {
-	unsafe {ptr.cast::<T>().read_unaligned()};
+unsafe fn drop_value<T>(ptr: *mut u8) {
+	std::ptr::drop_in_place(ptr.cast::<T>());
 }
-
-impl Drop for Singles {
-	fn drop(&mut self) {
-		for (_local, meta) in self.metas.iter() {
-			if meta.is_exsit {
-				(meta.drop_fn)(meta.buffer.as_ptr());
-			}
-		}
-	}
-}
-
-// fn drop<T>(ptr: usize) {
-// 	unsafe {ptr as usize as *mut T}
-// }
\ No newline at end of file
@@ -0,0 +1,401 @@
+/// 世界的序列化与反序列化
+/// 组件、资源类型需要先注册到类型注册表中（World::register_serde、World::register_resource_serde），未注册的类型不会被保存
+/// 保存的内容包括：原型、实体（包括实体版本）、已注册的组件和资源
+use std::{any::{Any, type_name}, io::{Read, Write}, marker::PhantomData, sync::Arc};
+
+use pi_hash::XHashMap;
+use pi_share::cell::TrustCell;
+use pi_slotmap::DelaySlotMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::DeserializeOwned};
+use thiserror::Error;
+
+use crate::{
+	archetype::{Archetype, ArchetypeId},
+	component::{CellMultiCase, Component, ComponentId, MultiCase, MultiCaseImpl, is_whole_value},
+	entity::{Entity, Id},
+	resource::Resource,
+	storage::{Local, LocalVersion, Offset},
+	world::WorldInner,
+};
+
+#[derive(Debug, Error)]
+pub enum SerializeError {
+	#[error("serialize error: {0}")]
+	Bincode(#[from] bincode::Error),
+	#[error("type {0:?} is not registered for serialization")]
+	Unregistered(String),
+	#[error("archetype {index} mismatch, expect {expect:?}, found {found:?}")]
+	ArchetypeMismatch { index: usize, expect: String, found: String },
+}
+
+/// 解码后、尚未写入世界的数据
+type Decoded = Box<dyn Any>;
+
+type SaveComponent = fn(&Archetype, &Arc<dyn MultiCase>) -> Result<Vec<u8>, bincode::Error>;
+type DecodeComponent = fn(&[u8]) -> Result<Decoded, bincode::Error>;
+type LoadComponent = fn(Decoded, &Arc<dyn MultiCase>, u32);
+
+/// 组件的序列化函数
+struct ComponentSerde {
+	name: &'static str,
+	id: ComponentId,
+	save: SaveComponent,
+	decode: DecodeComponent,
+	load: LoadComponent,
+	new_container: fn(ArchetypeId) -> Arc<dyn MultiCase>,
+}
+
+/// 资源的序列化函数
+struct ResourceSerde {
+	name: &'static str,
+	save: fn(&WorldInner) -> Result<Option<Vec<u8>>, bincode::Error>,
+	decode: fn(&[u8]) -> Result<Decoded, bincode::Error>,
+	load: fn(&mut WorldInner, Decoded),
+}
+
+/// 类型注册表，记录可序列化的组件和资源类型
+/// 类型通过类型名在保存和加载之间对应
+#[derive(Default)]
+pub struct TypeRegistry {
+	components: XHashMap<ComponentId, ComponentSerde>,
+	component_names: XHashMap<&'static str, ComponentId>,
+	resources: Vec<ResourceSerde>,
+}
+
+impl TypeRegistry {
+	pub(crate) fn register_component<C: Component + Serialize + DeserializeOwned>(&mut self, id: ComponentId) {
+		let name = type_name::<C>();
+		self.component_names.insert(name, id);
+		self.components.insert(id, ComponentSerde {
+			name,
+			id,
+			save: save_component::<C>,
+			decode: decode::<Vec<(LocalVersion, C)>>,
+			load: load_component::<C>,
+			new_container: new_container::<C>,
+		});
+	}
+
+	pub(crate) fn register_resource<R: Resource + Serialize + DeserializeOwned>(&mut self) {
+		let name = type_name::<R>();
+		if self.resources.iter().any(|r| r.name == name) {
+			return;
+		}
+		self.resources.push(ResourceSerde {
+			name,
+			save: save_resource::<R>,
+			decode: decode::<R>,
+			load: load_resource::<R>,
+		});
+	}
+
+	fn component_by_name(&self, name: &str) -> Result<&ComponentSerde, SerializeError> {
+		self.component_names.get(name)
+			.and_then(|id| self.components.get(id))
+			.ok_or_else(|| SerializeError::Unregistered(name.to_string()))
+	}
+
+	/// 取到动态原型的组件id（升序）
+	fn component_ids(&self, names: &[String]) -> Result<Vec<ComponentId>, SerializeError> {
+		let mut ids = names.iter()
+			.map(|name| self.component_by_name(name).map(|r| r.id))
+			.collect::<Result<Vec<ComponentId>, SerializeError>>()?;
+		ids.sort();
+		Ok(ids)
+	}
+}
+
+#[derive(Serialize, Deserialize)]
+struct WorldData {
+	archetypes: Vec<ArchetypeData>,
+	resources: Vec<(String, Vec<u8>)>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ArchetypeData {
+	// 静态原型的标识名称，动态原型为None
+	ident: Option<String>,
+	// 动态原型的组件类型名
+	components: Vec<String>,
+	// 实体容器（保留实体版本和空闲槽位）
+	entities: Vec<u8>,
+	// 组件类型名与该类型组件的数据
+	columns: Vec<(String, Vec<u8>)>,
+}
+
+/// 解码并校验后的原型，所有原型和资源都解码成功后才写入世界
+struct DecodedArchetype {
+	// 需要创建的动态原型的组件，原型已存在时为None
+	create: Option<Vec<ComponentId>>,
+	entities: DelaySlotMap<LocalVersion, ()>,
+	columns: Vec<(LoadComponent, ComponentId, Decoded)>,
+}
+
+impl WorldInner {
+	/// 注册可序列化的组件类型
+	/// 保存时需要整体读取组件，SoA存储的组件不能注册，注册时panic
+	pub fn register_serde<C: Component + Serialize + DeserializeOwned>(&mut self) {
+		assert!(is_whole_value::<C>(), "register_serde: component {} uses SoA storage and cannot be serialized as a whole", std::any::type_name::<C>());
+		let id = self.components.get_or_insert_id::<C>();
+		self.type_registry.register_component::<C>(id);
+	}
+
+	/// 注册可序列化的资源类型
+	pub fn register_resource_serde<R: Resource + Serialize + DeserializeOwned>(&mut self) {
+		self.type_registry.register_resource::<R>();
+	}
+
+	/// 保存世界
+	/// 静态原型只保存其标识，加载时，目标世界中需要以相同的顺序创建这些原型
+	/// 动态原型中不能含有未注册的组件类型（组件集合决定了动态原型）
+	pub fn save<W: Write>(&self, writer: W) -> Result<(), SerializeError> {
+		let registry = &self.type_registry;
+		let mut archetypes = Vec::with_capacity(self.archetypes.archetypes.len());
+		for archetype in self.archetypes.archetypes.iter() {
+			let (ident, components) = if archetype.is_dynamic() {
+				let names = archetype.component_ids().iter().map(|id| match registry.components.get(id) {
+					Some(r) => Ok(r.name.to_string()),
+					None => Err(SerializeError::Unregistered(self.components.infos[id.offset()].name.to_string())),
+				}).collect::<Result<Vec<String>, SerializeError>>()?;
+				(None, names)
+			} else {
+				(Some(self.archetype_ident(archetype).to_string()), Vec::new())
+			};
+
+			let mut columns = Vec::new();
+			for id in archetype.component_ids() {
+				if let Some(r) = registry.components.get(id) {
+					let container = unsafe { archetype.get_component(*id) };
+					columns.push((r.name.to_string(), (r.save)(archetype, container)?));
+				}
+			}
+
+			archetypes.push(ArchetypeData {
+				ident,
+				components,
+				entities: bincode::serialize(&**archetype.entities)?,
+				columns,
+			});
+		}
+
+		let mut resources = Vec::new();
+		for r in registry.resources.iter() {
+			if let Some(bytes) = (r.save)(self)? {
+				resources.push((r.name.to_string(), bytes));
+			}
+		}
+
+		bincode::serialize_into(writer, &WorldData { archetypes, resources })?;
+		Ok(())
+	}
+
+	/// 加载世界
+	/// 原型按保存时的顺序与当前世界中的原型一一对应，缺少的动态原型会被创建，因此组件中持有的Id、Entity在加载后仍然有效
+	/// 原型中已有的实体会被清除；加载组件不发出事件，资源通过insert_resource写入，会发出资源的创建或修改事件
+	/// 先解码并校验全部数据，出错时世界保持不变
+	pub fn load<R: Read>(&mut self, reader: R) -> Result<(), SerializeError> {
+		let data: WorldData = bincode::deserialize_from(reader)?;
+
+		let mut archetypes = Vec::with_capacity(data.archetypes.len());
+		for (index, archetype_data) in data.archetypes.iter().enumerate() {
+			archetypes.push(self.decode_archetype(index, archetype_data, &archetypes)?);
+		}
+		let mut resources = Vec::with_capacity(data.resources.len());
+		for (name, bytes) in data.resources.iter() {
+			let r = match self.type_registry.resources.iter().find(|r| r.name == name) {
+				Some(r) => r,
+				None => return Err(SerializeError::Unregistered(name.clone())),
+			};
+			resources.push((r.load, (r.decode)(bytes)?));
+		}
+
+		let tick = self.read_change_tick();
+		for (index, decoded) in archetypes.into_iter().enumerate() {
+			let id = match decoded.create {
+				Some(component_ids) => self.create_archetype(component_ids),
+				None => ArchetypeId::new(index),
+			};
+			let archetype = &mut self.archetypes[id];
+
+			// 清除原有的实体及其组件
+			let locals: Vec<LocalVersion> = archetype.entities.keys().collect();
+			for local in locals {
+				for component_id in archetype.component_ids() {
+					unsafe { archetype.get_component(*component_id) }.delete(local);
+				}
+			}
+			**archetype.entities = decoded.entities;
+
+			for (load, component_id, values) in decoded.columns {
+				load(values, unsafe { archetype.get_component(component_id) }, tick);
+			}
+		}
+
+		for (load, value) in resources {
+			load(self, value);
+		}
+		Ok(())
+	}
+
+	/// 解码并校验保存的原型，不修改世界
+	/// decoded为之前已解码的原型，用于检查需要创建的动态原型是否重复
+	fn decode_archetype(&self, index: usize, data: &ArchetypeData, decoded: &[DecodedArchetype]) -> Result<DecodedArchetype, SerializeError> {
+		let create = self.check_archetype(index, data, decoded)?;
+		let entities = bincode::deserialize(&data.entities)?;
+
+		let mut columns = Vec::with_capacity(data.columns.len());
+		for (name, bytes) in data.columns.iter() {
+			let r = self.type_registry.component_by_name(name)?;
+			let contains = match &create {
+				Some(component_ids) => component_ids.contains(&r.id),
+				None => self.archetypes[ArchetypeId::new(index)].contains(r.id),
+			};
+			if !contains {
+				return Err(SerializeError::ArchetypeMismatch {
+					index,
+					expect: name.clone(),
+					found: String::from("none"),
+				});
+			}
+			columns.push((r.load, r.id, (r.decode)(bytes)?));
+		}
+		Ok(DecodedArchetype { create, entities, columns })
+	}
+
+	/// 静态原型的标识名称（实体访问的名称中含有原型类型名）
+	fn archetype_ident(&self, archetype: &Archetype) -> &'static str {
+		self.archetypes.archetype_component_info[archetype.entity_archetype_component_id().offset()]
+	}
+
+	/// 检查保存的原型与当前世界中对应的原型是否一致
+	/// 原型已存在时返回None，不存在的动态原型返回需要创建的组件（升序）
+	fn check_archetype(&self, index: usize, data: &ArchetypeData, decoded: &[DecodedArchetype]) -> Result<Option<Vec<ComponentId>>, SerializeError> {
+		let id = ArchetypeId::new(index);
+		let expect = match &data.ident {
+			Some(r) => r.clone(),
+			None => format!("{:?}", data.components),
+		};
+
+		if index < self.archetypes.archetypes.len() {
+			let archetype = &self.archetypes[id];
+			let found = match (&data.ident, archetype.is_dynamic()) {
+				(Some(ident), false) if ident == self.archetype_ident(archetype) => return Ok(None),
+				(None, true) => {
+					let mut component_ids = archetype.component_ids().to_vec();
+					component_ids.sort();
+					if self.type_registry.component_ids(&data.components)? == component_ids {
+						return Ok(None);
+					}
+					format!("{:?}", component_ids.iter().map(|r| self.components.infos[r.offset()].name).collect::<Vec<&str>>())
+				},
+				(_, false) => self.archetype_ident(archetype).to_string(),
+				(_, true) => String::from("dynamic archetype"),
+			};
+			return Err(SerializeError::ArchetypeMismatch { index, expect, found });
+		}
+
+		let component_ids = match data.ident {
+			Some(_) => None,
+			None => Some(self.type_registry.component_ids(&data.components)?),
+		};
+		// 静态原型无法通过名称创建，动态原型不能已经存在于其它位置
+		match component_ids {
+			Some(r) if self.archetypes.get_id_by_components(&r).is_none()
+				&& !decoded.iter().any(|d| d.create.as_ref() == Some(&r)) => Ok(Some(r)),
+			_ => Err(SerializeError::ArchetypeMismatch { index, expect, found: String::from("none") }),
+		}
+	}
+
+	/// 创建加载的动态原型
+	fn create_archetype(&mut self, component_ids: Vec<ComponentId>) -> ArchetypeId {
+		let dst = self.archetypes.create_archetype_by_components(component_ids.clone());
+		for component_id in component_ids {
+			let container = (self.type_registry.components[&component_id].new_container)(dst);
+			let name = self.components.infos[component_id.offset()].dynamic_name;
+			let archetype_component_id = self.archetypes.archetype_component_grow(name, true);
+			self.archetypes[dst].register_component_container(component_id, container, Local::new(archetype_component_id));
+		}
+		dst
+	}
+}
+
+fn save_component<C: Component + Serialize>(archetype: &Archetype, container: &Arc<dyn MultiCase>) -> Result<Vec<u8>, bincode::Error> {
+	let container = match container.downcast_ref::<CellMultiCase<C>>() {
+		Some(r) => r.borrow(),
+		None => panic!("downcast err"),
+	};
+	let values: Vec<(LocalVersion, &C)> = archetype.entities.keys()
+		.filter_map(|local| container.get(local).map(|r| (local, r)))
+		.collect();
+	bincode::serialize(&values)
+}
+
+fn decode<T: DeserializeOwned + 'static>(bytes: &[u8]) -> Result<Decoded, bincode::Error> {
+	bincode::deserialize::<T>(bytes).map(|r| Box::new(r) as Decoded)
+}
+
+fn load_component<C: Component>(values: Decoded, container: &Arc<dyn MultiCase>, tick: u32) {
+	let values = match values.downcast::<Vec<(LocalVersion, C)>>() {
+		Ok(r) => r,
+		Err(_) => panic!("downcast err"),
+	};
+	let mut container = match container.downcast_ref::<CellMultiCase<C>>() {
+		Some(r) => r.borrow_mut(),
+		None => panic!("downcast err"),
+	};
+	for (local, value) in *values {
+		container.insert_no_notify(local, value, tick);
+	}
+}
+
+fn new_container<C: Component>(archetype_id: ArchetypeId) -> Arc<dyn MultiCase> {
+	Arc::new(TrustCell::new(MultiCaseImpl::<C>::with_capacity(0, archetype_id)))
+}
+
+fn save_resource<R: Resource + Serialize>(world: &WorldInner) -> Result<Option<Vec<u8>>, bincode::Error> {
+	world.get_resource::<R>().map(bincode::serialize).transpose()
+}
+
+fn load_resource<R: Resource>(world: &mut WorldInner, value: Decoded) {
+	match value.downcast::<R>() {
+		Ok(r) => world.insert_resource(*r),
+		Err(_) => panic!("downcast err"),
+	};
+}
+
+impl Serialize for LocalVersion {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		self.0.serialize(serializer)
+	}
+}
+
+impl<'de> Deserialize<'de> for LocalVersion {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		u64::deserialize(deserializer).map(LocalVersion)
+	}
+}
+
+impl<T> Serialize for Id<T> {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		self.0.serialize(serializer)
+	}
+}
+
+impl<'de, T> Deserialize<'de> for Id<T> {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		LocalVersion::deserialize(deserializer).map(|r| Id(r, PhantomData))
+	}
+}
+
+impl Serialize for Entity {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		(self.archetype_id().offset(), self.local()).serialize(serializer)
+	}
+}
+
+impl<'de> Deserialize<'de> for Entity {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let (archetype_id, local) = <(usize, LocalVersion)>::deserialize(deserializer)?;
+		Ok(Entity::new(ArchetypeId::new(archetype_id), local))
+	}
+}
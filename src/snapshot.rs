@@ -0,0 +1,174 @@
+/// 世界快照
+/// 快照保存实体容器、已注册克隆函数的组件（包括其ticks）和资源，可在之后将世界恢复到快照时的状态
+/// 组件、资源类型需要先通过World::register_clone、World::register_resource_clone注册克隆函数，未注册的不随快照恢复
+use std::{any::Any, sync::Arc};
+
+use pi_hash::XHashMap;
+use pi_null::Null;
+use pi_slotmap::DelaySlotMap;
+
+use crate::{
+	archetype::Archetype,
+	component::{CellMultiCase, Component, ComponentId, ComponentTicks, MultiCase, is_whole_value},
+	entity::Entity,
+	monitor::Notify,
+	resource::{Resource, ResourceId, Singles},
+	storage::LocalVersion,
+	world::WorldInner,
+};
+
+type SnapshotData = Box<dyn Any + Send + Sync>;
+
+/// 组件的克隆函数
+struct ComponentClone {
+	snapshot: fn(&Archetype, &Arc<dyn MultiCase>) -> SnapshotData,
+	restore: fn(&SnapshotData, &Arc<dyn MultiCase>),
+}
+
+/// 资源的克隆函数
+struct ResourceClone {
+	snapshot: fn(&Singles, ResourceId) -> Option<SnapshotData>,
+	restore: fn(&mut Singles, ResourceId, &SnapshotData),
+}
+
+/// 克隆函数注册表
+#[derive(Default)]
+pub struct CloneRegistry {
+	components: XHashMap<ComponentId, ComponentClone>,
+	resources: XHashMap<ResourceId, ResourceClone>,
+}
+
+/// 世界快照，只能用于恢复创建它的世界
+pub struct WorldSnapshot {
+	archetypes: Vec<ArchetypeSnapshot>,
+	resources: Vec<(ResourceId, SnapshotData)>,
+}
+
+struct ArchetypeSnapshot {
+	entities: DelaySlotMap<LocalVersion, ()>,
+	columns: Vec<(ComponentId, SnapshotData)>,
+}
+
+impl WorldInner {
+	/// 为组件类型注册克隆函数
+	/// 快照需要整体克隆组件，SoA存储的组件不能注册，注册时panic
+	pub fn register_clone<C: Component + Clone>(&mut self) {
+		assert!(is_whole_value::<C>(), "register_clone: component {} uses SoA storage and cannot be cloned as a whole", std::any::type_name::<C>());
+		let id = self.components.get_or_insert_id::<C>();
+		self.clone_registry.components.insert(id, ComponentClone {
+			snapshot: snapshot_component::<C>,
+			restore: restore_component::<C>,
+		});
+	}
+
+	/// 为资源类型注册克隆函数
+	pub fn register_resource_clone<R: Resource + Clone>(&mut self) {
+		let id = self.get_or_insert_resource_id::<R>();
+		self.clone_registry.resources.insert(id, ResourceClone {
+			snapshot: snapshot_resource::<R>,
+			restore: restore_resource::<R>,
+		});
+	}
+
+	/// 创建快照
+	pub fn snapshot(&self) -> WorldSnapshot {
+		let registry = &self.clone_registry;
+		let archetypes = self.archetypes.archetypes.iter().map(|archetype| {
+			let columns = archetype.component_ids().iter()
+				.filter_map(|id| registry.components.get(id).map(|r| {
+					(*id, (r.snapshot)(archetype, unsafe { archetype.get_component(*id) }))
+				}))
+				.collect();
+			ArchetypeSnapshot {
+				entities: (**archetype.entities).clone(),
+				columns,
+			}
+		}).collect();
+
+		let resources = registry.resources.iter()
+			.filter_map(|(id, r)| (r.snapshot)(&self.archetypes.resources, *id).map(|data| (*id, data)))
+			.collect();
+
+		WorldSnapshot { archetypes, resources }
+	}
+
+	/// 将世界恢复到快照时的状态
+	/// 恢复过程不发出实体、组件、资源的事件，完成后发出一次世界恢复事件（`Restore`）
+	/// 快照中没有的组件（未注册克隆函数），在快照前后都存在的实体上保持不变，其余实体上的被删除
+	/// 快照之后创建的实体被移除，槽位版本继续增加，不会与之后创建的实体混淆；快照之后插入的资源，保持不变
+	pub fn restore(&mut self, snapshot: &WorldSnapshot) {
+		let registry = &self.clone_registry;
+		for (index, archetype) in self.archetypes.archetypes.iter_mut().enumerate() {
+			let r = snapshot.archetypes.get(index);
+			// 清除当前的组件，快照中没有的组件只清除快照中不存在的实体上的
+			let locals: Vec<LocalVersion> = archetype.entities.keys().collect();
+			for id in archetype.component_ids() {
+				let container = unsafe { archetype.get_component(*id) };
+				let column = r.is_some_and(|r| r.columns.iter().any(|(c, _)| c == id));
+				for local in locals.iter() {
+					if column || !r.is_some_and(|r| r.entities.contains_key(*local)) {
+						container.delete(*local);
+					}
+				}
+			}
+
+			match r {
+				Some(r) => {
+					archetype.entities.restore(&r.entities);
+					for (id, data) in r.columns.iter() {
+						(registry.components[id].restore)(data, unsafe { archetype.get_component(*id) });
+					}
+				},
+				None => for local in locals {
+					archetype.entities.remove_no_notify(local);
+				},
+			}
+		}
+
+		for (id, data) in snapshot.resources.iter() {
+			(registry.resources[id].restore)(&mut self.archetypes.resources, *id, data);
+		}
+
+		self.world_listners.restore_event(Entity::null());
+	}
+}
+
+fn snapshot_component<C: Component + Clone>(archetype: &Archetype, container: &Arc<dyn MultiCase>) -> SnapshotData {
+	let container = match container.downcast_ref::<CellMultiCase<C>>() {
+		Some(r) => r.borrow(),
+		None => panic!("downcast err"),
+	};
+	let values: Vec<(LocalVersion, C, ComponentTicks)> = archetype.entities.keys()
+		.filter_map(|local| {
+			// 不记录ticks的组件没有节拍，恢复时也不会写入，这里用默认值占位
+			container.get(local).map(|value| (local, value.clone(), container.tick(local).copied().unwrap_or(ComponentTicks::new(0))))
+		})
+		.collect();
+	Box::new(values)
+}
+
+fn restore_component<C: Component + Clone>(data: &SnapshotData, container: &Arc<dyn MultiCase>) {
+	let values = match data.downcast_ref::<Vec<(LocalVersion, C, ComponentTicks)>>() {
+		Some(r) => r,
+		None => panic!("downcast err"),
+	};
+	let mut container = match container.downcast_ref::<CellMultiCase<C>>() {
+		Some(r) => r.borrow_mut(),
+		None => panic!("downcast err"),
+	};
+	for (local, value, tick) in values.iter() {
+		container.insert_with_ticks(*local, value.clone(), *tick);
+	}
+}
+
+fn snapshot_resource<R: Resource + Clone>(resources: &Singles, id: ResourceId) -> Option<SnapshotData> {
+	unsafe { resources.get_with_tick::<R>(id) }.map(|(value, tick)| Box::new((value.clone(), *tick)) as SnapshotData)
+}
+
+fn restore_resource<R: Resource + Clone>(resources: &mut Singles, id: ResourceId, data: &SnapshotData) {
+	let (value, tick) = match data.downcast_ref::<(R, ComponentTicks)>() {
+		Some(r) => r,
+		None => panic!("downcast err"),
+	};
+	unsafe { resources.restore(id, value.clone(), *tick) };
+}
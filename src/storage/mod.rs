@@ -1,13 +1,52 @@
 use std::convert::From;
+use std::marker::PhantomData;
+use std::mem::{size_of, size_of_val};
 use std::ops::{Deref, DerefMut};
+use std::ptr::NonNull;
 use std::{ops::Index, ops::IndexMut};
 
+use fixedbitset::FixedBitSet;
+use pi_hash::XHashMap;
+
+use crate::component::StorageType;
+
+mod soa;
+mod packed;
+pub use soa::{SoAComponent, SoAField, SoAMap};
+pub use packed::{DenseKeys, PackedMap};
+
 pub use pi_slotmap::{Key, KeyData, SlotMap, SecondaryMap as SecondaryMap1, SparseSecondaryMap as SparseSecondaryMap1, DenseSlotMap, DelaySlotMap};
 pub use pi_map::Map;
 pub use pi_slotmap::dense::{Iter, IterMut, Keys, Values};
 pub use pi_null::Null;
 
 
+/// 哈希表占用的字节数（估算：每个桶存放一个元素和一个控制字节）
+#[inline]
+pub(crate) fn hash_mem_size<T>(capacity: usize) -> usize {
+	capacity * (size_of::<T>() + 1)
+}
+
+// 与pi_slotmap的SecondaryMap槽位布局一致，仅用于计算槽位大小
+#[allow(dead_code)]
+enum SecondarySlot<V> {
+	Occupied { value: V, version: u32 },
+	Vacant,
+}
+
+// 与pi_slotmap的SparseSecondaryMap槽位布局一致，仅用于计算槽位大小
+#[allow(dead_code)]
+struct SparseSlot<V> {
+	version: u32,
+	value: V,
+}
+
+/// pi_slotmap的SecondaryMap占用的字节数（槽位数组比容量多一个哨兵槽位）
+#[inline]
+pub(crate) fn secondary_mem_size<K: Key, V>(map: &SecondaryMap1<K, V>) -> usize {
+	(map.capacity() + 1) * size_of::<SecondarySlot<V>>()
+}
+
 pub struct SecondaryMap<K: Key, V>(SecondaryMap1<K, V>);
 
 impl<K: Key, V> Deref for SecondaryMap<K, V> {
@@ -37,7 +76,7 @@ impl<K: Key, V> Map for SecondaryMap<K, V> {
 		self.0.len()
 	}
     fn mem_size(&self) -> usize {
-		self.0.len()
+		secondary_mem_size(&self.0)
 	}
     fn contains(&self, key: &Self::Key) -> bool {
 		self.0.contains_key(*key)
@@ -107,7 +146,7 @@ impl<K: Key, V> Map for SparseSecondaryMap<K, V> {
 		self.0.len()
 	}
     fn mem_size(&self) -> usize {
-		self.0.len()
+		hash_mem_size::<(u32, SparseSlot<V>)>(self.0.capacity())
 	}
     fn contains(&self, key: &Self::Key) -> bool {
 		self.0.contains_key(*key)
@@ -148,6 +187,314 @@ impl<K: Key, V> IndexMut<K> for SparseSecondaryMap<K, V> {
 	}
 }
 
+/// 哈希存储，适合只有极少数实体拥有、且实体分布稀疏的组件
+pub struct HashSecondaryMap<K: Key, V>(XHashMap<K, V>);
+
+impl<K: Key, V> Deref for HashSecondaryMap<K, V> {
+	type Target = XHashMap<K, V>;
+    fn deref(&self) -> &Self::Target {
+		&self.0
+	}
+}
+
+impl<K: Key, V> DerefMut for HashSecondaryMap<K, V> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+		&mut self.0
+	}
+}
+
+impl<K: Key, V> Map for HashSecondaryMap<K, V> {
+	type Key = K;
+	type Val = V;
+
+	fn len(&self) -> usize {
+		self.0.len()
+	}
+	fn with_capacity(capacity: usize) -> Self {
+		HashSecondaryMap(XHashMap::with_capacity_and_hasher(capacity, Default::default()))
+	}
+    fn capacity(&self) -> usize {
+		self.0.capacity()
+	}
+    fn mem_size(&self) -> usize {
+		hash_mem_size::<(K, V)>(self.0.capacity())
+	}
+    fn contains(&self, key: &Self::Key) -> bool {
+		self.0.contains_key(key)
+	}
+    fn get(&self, key: &Self::Key) -> Option<&Self::Val> {
+		self.0.get(key)
+	}
+    fn get_mut(&mut self, key: &Self::Key) -> Option<&mut Self::Val> {
+		self.0.get_mut(key)
+	}
+    unsafe fn get_unchecked(&self, key: &Self::Key) -> &Self::Val {
+		&self.0[key]
+	}
+    unsafe fn get_unchecked_mut(&mut self, key: &Self::Key) -> &mut Self::Val {
+		self.0.get_mut(key).unwrap()
+	}
+    unsafe fn remove_unchecked(&mut self, key: &Self::Key) -> Self::Val {
+		self.0.remove(key).unwrap()
+	}
+    fn insert(&mut self, key: Self::Key, val: Self::Val) -> Option<Self::Val> {
+		self.0.insert(key, val)
+	}
+    fn remove(&mut self, key: &Self::Key) -> Option<Self::Val> {
+		self.0.remove(key)
+	}
+}
+
+impl<K: Key, V> Index<K> for HashSecondaryMap<K, V> {
+	type Output = V;
+    fn index(&self, index: K) -> &Self::Output {
+		&self.0[&index]
+	}
+}
+
+impl<K: Key, V> IndexMut<K> for HashSecondaryMap<K, V> {
+    fn index_mut(&mut self, index: K) -> &mut Self::Output {
+		self.0.get_mut(&index).unwrap()
+	}
+}
+
+/// 标记存储，用位集按实体槽位（`Offset`）记录实体是否拥有组件，组件必须是零大小类型
+/// 每个槽位同时记录插入时键的版本，槽位被新实体复用后，旧键不再命中
+pub struct TagMap<K: Key + Offset, V> {
+	bits: FixedBitSet,
+	versions: Vec<u32>,
+	len: usize,
+	_marker: PhantomData<(K, V)>,
+}
+
+impl<K: Key + Offset, V> TagMap<K, V> {
+	/// 零大小类型的引用不指向任何内存，任意对齐的非空指针都是合法的
+	#[inline]
+	fn value<'a>() -> &'a mut V {
+		const { assert!(size_of::<V>() == 0, "tag storage requires a zero-sized component") };
+		unsafe { &mut *NonNull::<V>::dangling().as_ptr() }
+	}
+
+	/// 零大小类型的值不含任何字节，取出时直接构造，不从指针读取
+	#[inline]
+	fn take() -> V {
+		const { assert!(size_of::<V>() == 0, "tag storage requires a zero-sized component") };
+		unsafe { std::mem::zeroed() }
+	}
+}
+
+impl<K: Key + Offset, V> Map for TagMap<K, V> {
+	type Key = K;
+	type Val = V;
+
+	fn len(&self) -> usize {
+		self.len
+	}
+	fn with_capacity(capacity: usize) -> Self {
+		TagMap { bits: FixedBitSet::with_capacity(capacity), versions: Vec::with_capacity(capacity), len: 0, _marker: PhantomData }
+	}
+    fn capacity(&self) -> usize {
+		self.bits.len()
+	}
+    fn mem_size(&self) -> usize {
+		size_of_val(self.bits.as_slice()) + self.versions.capacity() * size_of::<u32>()
+	}
+    fn contains(&self, key: &Self::Key) -> bool {
+		let offset = key.offset();
+		self.bits.contains(offset) && self.versions[offset] == key_version(key)
+	}
+    fn get(&self, key: &Self::Key) -> Option<&Self::Val> {
+		if self.contains(key) { Some(Self::value()) } else { None }
+	}
+    fn get_mut(&mut self, key: &Self::Key) -> Option<&mut Self::Val> {
+		if self.contains(key) { Some(Self::value()) } else { None }
+	}
+    unsafe fn get_unchecked(&self, _key: &Self::Key) -> &Self::Val {
+		Self::value()
+	}
+    unsafe fn get_unchecked_mut(&mut self, _key: &Self::Key) -> &mut Self::Val {
+		Self::value()
+	}
+    unsafe fn remove_unchecked(&mut self, key: &Self::Key) -> Self::Val {
+		self.remove(key).unwrap()
+	}
+    fn insert(&mut self, key: Self::Key, val: Self::Val) -> Option<Self::Val> {
+		// 值不占内存，由remove时重新构造
+		std::mem::forget(val);
+		let offset = key.offset();
+		if offset >= self.bits.len() {
+			self.bits.grow(offset + 1);
+			self.versions.resize(offset + 1, 0);
+		}
+		let version = std::mem::replace(&mut self.versions[offset], key_version(&key));
+		if !self.bits.put(offset) {
+			self.len += 1;
+			None
+		} else if version == key_version(&key) {
+			Some(Self::take())
+		} else {
+			// 槽位上是已失效实体的标记，直接覆盖
+			None
+		}
+	}
+    fn remove(&mut self, key: &Self::Key) -> Option<Self::Val> {
+		if !self.contains(key) {
+			return None;
+		}
+		self.bits.set(key.offset(), false);
+		self.len -= 1;
+		Some(Self::take())
+	}
+}
+
+impl<K: Key + Offset, V> Index<K> for TagMap<K, V> {
+	type Output = V;
+    fn index(&self, index: K) -> &Self::Output {
+		match self.get(&index) {
+			Some(r) => r,
+			None => panic!("invalid TagMap key used"),
+		}
+	}
+}
+
+impl<K: Key + Offset, V> IndexMut<K> for TagMap<K, V> {
+    fn index_mut(&mut self, index: K) -> &mut Self::Output {
+		match self.get_mut(&index) {
+			Some(r) => r,
+			None => panic!("invalid TagMap key used"),
+		}
+	}
+}
+
+/// 存储容器的类型，用于报告组件实际使用的存储（`ComponentInfo::storage_type`）
+/// 未特化的容器报告为`StorageType::Custom`
+pub trait StorageKind {
+	const STORAGE_TYPE: StorageType;
+}
+
+impl<T> StorageKind for T {
+	default const STORAGE_TYPE: StorageType = StorageType::Custom;
+}
+
+impl<K: Key, V> StorageKind for SecondaryMap<K, V> {
+	const STORAGE_TYPE: StorageType = StorageType::Dense;
+}
+
+impl<K: Key, V> StorageKind for SparseSecondaryMap<K, V> {
+	const STORAGE_TYPE: StorageType = StorageType::Sparse;
+}
+
+impl<K: Key, V> StorageKind for HashSecondaryMap<K, V> {
+	const STORAGE_TYPE: StorageType = StorageType::Hash;
+}
+
+impl<K: Key + Offset, V> StorageKind for TagMap<K, V> {
+	const STORAGE_TYPE: StorageType = StorageType::Tag;
+}
+
+impl<K: Key + Offset, V: SoAComponent> StorageKind for SoAMap<K, V> {
+	const STORAGE_TYPE: StorageType = StorageType::SoA;
+}
+
+impl<K: Key + Offset, V> StorageKind for PackedMap<K, V> {
+	const STORAGE_TYPE: StorageType = StorageType::Packed;
+}
+
+/// 预留容量
+/// Map接口没有预留容量的方法，未特化的容器不做任何处理
+pub trait Reserve {
+	fn reserve(&mut self, additional: usize);
+}
+
+impl<T> Reserve for T {
+	default fn reserve(&mut self, _additional: usize) {}
+}
+
+impl<K: Key, V> Reserve for SecondaryMap<K, V> {
+	fn reserve(&mut self, additional: usize) {
+		let capacity = self.0.len() + additional;
+		self.0.set_capacity(capacity);
+	}
+}
+
+impl<K: Key, V> Reserve for SparseSecondaryMap<K, V> {
+	fn reserve(&mut self, additional: usize) {
+		self.0.reserve(additional);
+	}
+}
+
+impl<K: Key, V> Reserve for HashSecondaryMap<K, V> {
+	fn reserve(&mut self, additional: usize) {
+		self.0.reserve(additional);
+	}
+}
+
+/// 释放未使用的容量（大量实体销毁后，容器不会自动收缩）
+/// Map接口没有收缩容量的方法，未特化的容器不做任何处理
+pub trait Shrink {
+	fn shrink(&mut self);
+}
+
+impl<T> Shrink for T {
+	default fn shrink(&mut self) {}
+}
+
+/// 键在槽位数组中的位置
+#[inline]
+fn slot_index<K: Key>(key: &K) -> usize {
+	(key.data().as_ffi() & u32::MAX as u64) as usize
+}
+
+impl<K: Key, V> Shrink for SecondaryMap<K, V> {
+	/// pi_slotmap的SecondaryMap不能收缩，按最大的槽位重建
+	fn shrink(&mut self) {
+		let max = self.0.keys().map(|k| slot_index(&k)).max().unwrap_or(0);
+		let mut map = SecondaryMap1::with_capacity(max);
+		for (k, v) in self.0.drain() {
+			map.insert(k, v);
+		}
+		self.0 = map;
+	}
+}
+
+impl<K: Key, V> Shrink for SparseSecondaryMap<K, V> {
+	fn shrink(&mut self) {
+		let mut map = SparseSecondaryMap1::with_capacity(self.0.len());
+		for (k, v) in self.0.drain() {
+			map.insert(k, v);
+		}
+		self.0 = map;
+	}
+}
+
+impl<K: Key, V> Shrink for HashSecondaryMap<K, V> {
+	fn shrink(&mut self) {
+		self.0.shrink_to_fit();
+	}
+}
+
+impl<K: Key + Offset, V> Shrink for TagMap<K, V> {
+	fn shrink(&mut self) {
+		self.bits = shrink_bits(&self.bits);
+		self.versions.truncate(self.bits.len());
+		self.versions.shrink_to_fit();
+	}
+}
+
+/// 键的版本（`KeyData`的高32位），按槽位存储的容器用它区分复用同一槽位的新旧实体
+#[inline]
+pub(crate) fn key_version<K: Key>(key: &K) -> u32 {
+	(key.data().as_ffi() >> 32) as u32
+}
+
+/// 按最大的置位重建位集
+pub(crate) fn shrink_bits(bits: &FixedBitSet) -> FixedBitSet {
+	let len = bits.ones().last().map_or(0, |r| r + 1);
+	let mut r = FixedBitSet::with_capacity(len);
+	r.extend(bits.ones());
+	r
+}
+
 pub trait Offset: Clone {
 	fn offset(&self) -> usize;
 }
@@ -271,7 +618,8 @@ impl FromOffset for Local{
 impl From<KeyData> for Local {
 	#[inline]
     fn from(data: KeyData) -> Self {
-		Local(data.as_ffi() as usize)
+		// 只取槽位，版本在data()中补回
+		Local((data.as_ffi() & u32::MAX as u64) as usize)
 	}
 }
 
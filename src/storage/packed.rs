@@ -0,0 +1,175 @@
+/// 紧凑存储
+/// 组件值连续存放在`Vec`中，通过实体槽位到下标的映射查找，删除时与最后一个元素交换
+/// 只有少数实体拥有的组件，迭代时不必遍历稀疏的内存
+use std::ops::{Index, IndexMut};
+
+use pi_map::Map;
+
+use super::{Key, LocalVersion, Offset, Reserve, Shrink};
+
+const NULL_INDEX: u32 = u32::MAX;
+
+/// 紧凑存储容器
+pub struct PackedMap<K: Key + Offset, V> {
+	values: Vec<V>,
+	/// 与values一一对应的键
+	keys: Vec<K>,
+	/// 实体槽位（`Offset`）到values下标的映射
+	indices: Vec<u32>,
+}
+
+impl<K: Key + Offset, V> PackedMap<K, V> {
+	/// 所有的键，与`values`一一对应
+	#[inline]
+	pub fn keys(&self) -> &[K] {
+		&self.keys
+	}
+
+	/// 所有的值，连续存放
+	#[inline]
+	pub fn values(&self) -> &[V] {
+		&self.values
+	}
+
+	#[inline]
+	pub fn values_mut(&mut self) -> &mut [V] {
+		&mut self.values
+	}
+
+	#[inline]
+	fn index_of(&self, key: &K) -> Option<usize> {
+		match self.indices.get(key.offset()) {
+			Some(&index) if index != NULL_INDEX && self.keys[index as usize] == *key => Some(index as usize),
+			_ => None,
+		}
+	}
+}
+
+impl<K: Key + Offset, V> Map for PackedMap<K, V> {
+	type Key = K;
+	type Val = V;
+
+	fn len(&self) -> usize {
+		self.values.len()
+	}
+	fn with_capacity(capacity: usize) -> Self {
+		PackedMap {
+			values: Vec::with_capacity(capacity),
+			keys: Vec::with_capacity(capacity),
+			indices: Vec::new(),
+		}
+	}
+    fn capacity(&self) -> usize {
+		self.values.capacity()
+	}
+    fn mem_size(&self) -> usize {
+		self.values.capacity() * size_of::<V>()
+			+ self.keys.capacity() * size_of::<K>()
+			+ self.indices.capacity() * size_of::<u32>()
+	}
+    fn contains(&self, key: &Self::Key) -> bool {
+		self.index_of(key).is_some()
+	}
+    fn get(&self, key: &Self::Key) -> Option<&Self::Val> {
+		self.index_of(key).map(|i| &self.values[i])
+	}
+    fn get_mut(&mut self, key: &Self::Key) -> Option<&mut Self::Val> {
+		self.index_of(key).map(|i| &mut self.values[i])
+	}
+    unsafe fn get_unchecked(&self, key: &Self::Key) -> &Self::Val {
+		self.values.get_unchecked(*self.indices.get_unchecked(key.offset()) as usize)
+	}
+    unsafe fn get_unchecked_mut(&mut self, key: &Self::Key) -> &mut Self::Val {
+		let index = *self.indices.get_unchecked(key.offset()) as usize;
+		self.values.get_unchecked_mut(index)
+	}
+    unsafe fn remove_unchecked(&mut self, key: &Self::Key) -> Self::Val {
+		self.remove(key).unwrap()
+	}
+    fn insert(&mut self, key: Self::Key, val: Self::Val) -> Option<Self::Val> {
+		let offset = key.offset();
+		if offset >= self.indices.len() {
+			self.indices.resize(offset + 1, NULL_INDEX);
+		}
+		let index = self.indices[offset];
+		if index != NULL_INDEX {
+			let index = index as usize;
+			// 槽位被新版本的实体复用时，旧版本的值视为不存在
+			let old = std::mem::replace(&mut self.values[index], val);
+			if self.keys[index] == key {
+				return Some(old);
+			}
+			self.keys[index] = key;
+			return None;
+		}
+		self.indices[offset] = self.values.len() as u32;
+		self.values.push(val);
+		self.keys.push(key);
+		None
+	}
+    fn remove(&mut self, key: &Self::Key) -> Option<Self::Val> {
+		let index = self.index_of(key)?;
+		self.indices[key.offset()] = NULL_INDEX;
+		let value = self.values.swap_remove(index);
+		self.keys.swap_remove(index);
+		// 原来的最后一个元素被移到index处
+		if let Some(moved) = self.keys.get(index) {
+			self.indices[moved.offset()] = index as u32;
+		}
+		Some(value)
+	}
+}
+
+impl<K: Key + Offset, V> Index<K> for PackedMap<K, V> {
+	type Output = V;
+    fn index(&self, index: K) -> &Self::Output {
+		match self.get(&index) {
+			Some(r) => r,
+			None => panic!("invalid PackedMap key used"),
+		}
+	}
+}
+
+impl<K: Key + Offset, V> IndexMut<K> for PackedMap<K, V> {
+    fn index_mut(&mut self, index: K) -> &mut Self::Output {
+		match self.get_mut(&index) {
+			Some(r) => r,
+			None => panic!("invalid PackedMap key used"),
+		}
+	}
+}
+
+impl<K: Key + Offset, V> Reserve for PackedMap<K, V> {
+	fn reserve(&mut self, additional: usize) {
+		self.values.reserve(additional);
+		self.keys.reserve(additional);
+	}
+}
+
+impl<K: Key + Offset, V> Shrink for PackedMap<K, V> {
+	fn shrink(&mut self) {
+		self.values.shrink_to_fit();
+		self.keys.shrink_to_fit();
+		let len = self.keys.iter().map(|k| k.offset() + 1).max().unwrap_or(0);
+		self.indices.truncate(len);
+		self.indices.shrink_to_fit();
+	}
+}
+
+/// 紧凑存放的容器可以直接列出拥有组件的实体，查询可以由它驱动迭代
+/// 未特化的容器返回None
+pub trait DenseKeys {
+	fn dense_keys(&self) -> Option<&Vec<LocalVersion>>;
+}
+
+impl<T> DenseKeys for T {
+	default fn dense_keys(&self) -> Option<&Vec<LocalVersion>> {
+		None
+	}
+}
+
+impl<V> DenseKeys for PackedMap<LocalVersion, V> {
+	fn dense_keys(&self) -> Option<&Vec<LocalVersion>> {
+		Some(&self.keys)
+	}
+}
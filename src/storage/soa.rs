@@ -0,0 +1,183 @@
+/// 结构数组（SoA）存储
+/// 组件的各字段分别存放在按`LocalVersion::offset()`索引的列中，只读写某个字段的系统不必加载整个组件
+use std::{any::type_name, marker::PhantomData, ops::{Index, IndexMut}};
+
+use fixedbitset::FixedBitSet;
+use pi_map::Map;
+use pi_share::ThreadSync;
+
+use super::{key_version, shrink_bits, Key, Offset, Shrink};
+use crate::component::FieldName;
+
+/// 使用SoA存储的组件，由`#[derive(Component)] #[storage(soa)]`生成实现
+/// 字段类型需要实现`Default`，空位以默认值填充
+/// SoA组件不能整体借用，`#[derive(ComponentFields)]`生成的setter无法使用，编译时即被拒绝：
+/// ```compile_fail
+/// use pi_ecs_macros::{Component, ComponentFields};
+///
+/// #[derive(Component, ComponentFields)]
+/// #[storage(soa)]
+/// pub struct Particle {
+///     pub position: f32,
+/// }
+/// ```
+pub trait SoAComponent: Sized + 'static {
+	/// 按字段拆分的列
+	type Columns: Default + ThreadSync + 'static;
+
+	/// 将组件拆分，写入各列的offset位置
+	fn write(self, columns: &mut Self::Columns, offset: usize);
+
+	/// 从各列的offset位置取出组件，原位置留下默认值
+	fn take(columns: &mut Self::Columns, offset: usize) -> Self;
+
+	/// 将各列的长度扩展到len
+	fn resize(columns: &mut Self::Columns, len: usize);
+
+	/// 各列占用的字节数
+	fn mem_size(columns: &Self::Columns) -> usize;
+
+	/// 将各列截断到len，并释放多余的容量
+	fn shrink(columns: &mut Self::Columns, len: usize);
+}
+
+/// SoA组件的字段，`F`为`#[derive(Component)]`生成的字段标记类型，如`particle_fields::velocity`
+pub trait SoAField<F: FieldName>: SoAComponent {
+	type Type: ThreadSync + 'static;
+
+	fn column(columns: &Self::Columns) -> &[Self::Type];
+
+	fn column_mut(columns: &mut Self::Columns) -> &mut [Self::Type];
+}
+
+/// SoA存储容器
+/// 组件不以整体存放，不能取得组件的整体引用，需通过`Field`、`FieldMut`查询字段
+/// 与`TagMap`相同，每个槽位记录插入时键的版本，槽位被新实体复用后，旧键不再命中
+pub struct SoAMap<K: Key + Offset, V: SoAComponent> {
+	columns: V::Columns,
+	present: FixedBitSet,
+	versions: Vec<u32>,
+	len: usize,
+	_marker: PhantomData<K>,
+}
+
+impl<K: Key + Offset, V: SoAComponent> SoAMap<K, V> {
+	/// 所有列
+	#[inline]
+	pub fn columns(&self) -> &V::Columns {
+		&self.columns
+	}
+
+	/// 字段`F`的列，按`LocalVersion::offset()`索引，没有组件的位置为默认值
+	#[inline]
+	pub fn column<F: FieldName>(&self) -> &[<V as SoAField<F>>::Type]
+	where
+		V: SoAField<F>,
+	{
+		V::column(&self.columns)
+	}
+
+	/// 字段`F`的可变列
+	#[inline]
+	pub fn column_mut<F: FieldName>(&mut self) -> &mut [<V as SoAField<F>>::Type]
+	where
+		V: SoAField<F>,
+	{
+		V::column_mut(&mut self.columns)
+	}
+
+	fn whole_value() -> ! {
+		panic!("{} uses SoA storage and cannot be borrowed as a whole, query its fields with Field or FieldMut", type_name::<V>())
+	}
+}
+
+impl<K: Key + Offset, V: SoAComponent> Map for SoAMap<K, V> {
+	type Key = K;
+	type Val = V;
+
+	fn len(&self) -> usize {
+		self.len
+	}
+	fn with_capacity(capacity: usize) -> Self {
+		let mut columns = V::Columns::default();
+		V::resize(&mut columns, capacity);
+		SoAMap { columns, present: FixedBitSet::with_capacity(capacity), versions: vec![0; capacity], len: 0, _marker: PhantomData }
+	}
+    fn capacity(&self) -> usize {
+		self.present.len()
+	}
+    fn mem_size(&self) -> usize {
+		V::mem_size(&self.columns) + std::mem::size_of_val(self.present.as_slice())
+			+ self.versions.capacity() * std::mem::size_of::<u32>()
+	}
+    fn contains(&self, key: &Self::Key) -> bool {
+		let offset = key.offset();
+		self.present.contains(offset) && self.versions[offset] == key_version(key)
+	}
+    fn get(&self, _key: &Self::Key) -> Option<&Self::Val> {
+		Self::whole_value()
+	}
+    fn get_mut(&mut self, _key: &Self::Key) -> Option<&mut Self::Val> {
+		Self::whole_value()
+	}
+    unsafe fn get_unchecked(&self, _key: &Self::Key) -> &Self::Val {
+		Self::whole_value()
+	}
+    unsafe fn get_unchecked_mut(&mut self, _key: &Self::Key) -> &mut Self::Val {
+		Self::whole_value()
+	}
+    unsafe fn remove_unchecked(&mut self, key: &Self::Key) -> Self::Val {
+		self.remove(key).unwrap()
+	}
+    fn insert(&mut self, key: Self::Key, val: Self::Val) -> Option<Self::Val> {
+		let offset = key.offset();
+		if offset >= self.present.len() {
+			self.present.grow(offset + 1);
+			self.versions.resize(offset + 1, 0);
+			V::resize(&mut self.columns, offset + 1);
+		}
+		let version = std::mem::replace(&mut self.versions[offset], key_version(&key));
+		let old = if !self.present.put(offset) {
+			self.len += 1;
+			None
+		} else if version == key_version(&key) {
+			Some(V::take(&mut self.columns, offset))
+		} else {
+			// 槽位上是已失效实体的字段，直接覆盖
+			None
+		};
+		val.write(&mut self.columns, offset);
+		old
+	}
+    fn remove(&mut self, key: &Self::Key) -> Option<Self::Val> {
+		if !self.contains(key) {
+			return None;
+		}
+		let offset = key.offset();
+		self.present.set(offset, false);
+		self.len -= 1;
+		Some(V::take(&mut self.columns, offset))
+	}
+}
+
+impl<K: Key + Offset, V: SoAComponent> Shrink for SoAMap<K, V> {
+	fn shrink(&mut self) {
+		self.present = shrink_bits(&self.present);
+		self.versions.truncate(self.present.len());
+		self.versions.shrink_to_fit();
+		V::shrink(&mut self.columns, self.present.len());
+	}
+}
+
+impl<K: Key + Offset, V: SoAComponent> Index<K> for SoAMap<K, V> {
+	type Output = V;
+    fn index(&self, _index: K) -> &Self::Output {
+		Self::whole_value()
+	}
+}
+
+impl<K: Key + Offset, V: SoAComponent> IndexMut<K> for SoAMap<K, V> {
+    fn index_mut(&mut self, _index: K) -> &mut Self::Output {
+		Self::whole_value()
+	}
+}
@@ -0,0 +1,191 @@
+/// 订阅
+/// 供ECS之外的代码（如网络、UI线程）以异步流的方式接收事件（`Events<T>`）和组件监听事件
+/// 流由有界的flume通道实现，通道满时按`Overflow`处理
+use std::{
+	fmt,
+	pin::Pin,
+	task::{Context, Poll},
+};
+
+use flume::{bounded, r#async::RecvStream, Receiver, Sender, TrySendError};
+use futures::{Stream, StreamExt};
+use pi_share::{Share, ShareWeak};
+
+use crate::{
+	archetype::ArchetypeIdent,
+	component::Component,
+	monitor::{Event, ListenType, Listener},
+	sys::param::event::Events,
+	world::WorldInner,
+};
+
+/// 通道满时的处理策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+	/// 丢弃最旧的未读事件
+	DropOldest,
+	/// 丢弃新事件
+	DropNewest,
+	/// 阻塞发送方，直到流中有空位（注意：发送方通常是系统或监听器所在的线程）
+	Block,
+}
+
+/// 订阅配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubscribeConfig {
+	/// 通道容量，最小为1
+	pub capacity: usize,
+	pub overflow: Overflow,
+}
+
+impl Default for SubscribeConfig {
+	fn default() -> Self {
+		Self {
+			capacity: 1024,
+			overflow: Overflow::DropOldest,
+		}
+	}
+}
+
+/// 订阅得到的事件流，丢弃后不再接收事件
+pub struct Subscription<T: 'static> {
+	stream: RecvStream<'static, T>,
+	_alive: Share<()>,
+}
+
+impl<T: 'static> Stream for Subscription<T> {
+	type Item = T;
+
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+		self.stream.poll_next_unpin(cx)
+	}
+}
+
+/// 事件流的发送端
+pub(crate) struct StreamSender<T> {
+	sender: Sender<T>,
+	/// 丢弃最旧事件时，用于从通道中取出事件
+	receiver: Option<Receiver<T>>,
+	overflow: Overflow,
+	alive: ShareWeak<()>,
+}
+
+impl<T> fmt::Debug for StreamSender<T> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("StreamSender")
+			.field("overflow", &self.overflow)
+			.field("closed", &self.is_closed())
+			.finish()
+	}
+}
+
+impl<T> StreamSender<T> {
+	/// 流是否已被丢弃
+	#[inline]
+	pub(crate) fn is_closed(&self) -> bool {
+		self.alive.strong_count() == 0
+	}
+
+	/// 按溢出策略发送事件
+	pub(crate) fn send(&self, value: T) {
+		if self.is_closed() {
+			return;
+		}
+		match self.overflow {
+			Overflow::DropNewest => {
+				let _ = self.sender.try_send(value);
+			}
+			Overflow::Block => {
+				let _ = self.sender.send(value);
+			}
+			Overflow::DropOldest => {
+				let mut value = value;
+				while let Err(TrySendError::Full(r)) = self.sender.try_send(value) {
+					if let Some(receiver) = &self.receiver {
+						let _ = receiver.try_recv();
+					}
+					value = r;
+				}
+			}
+		}
+	}
+}
+
+/// 组件事件流的监听器，流被丢弃后在应用点（`WorldInner::apply_listeners`）从世界中移除
+pub(crate) struct SubscriptionListener {
+	alive: ShareWeak<()>,
+	listener: Listener,
+	remove: fn(&mut WorldInner, &Listener),
+}
+
+/// 创建事件流及其发送端
+pub(crate) fn channel<T: 'static>(config: SubscribeConfig) -> (StreamSender<T>, Subscription<T>) {
+	let (sender, receiver) = bounded(config.capacity.max(1));
+	let alive = Share::new(());
+	let stream_sender = StreamSender {
+		sender,
+		receiver: match config.overflow {
+			Overflow::DropOldest => Some(receiver.clone()),
+			_ => None,
+		},
+		overflow: config.overflow,
+		alive: Share::downgrade(&alive),
+	};
+	(stream_sender, Subscription { stream: receiver.into_stream(), _alive: alive })
+}
+
+impl WorldInner {
+	/// 订阅事件`T`，按默认配置（容量1024，丢弃最旧）创建事件流
+	/// 若事件未注册，会先通过`add_event`注册
+	pub fn subscribe<T: Component + Clone>(&mut self) -> impl Stream<Item = T> + Unpin {
+		self.subscribe_with::<T>(SubscribeConfig::default())
+	}
+
+	/// 订阅事件`T`，指定通道容量和溢出策略
+	pub fn subscribe_with<T: Component + Clone>(&mut self, config: SubscribeConfig) -> impl Stream<Item = T> + Unpin {
+		if self.get_resource::<Events<T>>().is_none() {
+			self.add_event::<T>();
+		}
+		self.get_resource_mut::<Events<T>>().unwrap().subscribe(config)
+	}
+
+	/// 订阅原型`A`的组件`C`的`L`事件，按默认配置创建事件流
+	pub fn subscribe_component<A: ArchetypeIdent, C: Component, L: ListenType>(&mut self) -> impl Stream<Item = Event> + Unpin {
+		self.subscribe_component_with::<A, C, L>(SubscribeConfig::default())
+	}
+
+	/// 订阅原型`A`的组件`C`的`L`事件，指定通道容量和溢出策略
+	/// 流被丢弃后，监听器不再发送事件，并在下一个应用点或下一次订阅时从世界中移除
+	pub fn subscribe_component_with<A: ArchetypeIdent, C: Component, L: ListenType>(
+		&mut self,
+		config: SubscribeConfig,
+	) -> impl Stream<Item = Event> + Unpin {
+		self.remove_closed_subscriptions();
+		let (sender, subscription) = channel(config);
+		let alive = sender.alive.clone();
+		let listener = Listener::new(
+			format!("subscribe<{}>", std::any::type_name::<C>()),
+			move |e: Event| sender.send(e),
+		);
+		self.add_component_listener::<L, A, C>(listener.clone());
+		self.subscriptions.push(SubscriptionListener {
+			alive,
+			listener,
+			remove: |world, listener| world.remove_component_listener::<L, A, C>(listener),
+		});
+		subscription
+	}
+
+	/// 移除流已被丢弃的组件事件监听器
+	pub(crate) fn remove_closed_subscriptions(&mut self) {
+		let mut i = 0;
+		while i < self.subscriptions.len() {
+			if self.subscriptions[i].alive.strong_count() == 0 {
+				let r = self.subscriptions.swap_remove(i);
+				(r.remove)(self, &r.listener);
+			} else {
+				i += 1;
+			}
+		}
+	}
+}
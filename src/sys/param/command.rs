@@ -3,7 +3,7 @@ use std::marker::PhantomData;
 
 use pi_slotmap::Key;
 
-use crate::{component::Component, archetype::ArchetypeIdent, entity::{Entities, Id}, world::World, storage::Local, sys::system::SystemState};
+use crate::{component::Component, archetype::ArchetypeIdent, bundle::Bundle, entity::{Entities, Entity, Id}, resource::Resource, world::World, storage::Local, sys::system::SystemState};
 
 use super::{SystemParam, SystemParamState, SystemParamFetch};
 use pi_share::ThreadSync;
@@ -22,6 +22,24 @@ pub struct Commands<A: ArchetypeIdent, T: Component> {
 	queues: &'static mut CommandQueues<A, T>,
 }
 
+/// 迁移指令，用于将实体从原型`From`迁移到原型`To`
+pub struct MigrateCommands<From: ArchetypeIdent, To: ArchetypeIdent> {
+	_world: World,
+	queue: &'static mut CommandQueue<EntityMigrate<From, To>>,
+}
+
+/// 资源指令，用于删除资源
+pub struct ResourceCommands<T: Resource> {
+	_world: World,
+	queue: &'static mut CommandQueue<ResourceRemove<T>>,
+}
+
+/// 组件集合指令，用于为实体插入一组组件
+pub struct BundleCommands<A: ArchetypeIdent, B: Bundle> {
+	_world: World,
+	queue: &'static mut CommandQueue<BundleInsert<A, B>>,
+}
+
 pub struct CommandQueues<A: ArchetypeIdent, T: Component> {
 	create: CommandQueue<ComponentInsert<A, T>>,
 	delete: CommandQueue<ComponentDelete<A, T>>,
@@ -70,6 +88,48 @@ impl<A: ArchetypeIdent> EntityCommands<A> {
 	}
 }
 
+impl<From: ArchetypeIdent, To: ArchetypeIdent> MigrateCommands<From, To> {
+	pub fn new(queue: &'static mut CommandQueue<EntityMigrate<From, To>>, world: &World) -> Self {
+		Self {
+			_world: world.clone(),
+			queue,
+		}
+	}
+
+	/// 迁移实体，迁移后的实体id在指令应用后才能确定，可通过`Migrate`实体监听取得
+	pub fn migrate(&mut self, entity: Id<From>) {
+		self.queue.push(EntityMigrate(entity, PhantomData));
+	}
+}
+
+impl<T: Resource> ResourceCommands<T> {
+	pub fn new(queue: &'static mut CommandQueue<ResourceRemove<T>>, world: &World) -> Self {
+		Self {
+			_world: world.clone(),
+			queue,
+		}
+	}
+
+	/// 删除资源，指令应用时发出资源的删除事件
+	pub fn remove(&mut self) {
+		self.queue.push(ResourceRemove(PhantomData));
+	}
+}
+
+impl<A: ArchetypeIdent, B: Bundle> BundleCommands<A, B> {
+	pub fn new(queue: &'static mut CommandQueue<BundleInsert<A, B>>, world: &World) -> Self {
+		Self {
+			_world: world.clone(),
+			queue,
+		}
+	}
+
+	/// 为实体插入组件集合，指令应用时逐个发出组件的创建（或修改）事件
+	pub fn insert(&mut self, entity: Id<A>, bundle: B) {
+		self.queue.push(BundleInsert(entity, bundle));
+	}
+}
+
 impl<A: ArchetypeIdent, T: Component> Commands<A, T> {
 	/// Create a new `Commands` from a queue and a world.
     pub fn new(
@@ -146,6 +206,30 @@ impl<A: ArchetypeIdent, T: Component> Command for ComponentDelete<A, T> {
 	}
 }
 
+pub struct EntityMigrate<From: ArchetypeIdent, To: ArchetypeIdent>(pub(crate) Id<From>, PhantomData<To>);
+
+impl<From: ArchetypeIdent, To: ArchetypeIdent> Command for EntityMigrate<From, To> {
+	fn write(self, world: &mut World, _arch_id: Local, _type_id: Local) {
+		world.migrate::<From, To>(self.0);
+	}
+}
+
+pub struct BundleInsert<A: ArchetypeIdent, B>(pub(crate) Id<A>, pub(crate) B);
+
+impl<A: ArchetypeIdent, B: Bundle> Command for BundleInsert<A, B> {
+	fn write(self, world: &mut World, arch_id: Local, _type_id: Local) {
+		world.insert_bundle(Entity::new(arch_id, self.0.0), self.1);
+	}
+}
+
+pub struct ResourceRemove<T>(PhantomData<T>);
+
+impl<T: Resource> Command for ResourceRemove<T> {
+	fn write(self, world: &mut World, _arch_id: Local, type_id: Local) {
+		unsafe { world.archetypes.remove_resource::<T>(type_id) };
+	}
+}
+
 /**********************SystemParam Commands***************************/
 
 
@@ -222,4 +306,112 @@ impl<'w, 's, A: ArchetypeIdent> SystemParamFetch<'w, 's> for CommandQueue<Entity
     ) -> Self::Item {
 		EntityCommands::new(std::mem::transmute(state), world)
     }
-}
\ No newline at end of file
+}
+/**********************SystemParam MigrateCommands***************************/
+
+impl<From: ArchetypeIdent, To: ArchetypeIdent> SystemParam for MigrateCommands<From, To> {
+    type Fetch = CommandQueue<EntityMigrate<From, To>>;
+}
+
+// SAFE: only local state is accessed
+unsafe impl<From: ArchetypeIdent, To: ArchetypeIdent> SystemParamState for CommandQueue<EntityMigrate<From, To>> {
+    type Config = ();
+
+    fn init(world:  &mut World, _system_state: &mut SystemState, _config: Self::Config) -> Self {
+		let arch_id = world.archetypes_mut().get_or_create_archetype::<From>();
+		world.archetypes_mut().get_or_create_archetype::<To>();
+		CommandQueue::new(world, arch_id, Local::null())
+    }
+
+    fn default_config() {}
+
+	fn apply(&mut self, world: &mut World) {
+		self.apply(world);
+	}
+}
+
+impl<'w, 's, From: ArchetypeIdent, To: ArchetypeIdent> SystemParamFetch<'w, 's> for CommandQueue<EntityMigrate<From, To>> {
+    type Item = MigrateCommands<From, To>;
+
+    #[inline]
+    unsafe fn get_param(
+        state: &'s mut Self,
+        _system_state: & SystemState,
+        world: &'w World,
+        _last_change_tick: u32,
+    ) -> Self::Item {
+		MigrateCommands::new(&mut *(state as *mut Self), world)
+    }
+}
+
+/**********************SystemParam BundleCommands***************************/
+
+impl<A: ArchetypeIdent, B: Bundle> SystemParam for BundleCommands<A, B> {
+    type Fetch = CommandQueue<BundleInsert<A, B>>;
+}
+
+// SAFE: only local state is accessed
+unsafe impl<A: ArchetypeIdent, B: Bundle> SystemParamState for CommandQueue<BundleInsert<A, B>> {
+    type Config = ();
+
+    fn init(world:  &mut World, _system_state: &mut SystemState, _config: Self::Config) -> Self {
+		let arch_id = world.archetypes_mut().get_or_create_archetype::<A>();
+		CommandQueue::new(world, arch_id, Local::null())
+    }
+
+    fn default_config() {}
+
+	fn apply(&mut self, world: &mut World) {
+		self.apply(world);
+	}
+}
+
+impl<'w, 's, A: ArchetypeIdent, B: Bundle> SystemParamFetch<'w, 's> for CommandQueue<BundleInsert<A, B>> {
+    type Item = BundleCommands<A, B>;
+
+    #[inline]
+    unsafe fn get_param(
+        state: &'s mut Self,
+        _system_state: & SystemState,
+        world: &'w World,
+        _last_change_tick: u32,
+    ) -> Self::Item {
+		BundleCommands::new(&mut *(state as *mut Self), world)
+    }
+}
+
+/**********************SystemParam ResourceCommands***************************/
+
+impl<T: Resource> SystemParam for ResourceCommands<T> {
+    type Fetch = CommandQueue<ResourceRemove<T>>;
+}
+
+// SAFE: only local state is accessed
+unsafe impl<T: Resource> SystemParamState for CommandQueue<ResourceRemove<T>> {
+    type Config = ();
+
+    fn init(world:  &mut World, _system_state: &mut SystemState, _config: Self::Config) -> Self {
+		let resource_id = world.get_or_insert_resource_id::<T>();
+		CommandQueue::new(world, Local::null(), resource_id)
+    }
+
+    fn default_config() {}
+
+	fn apply(&mut self, world: &mut World) {
+		self.apply(world);
+	}
+}
+
+impl<'w, 's, T: Resource> SystemParamFetch<'w, 's> for CommandQueue<ResourceRemove<T>> {
+    type Item = ResourceCommands<T>;
+
+    #[inline]
+    unsafe fn get_param(
+        state: &'s mut Self,
+        _system_state: & SystemState,
+        world: &'w World,
+        _last_change_tick: u32,
+    ) -> Self::Item {
+		ResourceCommands::new(&mut *(state as *mut Self), world)
+    }
+}
@@ -3,6 +3,7 @@
 use super::{SystemParam, SystemParamFetch, SystemParamState};
 use crate::{
     component::Component,
+    subscribe::{channel, StreamSender, SubscribeConfig, Subscription},
     world::World,
     sys::{
         param::{Local, Res, ResMut, NotApply},
@@ -10,11 +11,13 @@ use crate::{
     },
 };
 use log::trace;
-use pi_share::ThreadSync;
+use pi_share::{Share, ShareMutex, ShareWeak, ThreadSync};
 use std::{
+    collections::VecDeque,
     fmt::{self},
     hash::Hash,
     marker::PhantomData, intrinsics::transmute,
+    sync::atomic::{AtomicUsize, Ordering},
 };
 
 /// `EventId` 事件的唯一标志
@@ -56,14 +59,24 @@ struct EventInstance<T> {
     pub event: T,
 }
 
-// 状态 ？
-#[derive(Debug)]
-enum State {
-    A,
-    B,
+/// 事件保留策略，决定 [`Events::update`] 时哪些事件会被清除
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventRetention {
+    /// 保留最近N帧（N次[`Events::update`]之间）发出的事件，N最小为1
+    Frames(usize),
+    /// 保留事件，直到所有已注册的 [`EventReader`] 都读取过它；
+    /// 没有 [`EventReader`] 时，update 会清除之前发出的所有事件
+    UntilConsumed,
+}
+
+impl Default for EventRetention {
+    /// 默认保留两帧，即原双缓冲的行为
+    fn default() -> Self {
+        EventRetention::Frames(2)
+    }
 }
 
-/// [`Events`] 事件集合，收集两次 [`Events::update`] 调用 之间的 事件；
+/// [`Events`] 事件集合，收集 [`Events::update`] 调用 之间的 事件；
 ///
 /// Events 用 [`EventWriter`] 写
 /// Events 用 [`EventReader`] 读
@@ -74,7 +87,7 @@ enum State {
 ///
 /// [`Events::update`] 只能 一帧 更新一次；
 ///
-/// 用 [`add_event`]初始化时，[`Events::update_system`] 会做更新操作；
+/// 用 [`World::add_event`](crate::world::WorldInner::add_event) 初始化时，`World::arrange()`插入的整理节点每帧会调用一次 [`Events::update`]；
 ///
 /// [`EventReader`] are expected to read events from this collection at least once per loop/frame.
 /// Events will persist across a single frame boundary and so ordering of event producers and
@@ -111,40 +124,111 @@ enum State {
 ///
 /// # 细节
 ///
-/// [`Events`] 用 双缓冲 实现
+/// 事件按id顺序存放在一个队列中，并记录每次 [`update`](Events::update) 时的事件计数；
 ///
-/// 每次调用 [`update`](Events::update) 都会 交换缓冲区，清掉 旧的那个；
-/// - [`EventReader`] 从两个Buffer 读 Event
+/// 每次调用 [`update`](Events::update) 都会 按 [`EventRetention`] 清掉 过期的事件；默认保留两帧：
 /// - [`EventReader`] 如果每次更新至少读取一次，则 不会 丢弃事件。
 /// - [`EventReader`] 在 两次更新 中 读取一次 可能仍会收到一些事件
 /// - [`EventReader`] 在 两次更新 后 读取的内容，保证会 删除 在 这些更新 之前 发生的 所有事件
 ///
-/// 如 没有调用 [`update`](Events::update)，则 buffers in [`Events`] 会不断的增长；
+/// 如 没有调用 [`update`](Events::update)，则 [`Events`] 会不断的增长；
 ///
 /// 可选的模式 是 调用 [`update`](Events::update) 手动跨帧控制事件何时被清除。
 /// 如果不清理，这会使消耗变得复杂，并且存在内存使用量不断扩大的风险，
-/// 但可以通过将事件作为Res 而不是 使用[`add_event`] 来完成
+/// 但可以通过将事件作为Res 而不是 使用[`World::add_event`](crate::world::WorldInner::add_event) 来完成
 #[derive(Debug)]
 pub struct Events<T> {
-    events_a: Vec<EventInstance<T>>,
-    events_b: Vec<EventInstance<T>>,
-    a_start_event_count: usize,
-    b_start_event_count: usize,
+    events: VecDeque<EventInstance<T>>,
+    /// 保留的各帧起始事件计数，最后一个为当前帧
+    frame_starts: VecDeque<usize>,
     event_count: usize,
-    state: State,
+    retention: EventRetention,
+    /// 已注册的读取器的读取位置（[`EventRetention::UntilConsumed`]使用）
+    readers: ShareMutex<Vec<ShareWeak<AtomicUsize>>>,
+    /// 事件流的订阅者，发送事件时即转发
+    subscribers: Vec<EventSubscriber<T>>,
+}
+
+// 事件流的订阅者
+#[derive(Debug)]
+struct EventSubscriber<T> {
+    sender: StreamSender<T>,
+    // 订阅时记下T的克隆函数，发送事件时不需要T: Clone
+    clone: fn(&T) -> T,
+}
+
+// 将事件转发给所有订阅者
+fn publish<T>(subscribers: &[EventSubscriber<T>], event: &T) {
+    for s in subscribers {
+        s.sender.send((s.clone)(event));
+    }
 }
 
 impl<T> Default for Events<T> {
     fn default() -> Self {
+        Self::with_retention(EventRetention::default())
+    }
+}
+
+impl<T> Events<T> {
+    /// 创建指定保留策略的事件集合
+    pub fn with_retention(retention: EventRetention) -> Self {
         Events {
-            a_start_event_count: 0,
-            b_start_event_count: 0,
+            events: VecDeque::new(),
+            frame_starts: VecDeque::from([0]),
             event_count: 0,
-            events_a: Vec::new(),
-            events_b: Vec::new(),
-            state: State::A,
+            retention,
+            readers: ShareMutex::new(Vec::new()),
+            subscribers: Vec::new(),
         }
     }
+
+    /// 保留策略
+    #[inline]
+    pub fn retention(&self) -> EventRetention {
+        self.retention
+    }
+
+    /// 设置保留策略，在下次 [`Events::update`] 时生效
+    #[inline]
+    pub fn set_retention(&mut self, retention: EventRetention) {
+        self.retention = retention;
+    }
+
+    /// 注册读取器的读取位置，[`EventRetention::UntilConsumed`]策略下，事件会保留到所有读取位置越过它
+    pub fn add_reader(&self, cursor: &Share<AtomicUsize>) {
+        self.readers.lock().push(Share::downgrade(cursor));
+    }
+
+    /// 订阅事件，之后发送的事件都会被克隆到返回的流中
+    pub fn subscribe(&mut self, config: SubscribeConfig) -> Subscription<T>
+    where
+        T: Clone + 'static,
+    {
+        let (sender, subscription) = channel(config);
+        self.subscribers.push(EventSubscriber { sender, clone: T::clone });
+        subscription
+    }
+
+    // 队列中第一个事件的id
+    #[inline]
+    fn first_event_id(&self) -> usize {
+        self.event_count - self.events.len()
+    }
+
+    // last_event_count之后的第一个事件在队列中的位置
+    #[inline]
+    fn index_of(&self, last_event_count: usize) -> usize {
+        last_event_count
+            .saturating_sub(self.first_event_id())
+            .min(self.events.len())
+    }
+
+    // 清除id小于keep的事件
+    fn remove_before(&mut self, keep: usize) {
+        let count = self.index_of(keep);
+        self.events.drain(..count);
+    }
 }
 
 impl<T: Component> Events<T> {
@@ -157,13 +241,11 @@ impl<T: Component> Events<T> {
         };
         trace!("Events::send() -> id: {}", event_id);
 
-        let event_instance = EventInstance { event_id, event };
-
-        match self.state {
-            State::A => self.events_a.push(event_instance),
-            State::B => self.events_b.push(event_instance),
+        if !self.subscribers.is_empty() {
+            self.subscribers.retain(|s| !s.sender.is_closed());
+            publish(&self.subscribers, &event);
         }
-
+        self.events.push_back(EventInstance { event_id, event });
         self.event_count += 1;
     }
 
@@ -192,21 +274,32 @@ impl<T: Component> Events<T> {
         }
     }
 
-    /// Swaps the event buffers and clears the oldest event buffer. In general, this should be
+    /// 开始新的一帧，并按保留策略清除过期的事件. In general, this should be
     /// called once per frame/update.
     pub fn update(&mut self) {
-        match self.state {
-            State::A => {
-                self.events_b.clear();
-                self.state = State::B;
-                self.b_start_event_count = self.event_count;
+        self.frame_starts.push_back(self.event_count);
+        let keep = match self.retention {
+            EventRetention::Frames(frames) => {
+                while self.frame_starts.len() > frames.max(1) {
+                    self.frame_starts.pop_front();
+                }
+                self.frame_starts[0]
             }
-            State::B => {
-                self.events_a.clear();
-                self.state = State::A;
-                self.a_start_event_count = self.event_count;
+            EventRetention::UntilConsumed => {
+                while self.frame_starts.len() > 1 {
+                    self.frame_starts.pop_front();
+                }
+                let mut readers = self.readers.lock();
+                readers.retain(|r| r.strong_count() > 0);
+                readers
+                    .iter()
+                    .filter_map(|r| r.upgrade())
+                    .map(|r| r.load(Ordering::Relaxed))
+                    .min()
+                    .unwrap_or(self.event_count)
             }
-        }
+        };
+        self.remove_before(keep);
     }
 
     /// A system that calls [`Events::update`] once per frame.
@@ -216,41 +309,27 @@ impl<T: Component> Events<T> {
 
     #[inline]
     fn reset_start_event_count(&mut self) {
-        self.a_start_event_count = self.event_count;
-        self.b_start_event_count = self.event_count;
+        self.frame_starts.clear();
+        self.frame_starts.push_back(self.event_count);
     }
 
     /// Removes all events.
     #[inline]
     pub fn clear(&mut self) {
         self.reset_start_event_count();
-        self.events_a.clear();
-        self.events_b.clear();
+        self.events.clear();
     }
 
     /// Returns true if there are no events in this collection.
     #[inline]
     pub fn is_empty(&self) -> bool {
-        self.events_a.is_empty() && self.events_b.is_empty()
+        self.events.is_empty()
     }
 
     /// Creates a draining iterator that removes all events.
     pub fn drain(&mut self) -> impl Iterator<Item = T> + '_ {
         self.reset_start_event_count();
-
-        let map = |i: EventInstance<T>| i.event;
-        match self.state {
-            State::A => self
-                .events_b
-                .drain(..)
-                .map(map)
-                .chain(self.events_a.drain(..).map(map)),
-            State::B => self
-                .events_a
-                .drain(..)
-                .map(map)
-                .chain(self.events_b.drain(..).map(map)),
-        }
+        self.events.drain(..).map(|i| i.event)
     }
 
     /// Iterates over events that happened since the last "update" call.
@@ -260,33 +339,13 @@ impl<T: Component> Events<T> {
     /// If events happen outside that window, they will not be handled. For example, any events that
     /// happen after this call and before the next `update()` call will be dropped.
     pub fn iter_current_update_events(&self) -> impl DoubleEndedIterator<Item = &T> {
-        match self.state {
-            State::A => self.events_a.iter().map(map_instance_event),
-            State::B => self.events_b.iter().map(map_instance_event),
-        }
+        let start = self.index_of(*self.frame_starts.back().unwrap());
+        self.events.range(start..).map(map_instance_event)
     }
 
     /// Determines how many events are in the reader after the given `last_event_count` parameter
     fn event_reader_len(&self, last_event_count: usize) -> usize {
-        let a_count = if last_event_count <= self.a_start_event_count {
-            self.events_a.len()
-        } else {
-            self.events_a
-                .len()
-                .checked_sub(last_event_count - self.a_start_event_count)
-                .unwrap_or_default()
-        };
-
-        let b_count = if last_event_count <= self.b_start_event_count {
-            self.events_b.len()
-        } else {
-            self.events_b
-                .len()
-                .checked_sub(last_event_count - self.b_start_event_count)
-                .unwrap_or_default()
-        };
-
-        a_count + b_count
+        self.events.len() - self.index_of(last_event_count)
     }
 }
 
@@ -295,8 +354,11 @@ impl<T> std::iter::Extend<T> for Events<T> {
     where
         I: IntoIterator<Item = T>,
     {
+        self.subscribers.retain(|s| !s.sender.is_closed());
+        let subscribers = &self.subscribers;
         let mut event_count = self.event_count;
         let events = iter.into_iter().map(|event| {
+            publish(subscribers, &event);
             let event_id = EventId {
                 id: event_count,
                 _marker: PhantomData,
@@ -305,10 +367,7 @@ impl<T> std::iter::Extend<T> for Events<T> {
             EventInstance { event_id, event }
         });
 
-        match self.state {
-            State::A => self.events_a.extend(events),
-            State::B => self.events_b.extend(events),
-        }
+        self.events.extend(events);
 
         trace!(
             "Events::extend() -> ids: ({}..{})",
@@ -323,6 +382,14 @@ impl<T> std::iter::Extend<T> for Events<T> {
 pub struct EventReader<'w, T: Component> {
     last_event_count: Local<'w, (usize, PhantomData<T>)>,
     events: Res<'w, Events<T>>,
+    cursor: Share<AtomicUsize>,
+}
+
+impl<'w, T: Component> Drop for EventReader<'w, T> {
+    fn drop(&mut self) {
+        // 同步读取位置，供EventRetention::UntilConsumed判断事件是否已被读取
+        self.cursor.store(self.last_event_count.0, Ordering::Relaxed);
+    }
 }
 
 impl<'w, T: Component> SystemParam for EventReader<'w, T> {
@@ -337,6 +404,8 @@ impl<'w, T: Component> SystemParam for EventReader<'w, T> {
 #[doc(hidden)]
 pub struct EventReaderState<TSystemParamState, T> {
     state: TSystemParamState,
+    cursor: Share<AtomicUsize>,
+    registered: bool,
     marker: std::marker::PhantomData<fn() -> T>,
 }
 
@@ -356,6 +425,8 @@ unsafe impl<TSystemParamState: SystemParamState, T: Component> SystemParamState
                 system_state,
                 TSystemParamState::default_config(),
             ),
+            cursor: Share::new(AtomicUsize::new(0)),
+            registered: false,
             marker: PhantomData,
         }
     }
@@ -385,10 +456,17 @@ impl<'w, 's, T: Component> SystemParamFetch<'w, 's>
         world: &'w World,
         change_tick: u32,
     ) -> Self::Item {
+        let events: Res<'static, Events<T>> = <<Res<Events<T> >as SystemParam> ::Fetch as SystemParamFetch> ::get_param(&mut state.state.1,system_state,world,change_tick);
+        // 资源在系统初始化时可能还不存在，因此在第一次取参数时注册读取位置
+        if !state.registered {
+            events.add_reader(&state.cursor);
+            state.registered = true;
+        }
         Self::Item {
                 last_event_count: <<Local<(usize,PhantomData<T>)> as SystemParam> ::Fetch as SystemParamFetch> ::get_param(transmute(&mut state.state.0), system_state, world, change_tick),
                 
-                events: <<Res<Events<T> >as SystemParam> ::Fetch as SystemParamFetch> ::get_param(&mut state.state.1,system_state,world,change_tick),
+                events,
+                cursor: state.cursor.clone(),
         }
     }
 }
@@ -558,27 +636,11 @@ fn internal_event_reader<'a, T>(
     last_event_count: &'a mut usize,
     events: &'a Events<T>,
 ) -> impl DoubleEndedIterator<Item = (&'a T, EventId<T>)> {
-    // if the reader has seen some of the events in a buffer, find the proper index offset.
-    // otherwise read all events in the buffer
-    let a_index = if *last_event_count > events.a_start_event_count {
-        *last_event_count - events.a_start_event_count
-    } else {
-        0
-    };
-    let b_index = if *last_event_count > events.b_start_event_count {
-        *last_event_count - events.b_start_event_count
-    } else {
-        0
-    };
-    let a = events.events_a.get(a_index..).unwrap_or_default();
-    let b = events.events_b.get(b_index..).unwrap_or_default();
-    let unread_count = a.len() + b.len();
-    *last_event_count = events.event_count - unread_count;
-    let iterator = match events.state {
-        State::A => b.iter().chain(a.iter()),
-        State::B => a.iter().chain(b.iter()),
-    };
-    iterator
+    // if the reader has seen some of the events, find the proper index offset.
+    // otherwise read all events
+    let unread = events.events.range(events.index_of(*last_event_count)..);
+    *last_event_count = events.event_count - unread.len();
+    unread
         .map(map_instance_event_with_id)
         .inspect(move |(_, id)| *last_event_count = (id.id + 1).max(*last_event_count))
 }
@@ -796,6 +858,37 @@ mod tests {
         assert!(reader.is_empty(&events));
     }
 
+    #[test]
+    fn test_events_retention() {
+        let mut events = Events::<TestEvent>::with_retention(EventRetention::Frames(3));
+        events.send(TestEvent { i: 0 });
+        let mut reader = events.get_reader();
+        events.update();
+        events.update();
+        assert_eq!(reader.len(&events), 1);
+        events.update();
+        assert!(reader.is_empty(&events));
+
+        let mut events = Events::<TestEvent>::with_retention(EventRetention::UntilConsumed);
+        let mut reader = events.get_reader();
+        let cursor = Share::new(AtomicUsize::new(0));
+        events.add_reader(&cursor);
+        events.send(TestEvent { i: 0 });
+        events.send(TestEvent { i: 1 });
+        for _ in 0..3 {
+            events.update();
+        }
+        assert_eq!(reader.len(&events), 2);
+        cursor.store(1, Ordering::Relaxed);
+        events.update();
+        assert_eq!(get_events(&events, &mut reader), vec![TestEvent { i: 1 }]);
+
+        // 没有读取器时，更新会清除之前的事件
+        drop(cursor);
+        events.update();
+        assert!(events.is_empty());
+    }
+
     #[derive(Clone, PartialEq, Debug, Default)]
     struct EmptyTestEvent;
 
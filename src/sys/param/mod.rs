@@ -3,18 +3,22 @@ pub mod event;
 pub mod interface;
 pub mod local;
 pub mod query;
+pub mod query_all;
 pub mod res;
 pub mod tick;
 pub mod world;
 pub mod param_set;
 pub mod entities;
+pub mod removed;
 
-pub use command::{Command, Commands, EntityCommands};
+pub use command::{BundleCommands, Command, Commands, EntityCommands, MigrateCommands, ResourceCommands};
 pub use interface::*;
 pub use local::Local;
 pub use query::Query;
+pub use query_all::QueryAll;
 pub use res::{Res, ResMut};
 pub use tick::*;
 pub use param_set::ParamSet;
 pub use entities::*;
+pub use removed::RemovedComponents;
 // pub use command::{Commands, EntityCommands};
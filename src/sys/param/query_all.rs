@@ -0,0 +1,147 @@
+use std::intrinsics::transmute;
+
+use crate::{
+    entity::Entity,
+    query::{
+        Fetch, FilterFetch, QueryAllIter, QueryAllState, ReadOnlyFetch, WorldQuery,
+    },
+	sys::param::interface::{SystemParam, SystemParamFetch, SystemParamState, assert_component_access_compatibility, NotApply},
+	sys::system::interface::SystemState,
+	world::World, WorldInner,
+};
+
+/// 跨原型的查询，匹配所有组件满足`Q`和`F`的原型
+/// 原型世代变化时（新建原型、原型注册了新组件），会在system运行前重新检查原型
+/// 创建system时已匹配的所有原型的访问合并为system的访问，派发器据此按读写关系建立依赖
+/// 注意：dispatcher的依赖在构建时确定，构建之后才匹配的原型，其数据访问不会参与依赖分析
+pub struct QueryAll<'world, 'state, Q: WorldQuery, F: WorldQuery = ()>
+where
+    F::Fetch: FilterFetch,
+{
+	pub(crate) _world: World, // 抓住World， 因为Query可能在异步块中，需要保证WorldInner不被释放
+	pub(crate) world_ref: &'world WorldInner,
+    pub(crate) state: &'state QueryAllState<Q, F>,
+}
+
+impl<'w, 's, Q: WorldQuery, F: WorldQuery> QueryAll<'w, 's, Q, F>
+where
+    F::Fetch: FilterFetch,
+{
+    /// Creates a new query.
+    ///
+    /// # Safety
+    ///
+    /// This will create a query that could violate memory safety rules. Make sure that this is only
+    /// called in ways that ensure the queries have unique mutable access.
+    #[inline]
+    pub(crate) unsafe fn new(
+        world: &'w World,
+		state: &'s QueryAllState<Q, F>,
+    ) -> Self {
+        Self {
+            _world: world.clone(),
+			state,
+			world_ref: &*(&**world as *const WorldInner),
+        }
+    }
+
+    /// Returns an [`Iterator`] over the query results.
+    ///
+    /// This can only be called for read-only queries, see [`Self::iter_mut`] for write-queries.
+    #[inline]
+    pub fn iter(&self) -> QueryAllIter<'_, '_, Q, F>
+    where
+        Q::Fetch: ReadOnlyFetch,
+    {
+        // SAFE: system runs without conflicts with other systems.
+        unsafe { self.state.iter_unchecked_manual(self.world_ref) }
+    }
+
+    /// Returns an [`Iterator`] over the query results.
+    #[inline]
+    pub fn iter_mut(&mut self) -> QueryAllIter<'_, '_, Q, F> {
+        // SAFE: system runs without conflicts with other systems.
+        unsafe { self.state.iter_unchecked_manual(self.world_ref) }
+    }
+
+    /// Gets the query result for the given [`Entity`].
+    #[inline]
+    pub fn get(&self, entity: Entity) -> Option<<Q::Fetch as Fetch<'_>>::Item>
+    where
+        Q::Fetch: ReadOnlyFetch,
+    {
+        // SAFE: system runs without conflicts with other systems.
+        unsafe { self.state.get_unchecked_manual(self.world_ref, entity) }
+    }
+
+    /// Gets the query result for the given [`Entity`].
+    #[inline]
+    pub fn get_mut(&mut self, entity: Entity) -> Option<<Q::Fetch as Fetch<'_>>::Item> {
+        // SAFE: system runs without conflicts with other systems.
+        unsafe { self.state.get_unchecked_manual(self.world_ref, entity) }
+    }
+}
+
+impl<'w, 's, Q: WorldQuery + 'static, F: WorldQuery + 'static> SystemParam for QueryAll<'w, 's, Q, F>
+where
+    F::Fetch: FilterFetch,
+{
+    type Fetch = QueryAllState<Q, F>;
+}
+
+// SAFE: Relevant query ArchetypeComponentId access is applied to SystemState. If
+// this QueryAllState conflicts with any prior access, a panic will occur.
+unsafe impl<Q: WorldQuery + 'static, F: WorldQuery + 'static> SystemParamState for QueryAllState<Q, F>
+where
+    F::Fetch: FilterFetch,
+{
+    type Config = ();
+
+    fn init(world: &mut World, system_state: &mut SystemState, _config: Self::Config) -> Self {
+        let state = QueryAllState::new(world);
+        assert_component_access_compatibility(
+            &system_state.name,
+            std::any::type_name::<Q>(),
+            std::any::type_name::<F>(),
+            &system_state.archetype_component_access,
+            &state.archetype_component_access,
+            world,
+        );
+
+		// 所有已匹配原型的访问，合并到系统的访问集中
+        system_state
+            .archetype_component_access.combined_access_mut()
+            .extend(state.archetype_component_access.access());
+
+        state
+    }
+
+    fn default_config() {}
+
+	fn apply(&mut self, world: &mut World) {
+		(*self).apply(world)
+	}
+}
+
+impl<'w, 's, Q: WorldQuery + 'static, F: WorldQuery + 'static> SystemParamFetch<'w, 's> for QueryAllState<Q, F>
+where
+    F::Fetch: FilterFetch,
+{
+    type Item = QueryAll<'static, 'static, Q, F>;
+
+    #[inline]
+    unsafe fn get_param(
+        state: &'s mut Self,
+        system_state: &SystemState,
+        world: &'w World,
+        change_tick: u32,
+    ) -> Self::Item {
+		// 原型世代变化时，重新检查原型
+		state.update_archetypes(world);
+		state.setting(world, system_state.last_change_tick, change_tick);
+
+        transmute(QueryAll::new(world, state))
+    }
+}
+
+impl<Q: WorldQuery, F: WorldQuery> NotApply for QueryAllState<Q, F> where F::Fetch: FilterFetch {}
@@ -0,0 +1,92 @@
+//! 被删除的组件
+
+use std::{any::type_name, marker::PhantomData, sync::Arc};
+
+use pi_share::cell::TrustCell;
+
+use crate::{
+	archetype::ArchetypeIdent,
+	component::Component,
+	entity::Id,
+	monitor::{Delete, Event, Listener},
+	sys::param::interface::{SystemParam, SystemParamFetch, SystemParamState, NotApply},
+	sys::system::interface::SystemState,
+	world::World,
+};
+
+/// 自系统上次运行以来，失去了组件T的实体（组件被移除或实体被销毁）
+/// 同一实体多次删除组件，会多次出现
+pub struct RemovedComponents<'s, A: ArchetypeIdent, T: Component> {
+	removed: &'s [Id<A>],
+	mark: PhantomData<T>,
+}
+
+impl<'s, A: ArchetypeIdent, T: Component> RemovedComponents<'s, A, T> {
+	/// 迭代失去组件的实体
+	pub fn iter(&self) -> impl Iterator<Item = Id<A>> + 's {
+		self.removed.iter().cloned()
+	}
+
+	pub fn len(&self) -> usize {
+		self.removed.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.removed.is_empty()
+	}
+}
+
+impl<'s, A: ArchetypeIdent, T: Component> SystemParam for RemovedComponents<'s, A, T> {
+	type Fetch = RemovedComponentsState<A, T>;
+}
+
+/// The [`SystemParamState`] of [`RemovedComponents`].
+pub struct RemovedComponentsState<A: ArchetypeIdent, T: Component> {
+	// 监听器收集的实体
+	list: Arc<TrustCell<Vec<Id<A>>>>,
+	// 本次运行可见的实体
+	removed: Vec<Id<A>>,
+	mark: PhantomData<T>,
+}
+
+// SAFE: 只访问参数自身的数据
+unsafe impl<A: ArchetypeIdent, T: Component> SystemParamState for RemovedComponentsState<A, T> {
+	type Config = ();
+
+	fn init(world: &mut World, _system_state: &mut SystemState, _config: Self::Config) -> Self {
+		let list: Arc<TrustCell<Vec<Id<A>>>> = Arc::new(TrustCell::new(Vec::new()));
+		let l = list.clone();
+		world.add_component_listener::<Delete, A, T>(Listener::new(type_name::<Self>(), move |e: Event| {
+			l.borrow_mut().push(Id(e.id.local(), PhantomData));
+		}));
+		Self {
+			list,
+			removed: Vec::new(),
+			mark: PhantomData,
+		}
+	}
+
+	fn default_config() {}
+}
+
+impl<'w, 's, A: ArchetypeIdent, T: Component> SystemParamFetch<'w, 's> for RemovedComponentsState<A, T> {
+	type Item = RemovedComponents<'static, A, T>;
+
+	#[inline]
+	unsafe fn get_param(
+		state: &'s mut Self,
+		_system_state: &SystemState,
+		_world: &'w World,
+		_change_tick: u32,
+	) -> Self::Item {
+		// 取走上次运行以来收集的实体
+		state.removed.clear();
+		std::mem::swap(&mut state.removed, &mut *state.list.borrow_mut());
+		RemovedComponents {
+			removed: std::mem::transmute::<&[Id<A>], &'static [Id<A>]>(state.removed.as_slice()),
+			mark: PhantomData,
+		}
+	}
+}
+
+impl<A: ArchetypeIdent, T: Component> NotApply for RemovedComponentsState<A, T> {}
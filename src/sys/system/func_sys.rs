@@ -2,6 +2,7 @@ use std::any::type_name;
 
 use crate::{
     archetype::ArchetypeComponentId,
+    component::check_tick,
     query::Access,
     sys::param::interface::{SystemParamState, SystemParam, SystemParamFetch},
     sys::system::interface::{System, SystemState, IntoSystem, InputMarker},
@@ -170,12 +171,8 @@ where
     }
 
     #[inline]
-    fn check_change_tick(&mut self, _change_tick: u32) {
-        // check_system_change_tick(
-        //     &mut self.system_state.last_change_tick,
-        //     change_tick,
-        //     self.system_state.name.as_ref(),
-        // );
+    fn check_change_tick(&mut self, change_tick: u32) {
+        check_tick(&mut self.system_state.last_change_tick, change_tick);
     }
 }
 
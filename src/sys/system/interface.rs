@@ -5,7 +5,7 @@ use crate::sys::param::SystemParamFetch;
 use crate::world::World;
 use crate::archetype::ArchetypeComponentId;
 use crate::query::{Access, FilteredAccessSet};
-use pi_share::ThreadSend;
+use pi_share::{ThreadSend, cell::TrustCell};
 
 /// An ECS system that can be added to a [Schedule](crate::schedule::Schedule)
 ///
@@ -51,6 +51,20 @@ pub trait System: ThreadSend + 'static {
     fn check_change_tick(&mut self, change_tick: u32);
 }
 
+/// 共享的系统，世界定期通过该接口截断系统过旧的节拍
+pub trait CheckChangeTick {
+    fn check_change_tick(&self, change_tick: u32);
+}
+
+impl<S: System> CheckChangeTick for TrustCell<S> {
+    fn check_change_tick(&self, change_tick: u32) {
+        // 系统正在运行（在系统内部检查节拍），跳过该系统
+        if let Ok(mut sys) = self.try_borrow_mut() {
+            sys.check_change_tick(change_tick);
+        }
+    }
+}
+
 /// 数据状态
 pub struct DataState<PramState> {
 	system_state: SystemState,
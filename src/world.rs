@@ -9,15 +9,21 @@ use pi_share::cell::TrustCell;
 use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
 
-use crate::archetype::{ArchetypeComponentId, ArchetypeId, ArchetypeIdent, Archetypes, ResourceType, EntityComponentType};
-use crate::component::{Component, ComponentId, Components};
+use crate::archetype::{ArchetypeComponentId, ArchetypeId, ArchetypeIdent, Archetypes, ResourceType, EntityComponentType, DynamicArchetype};
+use crate::bundle::Bundle;
+use crate::component::{check_tick, Component, ComponentId, Components, CHECK_TICK_THRESHOLD};
 use crate::entity::{Entities, Entity, Id};
-use crate::monitor::{ListenType, Listener, Apply};
+use crate::monitor::{ListenType, Listener, Apply, Notify, NotifyImpl, OldListener, OldListenType, ListenerError};
 use crate::prelude::{FilterFetch, FilteredAccessSet};
-use crate::query::{QueryState, WorldQuery};
+use crate::query::{QueryAllState, QueryState, WorldQuery};
 use crate::resource::{Resource, ResourceId};
-use crate::storage::{Local, LocalVersion, SecondaryMap};
+use crate::snapshot::CloneRegistry;
+use crate::subscribe::SubscriptionListener;
+use crate::storage::{Key, Local, LocalVersion, Offset, SecondaryMap};
+use crate::sys::param::event::{EventRetention, Events};
 use crate::sys::param::res::ResState;
+use crate::sys::system::CheckChangeTick;
+use pi_share::{Share, ShareWeak};
 
 /// 世界
 #[derive(Clone)]
@@ -51,6 +57,21 @@ impl World {
         QueryState::new(self)
     }
 
+    /// 查询所有满足条件的原型（包括动态原型）
+    #[inline]
+    pub fn query_all<Q: WorldQuery>(&mut self) -> QueryAllState<Q, ()> {
+        QueryAllState::new(self)
+    }
+
+    /// 带过滤 的 查询所有满足条件的原型
+    #[inline]
+    pub fn query_all_filtered<Q: WorldQuery, F: WorldQuery>(&mut self) -> QueryAllState<Q, F>
+    where
+        F::Fetch: FilterFetch,
+    {
+        QueryAllState::new(self)
+    }
+
     /// 取 res
     pub fn res<T: Component>(&self) -> ResState<T> {
         let component_id = self.get_resource_id::<T>();
@@ -102,14 +123,31 @@ pub struct WorldInner {
 
     /// 该字段描述了监听器监听的组件所访问的数据id
     pub(crate) listener_access:
-        SecondaryMap<ArchetypeComponentId, Vec<FilteredAccessSet<ArchetypeComponentId>>>,
+        SecondaryMap<ArchetypeComponentId, Vec<(Listener, FilteredAccessSet<ArchetypeComponentId>)>>,
 
-	pub(crate) listeners: Vec<Arc<dyn Apply>>,
+	pub(crate) listeners: Vec<ShareWeak<dyn Apply>>,
+    /// 通过`add_event`注册的事件集合的更新函数
+    pub(crate) event_updates: Vec<(TypeId, EventUpdate)>,
 
     pub(crate) change_tick: AtomicU32,
     pub(crate) last_change_tick: u32,
+    /// 上次检查节拍时的节拍
+    pub(crate) last_check_tick: u32,
+    /// 需要定期检查节拍的系统
+    pub(crate) systems: Vec<ShareWeak<dyn CheckChangeTick>>,
 
     pub(crate) query_generator: usize,
+
+    /// 世界级别的监听器（如世界恢复）
+    pub(crate) world_listners: NotifyImpl,
+    /// 组件事件流的监听器
+    pub(crate) subscriptions: Vec<SubscriptionListener>,
+    /// 可克隆的组件、资源类型（用于快照）
+    pub(crate) clone_registry: CloneRegistry,
+
+    /// 可序列化的组件、资源类型
+    #[cfg(feature = "serde")]
+    pub(crate) type_registry: crate::serialize::TypeRegistry,
 }
 
 impl WorldInner {
@@ -120,12 +158,26 @@ impl WorldInner {
             archetypes: Archetypes::new(),
             listener_access: SecondaryMap::with_capacity(0),
 			listeners: Vec::new(),
+            event_updates: Vec::new(),
             change_tick: AtomicU32::new(1),
             last_change_tick: 1,
+            last_check_tick: 1,
+            systems: Vec::new(),
             query_generator: 0,
+            world_listners: NotifyImpl::default(),
+            subscriptions: Vec::new(),
+            clone_registry: CloneRegistry::default(),
+            #[cfg(feature = "serde")]
+            type_registry: Default::default(),
         }
     }
 
+    /// 已注册的组件信息
+    #[inline]
+    pub fn components(&self) -> &Components {
+        &self.components
+    }
+
     #[inline]
     pub fn gen_query_id(&mut self) -> usize {
         self.query_generator += 1;
@@ -148,6 +200,13 @@ impl WorldInner {
         ResourceRef(component_id, PhantomData)
     }
 
+    /// 移除资源，会发出资源的删除事件
+    #[inline]
+    pub fn remove_resource<T: Resource>(&mut self) -> Option<T> {
+        let id = *self.components.get_resource_id::<T>()?;
+        unsafe { self.archetypes.remove_resource(id) }
+    }
+
 	#[inline]
     pub fn get_resource_ref<T: Resource>(&self) -> Option<ResourceRef<T>> {
         match self.components.get_resource_id::<T>() {
@@ -225,6 +284,43 @@ impl WorldInner {
         }
     }
 
+    /// 批量创建实体，每个实体插入一组组件
+	/// 组件类型只注册一次，并预先为实体、组件容器预留容量；全部实体创建完成后，再统一发出实体和组件的创建事件
+    pub fn spawn_batch<A: ArchetypeIdent, B: Bundle>(&mut self, iter: impl IntoIterator<Item = B>) -> Vec<Id<A>> {
+        let archetype_id = self.archetypes.get_or_create_archetype::<A>();
+        let change_tick = self.read_change_tick();
+        let iter = iter.into_iter();
+        let (additional, _) = iter.size_hint();
+
+        let containers = B::containers::<A>(&mut self.archetypes, &mut self.components, archetype_id, additional);
+        let archetype = &mut self.archetypes[archetype_id];
+        archetype.entities.reserve(additional);
+        let mut locals = Vec::with_capacity(additional);
+        for bundle in iter {
+            let local = archetype.entities.insert_no_notify();
+            bundle.insert(&containers, local, change_tick);
+            locals.push(local);
+        }
+
+        for local in locals.iter() {
+            let entity = Entity::new(archetype_id, *local);
+            archetype.entities.entity_listners.create_event(entity);
+            B::create_event(&containers, entity);
+        }
+        locals.into_iter().map(|local| unsafe { Id::new(local) }).collect()
+    }
+
+    /// 创建动态实体
+	/// 动态实体所在的原型由其拥有的组件集合决定，初始为空集合
+	/// 通过insert_component_dynamic、remove_component_dynamic增删组件时，实体会移动到对应组件集合的原型中
+    pub fn spawn_dynamic(&mut self) -> Entity {
+        let archetype_id = match self.archetypes.get_id_by_components(&[]) {
+            Some(r) => r,
+            None => self.archetypes.create_archetype_by_components(Vec::new()),
+        };
+        self.archetypes[archetype_id].create_entity()
+    }
+
     /// 删除实体
     #[inline]
     pub fn despawn(&mut self, entity: Entity) {
@@ -232,9 +328,15 @@ impl WorldInner {
     }
 
     /// 为实体插入组件
+	/// 实体所在的原型必须已注册该组件，动态实体请使用insert_component_dynamic
     pub fn insert_component<C: Component>(&mut self, entity: Entity, value: C) {
         let change_tick = self.read_change_tick();
         let id = self.components.get_or_insert_id::<C>();
+		debug_assert!(
+			!self.archetypes[entity.archetype_id()].is_dynamic() || self.archetypes[entity.archetype_id()].contains(id),
+			"insert_component: dynamic archetype does not contain {}, use insert_component_dynamic",
+			type_name::<C>()
+		);
         self.archetypes[entity.archetype_id()].insert_component(
             entity.local(),
             value,
@@ -243,13 +345,108 @@ impl WorldInner {
         );
     }
 
-    /// 为删除组件组件
+    /// 为实体插入组件，返回插入后的实体
+	/// 若实体在动态原型中，且原型不包含该组件，实体会被移动到新的原型，返回的实体与传入的实体不同，传入的实体随之失效
+    pub fn insert_component_dynamic<C: Component>(&mut self, entity: Entity, value: C) -> Entity {
+		let id = self.components.get_or_insert_id::<C>();
+		let archetype = &self.archetypes[entity.archetype_id()];
+		if !archetype.is_dynamic() || archetype.contains(id) {
+			self.insert_component(entity, value);
+			return entity;
+		}
+		let change_tick = self.read_change_tick();
+
+		let mut components = archetype.component_ids().to_vec();
+		components.push(id);
+		components.sort();
+		let dst = match self.archetypes.get_id_by_components(&components) {
+			Some(r) => r,
+			None => {
+				let dst = self.create_dynamic_archetype(components, entity.archetype_id());
+				let archetype_component_id = self.archetypes.archetype_component_grow(type_name::<EntityComponentType<DynamicArchetype, C>>(), true);
+				self.archetypes[dst].register_component_type::<C>(id, Local::new(archetype_component_id));
+				dst
+			}
+		};
+		let local = self.archetypes.move_entity(entity.archetype_id(), entity.local(), dst);
+		self.archetypes[dst].insert_component(local, value, id, change_tick);
+		Entity::new(dst, local)
+    }
+
+    /// 为实体插入组件集合，返回插入后的实体
+	/// 与逐个调用insert_component_dynamic相同，实体在动态原型中时，可能被移动到新的原型
+    pub fn insert_bundle<B: Bundle>(&mut self, entity: Entity, bundle: B) -> Entity {
+        bundle.insert_world(self, entity)
+    }
+
+    /// 为实体删除组件
+	/// 实体留在原来的原型中，动态实体请使用remove_component_dynamic
     #[inline]
     pub fn remove_component<C: Component>(&mut self, entity: Entity) {
         let id = self.components.get_or_insert_id::<C>();
         self.archetypes[entity.archetype_id()].remove_component(entity.local(), id);
     }
 
+    /// 为实体删除组件，返回删除后的实体
+	/// 若实体在动态原型中，实体会被移动到去掉该组件后的组件集合对应的原型，返回的实体与传入的实体不同，传入的实体随之失效
+    pub fn remove_component_dynamic<C: Component>(&mut self, entity: Entity) -> Entity {
+        let id = self.components.get_or_insert_id::<C>();
+		let archetype = &self.archetypes[entity.archetype_id()];
+		if !archetype.is_dynamic() || !archetype.contains(id) {
+			self.remove_component::<C>(entity);
+			return entity;
+		}
+
+		let mut components: Vec<ComponentId> = archetype.component_ids().iter().filter(|r| **r != id).cloned().collect();
+		components.sort();
+		let dst = match self.archetypes.get_id_by_components(&components) {
+			Some(r) => r,
+			None => self.create_dynamic_archetype(components, entity.archetype_id()),
+		};
+		// 先在原来的原型中删除组件（发出删除事件），再移动实体
+		self.archetypes[entity.archetype_id()].remove_component(entity.local(), id);
+		let local = self.archetypes.move_entity(entity.archetype_id(), entity.local(), dst);
+		Entity::new(dst, local)
+    }
+
+	/// 将实体从原型`From`迁移到原型`To`，返回迁移后的实体id
+	/// 两个原型都注册了的组件会被移动到新原型中，不产生组件和实体的创建、删除事件；
+	/// `To`中没有的组件被删除，并发出这些组件的删除事件
+	/// 迁移完成后，通过`To`的实体监听器发出一次迁移事件（`EventType::Migrate`，携带迁移前的实体）
+	/// 实体不存在时，返回None
+	pub fn migrate<From: ArchetypeIdent, To: ArchetypeIdent>(&mut self, id: Id<From>) -> Option<Id<To>> {
+		let src = self.archetypes.get_or_create_archetype::<From>();
+		let dst = self.archetypes.get_or_create_archetype::<To>();
+		if !self.archetypes[src].entities.contains(id.0) {
+			return None;
+		}
+		if src == dst {
+			return Some(unsafe { Id::new(id.0) });
+		}
+
+		let local = self.archetypes.move_entity(src, id.0, dst);
+		let (from, to) = (Entity::new(src, id.0), Entity::new(dst, local));
+		self.archetypes[dst].entities.entity_listners.migrate_event(to, from);
+		Some(unsafe { Id::new(local) })
+	}
+
+	/// 创建动态原型，并为其注册`components`中的组件容器
+	/// 容器类型从`template`原型中取得，不在`template`中的组件不会被注册，需要调用者自行注册
+	fn create_dynamic_archetype(&mut self, components: Vec<ComponentId>, template: ArchetypeId) -> ArchetypeId {
+		let dst = self.archetypes.create_archetype_by_components(components.clone());
+		let template = &self.archetypes[template];
+		let containers: Vec<_> = components.iter()
+			.filter(|id| template.contains(**id))
+			.map(|id| (*id, unsafe { template.get_component(*id) }.new_container(dst)))
+			.collect();
+		for (id, container) in containers {
+			let name = self.components.infos[id.offset()].dynamic_name;
+			let archetype_component_id = self.archetypes.archetype_component_grow(name, true);
+			self.archetypes[dst].register_component_container(id, container, Local::new(archetype_component_id));
+		}
+		dst
+	}
+
     /// 添加组件监听器
     pub fn add_component_listener<T: ListenType, A: ArchetypeIdent, C: Component>(
         &mut self,
@@ -283,6 +480,67 @@ impl WorldInner {
         self.archetypes.add_entity_listener::<T, A>(listener);
     }
 
+    /// 添加世界监听器
+    #[inline]
+    pub fn add_world_listener<T: ListenType>(&mut self, listener: Listener) {
+        T::add(&self.world_listners, listener);
+        self.world_listners.set_source("World", &self.archetypes.listener_depth);
+    }
+
+    /// 添加接收旧值的组件监听器
+    pub fn add_component_old_listener<T: OldListenType, A: ArchetypeIdent, C: Component>(
+        &mut self,
+        listener: OldListener<C>,
+    ) {
+		let component_id = self.components.get_or_insert_id::<C>();
+        self.archetypes
+            .add_component_old_listener::<T, A, C>(listener, component_id)
+    }
+
+    /// 移除接收旧值的组件监听器
+    pub fn remove_component_old_listener<T: OldListenType, A: ArchetypeIdent, C: Component>(
+        &mut self,
+        token: &Listener,
+    ) {
+		if let Some(component_id) = self.components.get_id(TypeId::of::<C>()) {
+			self.archetypes
+				.remove_component_old_listener::<T, A, C>(token, component_id)
+		}
+    }
+
+    /// 移除组件监听器
+    pub fn remove_component_listener<T: ListenType, A: ArchetypeIdent, C: Component>(
+        &mut self,
+        listener: &Listener,
+    ) {
+		if let Some(component_id) = self.components.get_id(TypeId::of::<C>()) {
+			self.archetypes
+				.remove_component_listener::<T, A, C>(listener, component_id)
+		}
+    }
+
+    /// 移除资源监听器
+    pub fn remove_resource_listener<T: ListenType, R: Component>(&mut self, listener: &Listener) {
+		if let Some(component_id) = self.components.get_resource_id::<R>() {
+			self.archetypes.resources.remove_listener::<T>(*component_id, listener);
+		}
+    }
+
+    /// 移除实体监听器
+    #[inline]
+    pub fn remove_entity_listener<T: ListenType, A: ArchetypeIdent>(
+        &mut self,
+        listener: &Listener,
+    ) {
+        self.archetypes.remove_entity_listener::<T, A>(listener);
+    }
+
+    /// 移除世界监听器
+    #[inline]
+    pub fn remove_world_listener<T: ListenType>(&mut self, listener: &Listener) {
+        T::remove(&self.world_listners, listener);
+    }
+
     /// 取到原型
     #[inline]
     pub fn archetypes(&self) -> &Archetypes {
@@ -331,6 +589,22 @@ impl WorldInner {
         self.last_change_tick
     }
 
+    /// 设置监听器级联的最大深度（监听器中修改数据，触发其它监听器，称为级联），只影响本世界
+    /// 超过该深度时跳过该监听器，错误记录在世界中，通过take_listener_errors取出
+    pub fn set_max_listener_depth(&self, depth: usize) {
+        self.archetypes.listener_depth.set(depth);
+    }
+
+    /// 监听器级联的最大深度，默认为DEFAULT_MAX_LISTENER_DEPTH
+    pub fn max_listener_depth(&self) -> usize {
+        self.archetypes.listener_depth.get()
+    }
+
+    /// 取出监听器级联中发生的错误（超过最大深度或形成循环），错误信息中包含级联链
+    pub fn take_listener_errors(&self) -> Vec<ListenerError> {
+        self.archetypes.listener_depth.take_errors()
+    }
+
     /// 节拍增加，返回增加前的节拍
     #[inline]
     pub fn increment_change_tick(&self) -> u32 {
@@ -346,12 +620,93 @@ impl WorldInner {
         self.last_change_tick = self.increment_change_tick();
     }
 
+    /// 注册需要定期检查节拍的系统，系统释放后自动移除
+    pub(crate) fn add_system_tick<S: CheckChangeTick + 'static>(&mut self, system: &Share<S>) {
+        let system: ShareWeak<S> = Share::downgrade(system);
+        self.systems.push(system);
+    }
+
+    /// 注册缓冲监听器，监听器释放后自动移除
+    pub(crate) fn add_apply<S: Apply + 'static>(&mut self, listener: &Share<S>) {
+        let listener: ShareWeak<S> = Share::downgrade(listener);
+        self.listeners.push(listener);
+    }
+
+    /// 将缓冲监听器积累的事件批量投递给监听器，并移除流已被丢弃的组件事件监听器
+    /// 由`World::arrange()`插入的整理节点自动调用
+    pub fn apply_listeners(&mut self) {
+        self.remove_closed_subscriptions();
+        self.listeners.retain(|l| l.strong_count() > 0);
+        let listeners: Vec<_> = self.listeners.iter().filter_map(|l| l.upgrade()).collect();
+        for l in listeners {
+            l.apply();
+        }
+    }
+
+    /// 注册事件类型：插入`Events<T>`资源，并由整理节点每帧调用一次`Events::update`
+    pub fn add_event<T: Component>(&mut self) {
+        self.add_event_with_retention::<T>(EventRetention::default());
+    }
+
+    /// 注册事件类型，并指定事件的保留策略
+    /// 重复注册时，只更新保留策略
+    pub fn add_event_with_retention<T: Component>(&mut self, retention: EventRetention) {
+        match self.get_resource_mut::<Events<T>>() {
+            Some(events) => events.set_retention(retention),
+            None => {
+                self.insert_resource(Events::<T>::with_retention(retention));
+            }
+        }
+        let id = TypeId::of::<Events<T>>();
+        if !self.event_updates.iter().any(|(r, _)| *r == id) {
+            self.event_updates.push((id, update_events::<T>));
+        }
+    }
+
+    /// 更新所有通过`add_event`注册的事件集合
+    /// 由`World::arrange()`插入的整理节点自动调用
+    pub fn update_events(&mut self) {
+        for (_, update) in self.event_updates.iter() {
+            update(self);
+        }
+    }
+
+    /// 距上次检查超过CHECK_TICK_THRESHOLD时，检查所有节拍
+    /// 由`World::arrange()`插入的整理节点自动调用
+    pub fn try_check_change_ticks(&mut self) {
+        let change_tick = self.change_tick();
+        if change_tick.wrapping_sub(self.last_check_tick) >= CHECK_TICK_THRESHOLD {
+            self.check_change_ticks();
+        }
+    }
+
+    /// 检查组件、资源、系统的节拍，将过旧的节拍截断，避免节拍回绕后`Added`、`Changed`误判
+    pub fn check_change_ticks(&mut self) {
+        let change_tick = self.change_tick();
+        for archetype in self.archetypes.archetypes.iter() {
+            for id in archetype.component_ids() {
+                unsafe { archetype.get_component(*id) }.check_ticks(change_tick);
+            }
+        }
+        self.archetypes.resources.check_ticks(change_tick);
+        self.systems.retain(|sys| match sys.upgrade() {
+            Some(sys) => {
+                sys.check_change_tick(change_tick);
+                true
+            }
+            None => false,
+        });
+        check_tick(&mut self.last_change_tick, change_tick);
+        self.last_check_tick = change_tick;
+    }
+
 	/// 取到组件，如果不存在组件，则注册组件
+	/// 动态原型的组件集合不可改变，不会为其注册组件；`archetype_id`为null时，仅返回组件id
 	pub fn get_or_register_component<C: Component>(&mut self, archetype_id: ArchetypeId) -> ComponentId {
+		let id = self.components.get_or_insert_id::<C>();
 		let archetype = self.archetypes.get_mut(archetype_id);
 		if let Some(archetype) = archetype {
-			let id = self.components.get_or_insert_id::<C>();
-			if archetype.contains(id) {
+			if archetype.contains(id) || archetype.is_dynamic() {
 				return id;
 			}
 			// 实体类型， TODO
@@ -362,6 +717,8 @@ impl WorldInner {
                 Local::new(g),
             );
 			id
+		} else if archetype_id.is_null() {
+			id
 		} else {
 			panic!("archetype is not exist, get_or_register_component fail, archetype:{:?}", archetype_id);// 原型不存在
 		}
@@ -396,6 +753,11 @@ impl<'a> ArchetypeInfo<'a> {
         self
     }
 
+    /// 为原型注册组件集合中的每个组件
+    pub fn register_bundle<B: Bundle>(self) -> Self {
+        B::register(self)
+    }
+
     /// 创建原型
     pub fn create(self) {
         // self.world
@@ -430,6 +792,12 @@ impl<'a, A: ArchetypeIdent> EntityRef<'a, A> {
         self
     }
 
+    /// 为实体插入组件集合中的每个组件
+    pub fn insert_bundle<B: Bundle>(&mut self, bundle: B) -> &mut Self {
+        bundle.insert_entity(self);
+        self
+    }
+
     /// 实体id
     pub fn id(&self) -> Id<A> {
 		unsafe{ Id::new(self.local) }
@@ -463,6 +831,11 @@ impl<T: Resource> ResourceRef<T> {
 		world.archetypes.insert_resource(value, self.0, change_tick)
 	}
 
+	/// 从世界中取出资源，会发出资源的删除事件
+	pub fn take(&self, world: &mut World) -> Option<T> {
+		unsafe { world.archetypes.remove_resource(self.0) }
+	}
+
 	pub fn id(&self) -> ResourceId {
 		self.0
 	}
@@ -503,3 +876,11 @@ unsafe impl Sync for WorldInner {}
 
 unsafe impl Send for WorldInner {}
 
+/// 事件集合的更新函数
+type EventUpdate = fn(&WorldInner);
+
+fn update_events<T: Component>(world: &WorldInner) {
+    if let Some(events) = world.get_resource_mut::<Events<T>>() {
+        events.update();
+    }
+}
@@ -0,0 +1,83 @@
+/// 测试动态原型
+/// 动态实体所在的原型由其拥有的组件集合决定，增删组件时实体会在原型之间移动
+/// query_all会匹配所有组件满足条件的原型（包括静态原型和动态原型）
+
+use pi_ecs::{prelude::{World, With, WithOut}, entity::Entity};
+
+pub struct Node;
+
+#[derive(Debug, PartialEq)]
+pub struct Position(pub usize);
+
+#[derive(Debug, PartialEq)]
+pub struct Velocity(pub usize);
+
+#[test]
+fn test() {
+	let mut world = World::new();
+
+	// 静态原型的实体
+	world.new_archetype::<Node>()
+		.register::<Position>()
+		.create();
+	world.spawn::<Node>().insert(Position(0));
+
+	// 动态实体
+	let mut entitys: Vec<Entity> = Vec::new();
+	for i in 1..4 {
+		let e = world.spawn_dynamic();
+		let e = world.insert_component_dynamic(e, Position(i));
+		entitys.push(e);
+	}
+	let e = world.insert_component_dynamic(entitys[2], Velocity(30));
+	// 实体移动到了{Position, Velocity}原型，组件被保留
+	assert_ne!(e.archetype_id(), entitys[2].archetype_id());
+	assert!(world.archetypes()[e.archetype_id()].is_dynamic());
+	entitys[2] = e;
+
+	// 相同组件集合的实体，在同一个原型中
+	let e = world.insert_component_dynamic(entitys[0], Velocity(10));
+	assert_eq!(e.archetype_id(), entitys[2].archetype_id());
+	entitys[0] = e;
+
+	let mut query = world.query_all::<&Position>();
+	let mut r: Vec<usize> = query.iter(&world).map(|p| p.0).collect();
+	r.sort();
+	assert_eq!(r, vec![0, 1, 2, 3]);
+
+	let mut query = world.query_all_filtered::<(&Position, &Velocity), ()>();
+	let mut r: Vec<(usize, usize)> = query.iter(&world).map(|(p, v)| (p.0, v.0)).collect();
+	r.sort();
+	assert_eq!(r, vec![(1, 10), (3, 30)]);
+	assert_eq!(query.get(&world, entitys[0]).map(|r| r.1), Some(&Velocity(10)));
+	assert!(query.get(&world, entitys[1]).is_none());
+
+	// 删除组件，实体移回{Position}原型
+	let e = world.remove_component_dynamic::<Velocity>(entitys[2]);
+	assert_eq!(e.archetype_id(), entitys[1].archetype_id());
+	entitys[2] = e;
+
+	let mut query = world.query_all_filtered::<&Position, WithOut<Velocity>>();
+	let mut r: Vec<usize> = query.iter(&world).map(|p| p.0).collect();
+	r.sort();
+	assert_eq!(r, vec![0, 2, 3]);
+
+	// 创建查询后，新的原型通过update_archetypes匹配
+	let mut query = world.query_all_filtered::<&Position, With<Velocity>>();
+	assert_eq!(query.iter(&world).count(), 1);
+	let e = world.spawn_dynamic();
+	let e = world.insert_component_dynamic(e, Velocity(40));
+	let e = world.insert_component_dynamic(e, Position(4));
+	query.update_archetypes(&world);
+	let mut r: Vec<usize> = query.iter(&world).map(|p| p.0).collect();
+	r.sort();
+	assert_eq!(r, vec![1, 4]);
+
+	world.despawn(entitys[0]);
+	query.update_archetypes(&world);
+	assert_eq!(query.iter(&world).map(|p| p.0).collect::<Vec<usize>>(), vec![4]);
+
+	// 原型已包含的组件，可以直接通过insert_component修改，实体不移动
+	world.insert_component(e, Position(5));
+	assert_eq!(query.iter(&world).map(|p| p.0).collect::<Vec<usize>>(), vec![5]);
+}
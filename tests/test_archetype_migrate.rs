@@ -0,0 +1,155 @@
+/// 测试实体迁移
+/// 实体从一个原型迁移到另一个原型，两个原型都注册了的组件被保留，新原型中没有的组件被删除（发出删除事件），
+/// 迁移不产生实体的创建和删除事件，只在新原型上产生一次迁移事件
+
+use pi_ecs::{
+	prelude::{World, StageBuilder, SingleDispatcher, Dispatcher, Res, ResMut, Id, Offset, MigrateCommands, IntoSystem, EventType},
+	monitor::{Event, ListenSetup, Listeners},
+	entity::Entity,
+};
+use pi_ecs_macros::listen;
+use pi_async::prelude::{multi_thread::MultiTaskRuntime, AsyncRuntimeBuilder};
+use std::{sync::Arc, any::TypeId};
+
+/// 待加载的节点
+pub struct Pending;
+/// 已加载的节点
+pub struct Loaded;
+
+#[derive(Debug, PartialEq)]
+pub struct Position(pub usize);
+
+#[derive(Debug, PartialEq)]
+pub struct Source(pub usize);
+
+#[derive(Debug, PartialEq)]
+pub struct Mesh(pub usize);
+
+#[derive(Default)]
+pub struct Migrated(pub Vec<(Entity, Entity)>);
+
+#[derive(Default)]
+pub struct Count(pub usize);
+
+#[derive(Default)]
+pub struct SourceDeleted(pub Vec<usize>);
+
+#[derive(Default)]
+pub struct PendingMigrated(pub usize);
+
+/// 监听迁移，记录迁移前后的实体
+#[listen(entity = (Loaded, Migrate))]
+fn migrated(input: Event, mut migrated: ResMut<Migrated>) {
+	if let EventType::Migrate(from) = input.ty {
+		migrated.0.push((from, input.id));
+	}
+}
+
+/// 迁移不应该产生创建、删除事件
+#[listen(entity = (Loaded, (Create, Delete)), component = (Loaded, Position, (Create, Delete)))]
+fn created_deleted(_input: Event, mut count: ResMut<Count>) {
+	count.0 += 1;
+}
+
+/// 原来的原型不会收到迁移事件
+#[listen(entity = (Pending, Migrate))]
+fn pending_migrated(_input: Event, mut count: ResMut<PendingMigrated>) {
+	count.0 += 1;
+}
+
+/// Loaded没有Source组件，迁移时Source被删除
+#[listen(component = (Pending, Source, Delete))]
+fn source_deleted(input: Event, mut deleted: ResMut<SourceDeleted>) {
+	deleted.0.push(input.id.local().offset());
+}
+
+#[derive(Default)]
+pub struct Pendings(pub Vec<Id<Pending>>);
+
+/// 通过指令迁移实体
+fn load(mut command: MigrateCommands<Pending, Loaded>, pendings: Res<Pendings>) {
+	for id in pendings.0.iter() {
+		command.migrate(*id);
+	}
+}
+
+#[test]
+fn test() {
+	let mut world = World::new();
+	world.insert_resource(Migrated::default());
+	world.insert_resource(Count::default());
+	world.insert_resource(SourceDeleted::default());
+	world.insert_resource(PendingMigrated::default());
+
+	world.new_archetype::<Pending>()
+		.register::<Position>()
+		.register::<Source>()
+		.create();
+	world.new_archetype::<Loaded>()
+		.register::<Position>()
+		.register::<Mesh>()
+		.create();
+
+	migrated.listeners().setup(&mut world);
+	created_deleted.listeners().setup(&mut world);
+	pending_migrated.listeners().setup(&mut world);
+	source_deleted.listeners().setup(&mut world);
+
+	let e1 = world.spawn::<Pending>().insert(Position(1)).insert(Source(1)).id();
+	let e2 = world.spawn::<Pending>().insert(Position(2)).insert(Source(2)).id();
+
+	// 直接迁移
+	let id: Id<Loaded> = world.migrate::<Pending, Loaded>(e1).unwrap();
+	let pending = *world.archetypes().get_id_by_ident(TypeId::of::<Pending>()).unwrap();
+	let loaded = *world.archetypes().get_id_by_ident(TypeId::of::<Loaded>()).unwrap();
+	let mut query = world.query::<Loaded, &Position>();
+	assert_eq!(query.get(&world, id), Some(&Position(1)));
+	let mut query = world.query::<Pending, &Position>();
+	assert!(query.get(&world, e1).is_none());
+	assert_eq!(world.get_resource::<Count>().unwrap().0, 0);
+	let (from, to) = world.get_resource::<Migrated>().unwrap().0[0];
+	assert_eq!((from.archetype_id(), from.local().offset()), (pending, e1.offset()));
+	assert_eq!((to.archetype_id(), to.local().offset()), (loaded, id.offset()));
+	assert_eq!(world.get_resource::<Migrated>().unwrap().0.len(), 1);
+	assert_eq!(world.get_resource::<SourceDeleted>().unwrap().0, vec![e1.offset()]);
+
+	// 已迁移的实体不能再次迁移
+	assert!(world.migrate::<Pending, Loaded>(e1).is_none());
+
+	// 通过指令迁移
+	world.insert_resource(Pendings(vec![e2]));
+	let dispatcher = get_dispatcher(&mut world);
+	futures::executor::block_on(dispatcher.run());
+	let migrated = &world.get_resource::<Migrated>().unwrap().0;
+	assert_eq!(migrated.len(), 2);
+	let mut query = world.query::<Loaded, (&Position, Option<&Mesh>)>();
+	let mut r: Vec<usize> = query.iter(&world).map(|(p, m)| {
+		assert!(m.is_none());
+		p.0
+	}).collect();
+	r.sort();
+	assert_eq!(r, vec![1, 2]);
+	assert_eq!(world.get_resource::<Count>().unwrap().0, 0);
+	assert_eq!(world.get_resource::<SourceDeleted>().unwrap().0, vec![e1.offset(), e2.offset()]);
+	assert_eq!(world.get_resource::<PendingMigrated>().unwrap().0, 0);
+}
+
+fn get_dispatcher(world: &mut World) -> SingleDispatcher<MultiTaskRuntime> {
+	let rt = AsyncRuntimeBuilder::default_multi_thread(
+		None,
+		None,
+		None,
+		None,
+	);
+	let system = load.system(world);
+
+	let mut stage = StageBuilder::new();
+	stage.add_node(system);
+
+	let mut stages = Vec::new();
+	stages.push(Arc::new(stage.build(world)));
+	let mut dispatcher = SingleDispatcher::new(rt);
+	dispatcher.init(stages, world);
+
+	dispatcher
+}
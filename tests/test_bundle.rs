@@ -0,0 +1,124 @@
+/// 测试组件集合
+/// #[derive(Bundle)]的结构体可以一次为原型注册、为实体插入一组组件
+
+use pi_ecs::{
+	prelude::{World, Query, ResMut, Id, IntoSystem, StageBuilder, SingleDispatcher, Dispatcher, BundleCommands, Local},
+	monitor::{Event, ListenSetup, Listeners},
+};
+use pi_ecs_macros::{listen, Bundle};
+use pi_async::prelude::{multi_thread::MultiTaskRuntime, AsyncRuntimeBuilder};
+use std::sync::Arc;
+
+pub struct Node;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Transform(pub usize);
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Style(pub usize);
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Visibility(pub bool);
+
+#[derive(Bundle)]
+pub struct NodeBundle {
+	transform: Transform,
+	style: Style,
+	visibility: Visibility,
+}
+
+#[derive(Bundle)]
+pub struct StyleBundle(Style, Visibility);
+
+fn bundle(i: usize) -> NodeBundle {
+	NodeBundle {
+		transform: Transform(i),
+		style: Style(i),
+		visibility: Visibility(i % 2 == 0),
+	}
+}
+
+#[derive(Default)]
+pub struct Count {
+	create: usize,
+	modify: usize,
+}
+
+#[listen(component = (Node, Transform, Create))]
+fn created(_input: Event, mut count: ResMut<Count>) {
+	count.create += 1;
+}
+
+#[listen(component = (Node, Style, Modify))]
+fn modified(_input: Event, mut count: ResMut<Count>) {
+	count.modify += 1;
+}
+
+/// 系统中通过指令插入组件集合
+fn insert(mut commands: BundleCommands<Node, StyleBundle>, query: Query<Node, Id<Node>>, mut first: Local<bool>) {
+	if *first {
+		return;
+	}
+	*first = true;
+	for id in query.iter() {
+		commands.insert(id, StyleBundle(Style(100), Visibility(false)));
+	}
+}
+
+#[test]
+fn test() {
+	let mut world = World::new();
+	world.insert_resource(Count::default());
+	world.new_archetype::<Node>()
+		.register_bundle::<NodeBundle>()
+		.create();
+	created.listeners().setup(&mut world);
+	modified.listeners().setup(&mut world);
+
+	let e1 = world.spawn::<Node>().insert_bundle(bundle(1)).entity();
+	let e2 = world.spawn::<Node>().insert_bundle(bundle(2)).entity();
+	// 重复插入，发出修改事件
+	assert_eq!(world.insert_bundle(e2, bundle(3)), e2);
+	let ids = world.spawn_batch::<Node, _>((4..6).map(bundle));
+	assert_eq!(ids.len(), 2);
+
+	let count = world.get_resource::<Count>().unwrap();
+	assert_eq!((count.create, count.modify), (4, 1));
+
+	let mut query = world.query::<Node, (&Transform, &Style, &Visibility)>();
+	let id1: Id<Node> = unsafe { Id::new(e1.local()) };
+	let id2: Id<Node> = unsafe { Id::new(e2.local()) };
+	assert_eq!(query.get(&world, id1), Some((&Transform(1), &Style(1), &Visibility(false))));
+	assert_eq!(query.get(&world, id2), Some((&Transform(3), &Style(3), &Visibility(false))));
+	assert_eq!(query.get(&world, ids[0]), Some((&Transform(4), &Style(4), &Visibility(true))));
+
+	let dispatcher = get_dispatcher(&mut world);
+	futures::executor::block_on(dispatcher.run());
+
+	let mut query = world.query::<Node, (&Transform, &Style, &Visibility)>();
+	for id in [id1, id2, ids[0], ids[1]] {
+		let (_, style, visibility) = query.get(&world, id).unwrap();
+		assert_eq!((style, visibility), (&Style(100), &Visibility(false)));
+	}
+	assert_eq!(world.get_resource::<Count>().unwrap().modify, 5);
+}
+
+fn get_dispatcher(world: &mut World) -> SingleDispatcher<MultiTaskRuntime> {
+	let rt = AsyncRuntimeBuilder::default_multi_thread(
+		None,
+		None,
+		None,
+		None,
+	);
+	let system = insert.system(world);
+
+	let mut stage = StageBuilder::new();
+	stage.add_node(system);
+
+	let mut stages = Vec::new();
+	stages.push(Arc::new(stage.build(world)));
+	let mut dispatcher = SingleDispatcher::new(rt);
+	dispatcher.init(stages, world);
+
+	dispatcher
+}
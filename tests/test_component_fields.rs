@@ -0,0 +1,100 @@
+/// 测试字段级的修改事件
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use pi_ecs::{
+	prelude::{World, Query, Write, Id, IntoSystem, System},
+	monitor::{Event, ListenSetup, Listeners}
+};
+use pi_ecs_macros::{listen, ComponentFields};
+
+/// 定义一个名为Node原型类型
+pub struct Node;
+
+/// 定义一个组件类型，为其生成字段setter
+#[derive(Debug, ComponentFields)]
+pub struct Color {
+	pub r: f32,
+	pub g: f32,
+	pub list: [f32; 4],
+}
+
+static R_COUNT: AtomicUsize = AtomicUsize::new(0);
+static LIST_INDEX: AtomicUsize = AtomicUsize::new(0);
+static MODIFY_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// 只监听字段r的修改
+#[listen(component = (Node, Color, ModifyField<color_fields::r>))]
+fn listener_r(
+	input: Event,
+) {
+	assert_eq!(input.field, "r");
+	R_COUNT.fetch_add(1, Ordering::Relaxed);
+}
+
+/// 只监听字段list的修改，记录被修改元素的索引
+#[listen(component = (Node, Color, ModifyField<color_fields::list>))]
+fn listener_list(
+	input: Event,
+) {
+	LIST_INDEX.store(input.index, Ordering::Relaxed);
+}
+
+/// 监听所有修改
+#[listen(component = (Node, Color, Modify))]
+fn listener_modify(
+	_input: Event,
+) {
+	MODIFY_COUNT.fetch_add(1, Ordering::Relaxed);
+}
+
+fn set_g(mut query: Query<Node, Write<Color>>) {
+	for mut color in query.iter_mut() {
+		color.set_g(0.5);
+	}
+}
+
+fn set_r(mut query: Query<Node, Write<Color>>) {
+	for mut color in query.iter_mut() {
+		color.set_r(0.5);
+	}
+}
+
+fn set_list(mut query: Query<Node, Write<Color>>) {
+	for mut color in query.iter_mut() {
+		color.set_list_at(2, 0.5);
+	}
+}
+
+#[test]
+fn test() {
+	let mut world = World::new();
+	world.new_archetype::<Node>()
+		.register::<Color>()
+		.create();
+
+	listener_r.listeners().setup(&mut world);
+	listener_list.listeners().setup(&mut world);
+	listener_modify.listeners().setup(&mut world);
+
+	let e: Id<Node> = world.spawn::<Node>().insert(Color { r: 0.0, g: 0.0, list: [0.0; 4] }).id();
+
+	let mut sys = set_g.system(&mut world);
+	sys.run(());
+	assert_eq!(R_COUNT.load(Ordering::Relaxed), 0);
+	assert_eq!(MODIFY_COUNT.load(Ordering::Relaxed), 1);
+
+	let mut sys = set_r.system(&mut world);
+	sys.run(());
+	assert_eq!(R_COUNT.load(Ordering::Relaxed), 1);
+	assert_eq!(MODIFY_COUNT.load(Ordering::Relaxed), 2);
+
+	let mut sys = set_list.system(&mut world);
+	sys.run(());
+	assert_eq!(LIST_INDEX.load(Ordering::Relaxed), 2);
+	assert_eq!(R_COUNT.load(Ordering::Relaxed), 1);
+	assert_eq!(MODIFY_COUNT.load(Ordering::Relaxed), 3);
+
+	let color = world.query::<Node, &Color>().get(&world, e).unwrap();
+	assert_eq!((color.r, color.g, color.list[2]), (0.5, 0.5, 0.5));
+}
@@ -0,0 +1,87 @@
+/// 测试不记录ticks的组件（`#[component(no_ticks)]`）
+/// 组件的插入、修改仍然发出事件，可以正常查询，但不能用于依赖节拍的查询（Changed、Added、Modifyed、ChangeTrackers）
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use pi_ecs::{
+	prelude::{World, QueryState, QueryError, ChangeTrackers},
+	query::filter::{Changed, Added, Deleted},
+	monitor::{Event, EventType, ListenSetup, Listeners},
+};
+use pi_ecs_macros::{listen, Component};
+
+/// 定义一个名为Node原型类型
+pub struct Node;
+
+#[derive(Debug, PartialEq)]
+/// 定义一个组件类型
+pub struct Position(pub usize);
+
+/// 不记录ticks的组件
+#[derive(Debug, PartialEq, Component)]
+#[component(no_ticks)]
+pub struct Velocity(pub usize);
+
+/// 同时指定存储和no_ticks
+#[derive(Debug, PartialEq, Component)]
+#[storage(sparse)]
+#[component(no_ticks)]
+pub struct Mass(pub usize);
+
+static CREATE_COUNT: AtomicUsize = AtomicUsize::new(0);
+static MODIFY_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+#[listen(component = (Node, Velocity, (Create, Modify)))]
+fn listener_velocity(e: Event) {
+	match e.ty {
+		EventType::Create => CREATE_COUNT.fetch_add(1, Ordering::Relaxed),
+		_ => MODIFY_COUNT.fetch_add(1, Ordering::Relaxed),
+	};
+}
+
+#[test]
+fn test() {
+	let mut world = World::new();
+	world.new_archetype::<Node>()
+		.register::<Position>()
+		.register::<Velocity>()
+		.register::<Mass>()
+		.create();
+
+	listener_velocity.listeners().setup(&mut world);
+
+	let e1 = world.spawn::<Node>().insert(Position(1)).insert(Velocity(1)).insert(Mass(1)).entity();
+	let _e2 = world.spawn::<Node>().insert(Position(2)).insert(Velocity(2)).entity();
+	world.insert_component(e1, Velocity(10));
+	assert_eq!(CREATE_COUNT.load(Ordering::Relaxed), 2);
+	assert_eq!(MODIFY_COUNT.load(Ordering::Relaxed), 1);
+
+	// 普通查询不受影响
+	let mut query = world.query::<Node, (&Position, &Velocity, Option<&Mass>)>();
+	let mut r: Vec<(usize, usize, Option<usize>)> = query.iter(&world).map(|(p, v, m)| (p.0, v.0, m.map(|m| m.0))).collect();
+	r.sort();
+	assert_eq!(r, vec![(1, 10, Some(1)), (2, 2, None)]);
+
+	// 删除过滤器不依赖节拍
+	world.query_filtered::<Node, &Position, Deleted<Velocity>>();
+
+	// 依赖节拍的查询在创建时被拒绝
+	match QueryState::<Node, &Position, Changed<Velocity>>::try_new(&mut world) {
+		Err(QueryError::NoTicks { component, query }) => {
+			assert!(component.ends_with("Velocity"));
+			assert_eq!(query, "Changed");
+		},
+		_ => panic!("Changed<Velocity> should be rejected"),
+	}
+	assert!(QueryState::<Node, Option<ChangeTrackers<Mass>>>::try_new(&mut world).is_err());
+	assert!(QueryState::<Node, &Position, (Changed<Position>, Added<Mass>)>::try_new(&mut world).is_err());
+	assert!(QueryState::<Node, ChangeTrackers<Position>, Changed<Position>>::try_new(&mut world).is_ok());
+
+	let r = catch_unwind(AssertUnwindSafe(|| {
+		world.query_filtered::<Node, &Position, Changed<Velocity>>();
+	}));
+	let err = r.unwrap_err();
+	let msg = err.downcast_ref::<String>().unwrap();
+	assert!(msg.contains("#[component(no_ticks)]"), "{}", msg);
+}
@@ -0,0 +1,86 @@
+/// 测试World::add_event注册的事件，由整理节点每帧更新，并按保留策略清除
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use pi_async::prelude::{multi_thread::MultiTaskRuntime, AsyncRuntimeBuilder};
+use pi_ecs::{
+	prelude::{World, StageBuilder, SingleDispatcher, Dispatcher, IntoSystem},
+	sys::param::event::{EventReader, EventRetention, Events},
+};
+use std::sync::Arc;
+
+/// 按默认策略（保留两帧）保留的事件
+pub struct Hit(pub usize);
+
+/// 保留到所有读取器都读取过的事件
+pub struct Click(pub usize);
+
+static HIT_COUNT: AtomicUsize = AtomicUsize::new(0);
+static CLICK_COUNT: AtomicUsize = AtomicUsize::new(0);
+static CLICK_READ: AtomicBool = AtomicBool::new(false);
+
+fn read_hit(mut hits: EventReader<Hit>) {
+	HIT_COUNT.fetch_add(hits.iter().count(), Ordering::Relaxed);
+}
+
+/// 只有CLICK_READ为true时，才读取事件
+fn read_click(mut clicks: EventReader<Click>) {
+	if CLICK_READ.load(Ordering::Relaxed) {
+		CLICK_COUNT.fetch_add(clicks.iter().count(), Ordering::Relaxed);
+	}
+}
+
+#[test]
+fn test() {
+	let mut world = World::new();
+	world.add_event::<Hit>();
+	world.add_event_with_retention::<Click>(EventRetention::UntilConsumed);
+	// 重复注册不会重复更新
+	world.add_event::<Hit>();
+	assert_eq!(world.get_resource::<Events<Hit>>().unwrap().retention(), EventRetention::Frames(2));
+
+	let dispatcher = get_dispatcher(&mut world);
+
+	world.get_resource_mut::<Events<Hit>>().unwrap().send(Hit(1));
+	world.get_resource_mut::<Events<Click>>().unwrap().send(Click(1));
+
+	futures::executor::block_on(dispatcher.run());
+	assert_eq!(HIT_COUNT.load(Ordering::Relaxed), 1);
+	// 保留两帧，第一次更新后事件仍在
+	assert!(!world.get_resource::<Events<Hit>>().unwrap().is_empty());
+
+	futures::executor::block_on(dispatcher.run());
+	assert_eq!(HIT_COUNT.load(Ordering::Relaxed), 1);
+	assert!(world.get_resource::<Events<Hit>>().unwrap().is_empty());
+
+	// Click的读取器一直没有读取，事件被保留
+	for _ in 0..3 {
+		futures::executor::block_on(dispatcher.run());
+	}
+	assert!(!world.get_resource::<Events<Click>>().unwrap().is_empty());
+
+	CLICK_READ.store(true, Ordering::Relaxed);
+	futures::executor::block_on(dispatcher.run());
+	assert_eq!(CLICK_COUNT.load(Ordering::Relaxed), 1);
+	assert!(world.get_resource::<Events<Click>>().unwrap().is_empty());
+}
+
+fn get_dispatcher(world: &mut World) -> SingleDispatcher<MultiTaskRuntime> {
+	let rt = AsyncRuntimeBuilder::default_multi_thread(
+		None,
+		None,
+		None,
+		None,
+	);
+
+	let mut stage = StageBuilder::new();
+	stage.add_node(read_hit.system(world));
+	stage.add_node(read_click.system(world));
+
+	let mut stages = Vec::new();
+	stages.push(Arc::new(stage.build(world)));
+	let mut dispatcher = SingleDispatcher::new(rt);
+	dispatcher.init(stages, world);
+
+	dispatcher
+}
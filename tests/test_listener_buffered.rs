@@ -0,0 +1,78 @@
+/// 测试缓冲监听器，事件在应用点批量投递
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use pi_ecs::{
+	prelude::{World, Local, Write, Id},
+	monitor::{Event, ListenSetup, Listeners}
+};
+use pi_ecs_macros::listen;
+
+/// 定义一个名为Node原型类型
+pub struct Node;
+
+#[derive(Debug)]
+/// 定义一个组件类型
+pub struct Position(pub usize);
+
+static BATCH_COUNT: AtomicUsize = AtomicUsize::new(0);
+static EVENT_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Default)]
+pub struct Local1(pub usize);
+
+/// 缓冲监听器，监听组件Position的修改
+#[listen(component = (Node, Position, Modify))]
+fn listener_buffered(
+	events: &[Event],
+	mut local: Local<Local1>,
+) {
+	local.0 += 1;
+	BATCH_COUNT.store(local.0, Ordering::Relaxed);
+	EVENT_COUNT.fetch_add(events.len(), Ordering::Relaxed);
+}
+
+#[test]
+fn test() {
+	let mut world = World::new();
+	world.new_archetype::<Node>()
+		.register::<Position>()
+		.create();
+
+	listener_buffered.listeners().setup(&mut world);
+
+	let e1 = world.spawn::<Node>().insert(Position(1)).entity();
+	let e2 = world.spawn::<Node>().insert(Position(2)).entity();
+
+	// 同一实体的多次修改，只投递一次
+	for i in 0..3 {
+		world.insert_component(e1, Position(i));
+	}
+	world.insert_component(e2, Position(3));
+
+	// 应用点之前，不会投递
+	assert_eq!(BATCH_COUNT.load(Ordering::Relaxed), 0);
+
+	world.apply_listeners();
+	assert_eq!(BATCH_COUNT.load(Ordering::Relaxed), 1);
+	assert_eq!(EVENT_COUNT.load(Ordering::Relaxed), 2);
+
+	// 没有事件时，不运行监听器
+	world.apply_listeners();
+	assert_eq!(BATCH_COUNT.load(Ordering::Relaxed), 1);
+
+	world.insert_component(e1, Position(4));
+	world.apply_listeners();
+	assert_eq!(BATCH_COUNT.load(Ordering::Relaxed), 2);
+	assert_eq!(EVENT_COUNT.load(Ordering::Relaxed), 3);
+
+	// 同一实体不同字段、不同索引的修改，也只投递一次
+	let mut query = world.query::<Node, Write<Position>>();
+	let mut item = query.get_mut(&mut world, unsafe { Id::new(e2.local()) }).unwrap();
+	item.notify_modify();
+	item.notify_modify_field("0", 0);
+	item.notify_modify_field("0", 1);
+	world.apply_listeners();
+	assert_eq!(BATCH_COUNT.load(Ordering::Relaxed), 3);
+	assert_eq!(EVENT_COUNT.load(Ordering::Relaxed), 4);
+}
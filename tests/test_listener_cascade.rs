@@ -0,0 +1,116 @@
+/// 测试监听器级联的循环检测和深度限制
+
+use pi_ecs::{
+	prelude::{World, Query, Write},
+	monitor::{Event, ListenSetup, Listeners, DEFAULT_MAX_LISTENER_DEPTH}
+};
+use pi_ecs_macros::listen;
+
+/// 定义一个名为Node原型类型
+pub struct Node;
+
+#[derive(Debug)]
+pub struct Width(pub usize);
+
+#[derive(Debug)]
+pub struct Height(pub usize);
+
+#[derive(Debug, PartialEq)]
+pub struct Area(pub usize);
+
+/// Width修改后修改Height
+#[listen(component = (Node, Width, Modify))]
+fn width_to_height(
+	input: Event,
+	query: Query<Node, Write<Height>>,
+) {
+	if let Some(mut h) = query.get_mut_by_entity(input.id) {
+		h.write(Height(1));
+	}
+}
+
+/// Height修改后修改Width，形成循环
+#[listen(component = (Node, Height, Modify))]
+fn height_to_width(
+	input: Event,
+	query: Query<Node, Write<Width>>,
+) {
+	if let Some(mut w) = query.get_mut_by_entity(input.id) {
+		w.write(Width(1));
+	}
+}
+
+/// Height修改后修改Area，不构成循环
+#[listen(component = (Node, Height, Modify))]
+fn height_to_area(
+	input: Event,
+	query: Query<Node, Write<Area>>,
+) {
+	if let Some(mut a) = query.get_mut_by_entity(input.id) {
+		a.write(Area(1));
+	}
+}
+
+/// 取出世界中唯一的级联错误
+fn take_error(world: &World) -> String {
+	let errors = world.take_listener_errors();
+	assert_eq!(errors.len(), 1, "{:?}", errors);
+	errors[0].to_string()
+}
+
+#[test]
+fn test() {
+	let mut world = World::new();
+	world.new_archetype::<Node>()
+		.register::<Width>()
+		.register::<Height>()
+		.register::<Area>()
+		.create();
+
+	let (r, e) = {
+		let mut r = world.spawn::<Node>();
+		r.insert(Width(0)).insert(Height(0)).insert(Area(0));
+		(r.id(), r.entity())
+	};
+
+	width_to_height.listeners().setup(&mut world);
+	height_to_area.listeners().setup(&mut world);
+
+	// Width -> width_to_height -> Height -> height_to_area，级联深度为2
+	world.insert_component(e, Width(1));
+	assert!(world.take_listener_errors().is_empty());
+	let mut query = world.query::<Node, &Area>();
+	assert_eq!(query.get(&world, r.clone()), Some(&Area(1)));
+
+	// 最大深度是世界的设置，不影响其它世界
+	// 超过最大深度的监听器被跳过，错误记录在世界中
+	world.set_max_listener_depth(1);
+	assert_eq!(World::new().max_listener_depth(), DEFAULT_MAX_LISTENER_DEPTH);
+	world.insert_component(e, Area(0));
+	world.insert_component(e, Width(2));
+	let msg = take_error(&world);
+	assert!(msg.contains("depth exceeded 1"), "{}", msg);
+	assert!(msg.contains("width_to_height"), "{}", msg);
+	assert!(msg.contains("height_to_area"), "{}", msg);
+	assert_eq!(query.get(&world, r.clone()), Some(&Area(0)));
+
+	// 出错之后，级联状态已被清理，可以继续正常触发
+	world.set_max_listener_depth(DEFAULT_MAX_LISTENER_DEPTH);
+	world.insert_component(e, Width(3));
+	assert!(world.take_listener_errors().is_empty());
+	assert_eq!(query.get(&world, r.clone()), Some(&Area(1)));
+
+	// Width -> width_to_height -> Height -> height_to_width -> Width -> width_to_height
+	// 级联深度超过CYCLE_CHECK_DEPTH后检测到循环，跳过循环的监听器
+	let h = height_to_width.listeners().setup(&mut world);
+	world.insert_component(e, Width(4));
+	let msg = take_error(&world);
+	assert!(msg.contains("cycle detected"), "{}", msg);
+	assert!(msg.contains("Width"), "{}", msg);
+	assert!(msg.contains("Height"), "{}", msg);
+	assert!(msg.contains("height_to_width"), "{}", msg);
+
+	h.remove(&mut world);
+	world.insert_component(e, Width(5));
+	assert!(world.take_listener_errors().is_empty());
+}
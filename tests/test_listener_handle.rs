@@ -0,0 +1,88 @@
+/// 测试通过监听器句柄移除监听器
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use pi_ecs::{
+	prelude::{ World, Local},
+	monitor::{Event, ListenSetup, Listeners}
+};
+use pi_ecs_macros::listen;
+
+/// 定义一个名为Node原型类型
+pub struct Node;
+
+#[derive(Debug)]
+/// 定义一个组件类型
+pub struct Position(pub usize);
+
+#[derive(Debug)]
+/// 定义一个资源类型
+pub struct Resource1(pub usize);
+
+static COMPONENT_COUNT: AtomicUsize = AtomicUsize::new(0);
+static RESOURCE_COUNT: AtomicUsize = AtomicUsize::new(0);
+static DROP_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// 系统的本地数据，系统被释放时计数
+#[derive(Default)]
+pub struct DropMark;
+
+impl Drop for DropMark {
+	fn drop(&mut self) {
+		DROP_COUNT.fetch_add(1, Ordering::Relaxed);
+	}
+}
+
+/// 监听组件的创建和修改，以及实体的删除
+#[listen(component = (Node, Position, (Create, Modify)), entity = (Node, Delete))]
+fn listener_component(
+	_input: Event,
+	_local: Local<DropMark>,
+) {
+	COMPONENT_COUNT.fetch_add(1, Ordering::Relaxed);
+}
+
+/// 监听资源的修改
+#[listen(resource = (Resource1, Modify))]
+fn listener_resource(
+	_input: Event,
+	_local: Local<DropMark>,
+) {
+	RESOURCE_COUNT.fetch_add(1, Ordering::Relaxed);
+}
+
+#[test]
+fn test() {
+	let mut world = World::new();
+	world.new_archetype::<Node>()
+		.register::<Position>()
+		.create();
+	world.insert_resource(Resource1(1));
+
+	let h1 = listener_component.listeners().setup(&mut world);
+	let h2 = listener_resource.listeners().setup(&mut world);
+
+	let e = world.spawn::<Node>().insert(Position(1)).entity();
+	world.insert_component(e, Position(2));
+	world.insert_resource(Resource1(2));
+	assert_eq!(COMPONENT_COUNT.load(Ordering::Relaxed), 2);
+	assert_eq!(RESOURCE_COUNT.load(Ordering::Relaxed), 1);
+	assert_eq!(DROP_COUNT.load(Ordering::Relaxed), 0);
+
+	// 移除组件监听器，其系统被释放，资源监听器不受影响
+	h1.remove(&mut world);
+	assert_eq!(DROP_COUNT.load(Ordering::Relaxed), 1);
+
+	world.insert_component(e, Position(3));
+	world.despawn(e);
+	world.spawn::<Node>().insert(Position(4));
+	world.insert_resource(Resource1(3));
+	assert_eq!(COMPONENT_COUNT.load(Ordering::Relaxed), 2);
+	assert_eq!(RESOURCE_COUNT.load(Ordering::Relaxed), 2);
+
+	h2.remove(&mut world);
+	assert_eq!(DROP_COUNT.load(Ordering::Relaxed), 2);
+
+	world.insert_resource(Resource1(4));
+	assert_eq!(RESOURCE_COUNT.load(Ordering::Relaxed), 2);
+}
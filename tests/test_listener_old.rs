@@ -0,0 +1,97 @@
+/// 测试接收旧值的监听器
+
+use pi_ecs::{
+	prelude::{World, Query, Write, ResMut},
+	monitor::{Event, Old, ListenSetup, Listeners},
+	sys::param::world::WorldRead,
+};
+use pi_ecs_macros::listen;
+
+/// 定义一个名为Node原型类型
+pub struct Node;
+
+#[derive(Debug, Clone, PartialEq)]
+/// 定义一个组件类型
+pub struct Position(pub usize);
+
+/// 记录监听器收到的旧值
+#[derive(Default)]
+pub struct Record {
+	modified: Vec<usize>,
+	deleted: Vec<usize>,
+	reported: Vec<usize>,
+}
+
+/// 监听修改，收到被替换的旧值
+#[listen(component = (Node, Position, Modify))]
+fn listener_modify(
+	e: Event<Old<&Position>>,
+	mut record: ResMut<Record>,
+) {
+	record.modified.push(e.old.0.0);
+}
+
+/// 监听删除，收到被删除的值
+#[listen(component = (Node, Position, Delete))]
+fn listener_delete(
+	e: Event<Old<Position>>,
+	mut record: ResMut<Record>,
+) {
+	let Old(value) = e.old;
+	record.deleted.push(value.0);
+}
+
+/// 监听修改，在监听器中读取同一组件容器
+#[listen(component = (Node, Position, Modify))]
+fn listener_report(
+	_e: Event<Old<&Position>>,
+	world: WorldRead,
+) {
+	let bytes = world.memory_report().total();
+	world.get_resource_mut::<Record>().unwrap().reported.push(bytes);
+}
+
+fn remove(mut query: Query<Node, Write<Position>>) {
+	for mut p in query.iter_mut() {
+		p.remove();
+	}
+}
+
+#[test]
+fn test() {
+	let mut world = World::new();
+	world.new_archetype::<Node>()
+		.register::<Position>()
+		.create();
+	world.insert_resource(Record::default());
+
+	let h = listener_modify.listeners().setup(&mut world);
+	listener_delete.listeners().setup(&mut world);
+
+	let e1 = world.spawn::<Node>().insert(Position(1)).entity();
+	let e2 = world.spawn::<Node>().insert(Position(2)).entity();
+
+	world.insert_component(e1, Position(10));
+	world.insert_component(e1, Position(11));
+	assert_eq!(world.get_resource::<Record>().unwrap().modified, vec![1, 10]);
+
+	// 销毁实体
+	world.despawn(e1);
+	assert_eq!(world.get_resource::<Record>().unwrap().deleted, vec![11]);
+
+	// 移除监听器后，不再收到旧值
+	h.remove(&mut world);
+	world.insert_component(e2, Position(20));
+	assert_eq!(world.get_resource::<Record>().unwrap().modified, vec![1, 10]);
+
+	// 在系统中删除组件
+	let mut sys = pi_ecs::prelude::IntoSystem::system(remove, &mut world);
+	pi_ecs::prelude::System::run(&mut sys, ());
+	assert_eq!(world.get_resource::<Record>().unwrap().deleted, vec![11, 20]);
+
+	// 监听器中可以读取同一组件容器
+	let e3 = world.spawn::<Node>().insert(Position(3)).entity();
+	listener_report.listeners().setup(&mut world);
+	world.insert_component(e3, Position(30));
+	assert_eq!(world.get_resource::<Record>().unwrap().reported.len(), 1);
+}
@@ -0,0 +1,77 @@
+/// 测试内存报告：按原型 → 组件统计字节数，组件名称取自ComponentInfo
+
+use std::mem::size_of;
+
+use pi_ecs::{
+	prelude::World,
+	component::StorageType,
+	query::filter::Changed,
+};
+use pi_ecs_macros::Component;
+
+/// 定义一个名为Node原型类型
+pub struct Node;
+
+#[derive(Debug, PartialEq)]
+/// 定义一个组件类型
+pub struct Position(pub usize);
+
+/// 与Position大小相同，但不记录ticks
+#[derive(Debug, PartialEq, Component)]
+#[component(no_ticks)]
+pub struct Velocity(pub usize);
+
+/// 不记录ticks的标记组件
+#[derive(Debug, PartialEq, Component)]
+#[storage(tag)]
+#[component(no_ticks)]
+pub struct Dirty;
+
+pub struct Config(pub [u8; 64]);
+
+#[test]
+fn test() {
+	let mut world = World::new();
+	world.new_archetype::<Node>()
+		.register::<Position>()
+		.register::<Velocity>()
+		.register::<Dirty>()
+		.create();
+	world.insert_resource(Config([0; 64]));
+
+	let report = world.memory_report();
+	let node = report.archetype(std::any::type_name::<Node>()).unwrap();
+	assert_eq!(node.components.len(), 3);
+
+	// 创建查询时为其分配脏列表
+	world.query_filtered::<Node, &Position, Changed<Position>>();
+	for i in 0..1000 {
+		world.spawn::<Node>().insert(Position(i)).insert(Velocity(i)).insert(Dirty);
+	}
+
+	let report = world.memory_report();
+	let node = report.archetype(std::any::type_name::<Node>()).unwrap();
+	assert!(node.entities >= 1000 * size_of::<u64>());
+
+	let position = node.component(std::any::type_name::<Position>()).unwrap();
+	let velocity = node.component(std::any::type_name::<Velocity>()).unwrap();
+	let dirty = node.component(std::any::type_name::<Dirty>()).unwrap();
+	assert_eq!(position.storage_type, StorageType::Dense);
+	assert_eq!(dirty.storage_type, StorageType::Tag);
+	assert!(position.bytes >= 1000 * size_of::<usize>());
+	// 不记录ticks的组件不为节拍分配内存
+	assert!(velocity.bytes >= 1000 * size_of::<usize>());
+	assert!(velocity.bytes < position.bytes);
+	// 标记组件每实体只占一位及一个u32版本号
+	assert!(dirty.bytes >= 1000 / 8 + 1000 * size_of::<u32>() && dirty.bytes < velocity.bytes);
+
+	let config = report.resources.iter().find(|r| r.name == std::any::type_name::<Config>()).unwrap();
+	assert_eq!(config.storage_type, StorageType::Resource);
+	assert!(config.bytes >= 64);
+	assert!(report.dirty_lists > 0);
+
+	assert!(report.total() > node.total());
+	let text = report.to_string();
+	assert!(text.contains(std::any::type_name::<Position>()));
+	assert!(text.contains(std::any::type_name::<Config>()));
+}
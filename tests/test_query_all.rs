@@ -0,0 +1,113 @@
+/// 测试跨原型查询QueryAll
+/// QueryAll匹配所有组件满足条件的原型，迭代出的实体为Entity（带有原型id）
+/// 含有QueryAll的系统合并所有已匹配原型的访问，派发器按读写关系为其建立依赖
+
+use pi_ecs::{prelude::{World, StageBuilder, SingleDispatcher, Dispatcher, QueryAll, Query, ResMut}, sys::system::{IntoSystem, System}, entity::Entity};
+use pi_graph::DirectedGraph;
+use pi_async::prelude::{multi_thread::MultiTaskRuntime, AsyncRuntimeBuilder};
+use std::sync::Arc;
+
+pub struct Node;
+pub struct Camera;
+
+#[derive(Debug)]
+pub struct Transform(pub usize);
+
+#[derive(Debug)]
+pub struct Light(pub usize);
+
+#[derive(Default)]
+pub struct Collected(pub Vec<(Entity, usize)>);
+
+#[derive(Default)]
+pub struct Counter(pub usize);
+
+/// 一个系统迭代所有原型中的Transform
+fn transform(
+	query: QueryAll<(Entity, &Transform)>,
+	mut collected: ResMut<Collected>,
+) {
+	collected.0.clear();
+	for (e, t) in query.iter() {
+		collected.0.push((e, t.0));
+	}
+}
+
+/// 通过QueryAll写所有原型中的Transform
+fn write_transform(mut query: QueryAll<&mut Transform>) {
+	for _t in query.iter_mut() {}
+}
+
+/// 只读Node原型中的Transform，与QueryAll的写冲突
+fn count(query: Query<Node, &Transform>, mut counter: ResMut<Counter>) {
+	counter.0 += query.iter().count();
+}
+
+#[test]
+fn test() {
+	// 创建world
+	let mut world = World::new();
+	world.insert_resource(Collected::default());
+	world.insert_resource(Counter::default());
+
+	world.new_archetype::<Node>()
+		.register::<Transform>()
+		.create();
+	world.new_archetype::<Camera>()
+		.register::<Transform>()
+		.create();
+
+	let node = world.spawn::<Node>().insert(Transform(1)).entity();
+	let camera = world.spawn::<Camera>().insert(Transform(2)).entity();
+	let e = world.spawn_dynamic();
+	let dynamic = world.insert_component_dynamic(e, Transform(3));
+
+	let dispatcher = get_dispatcher(&mut world);
+	futures::executor::block_on(dispatcher.run());
+
+	let mut r = world.get_resource::<Collected>().unwrap().0.clone();
+	r.sort_by_key(|r| r.1);
+	assert_eq!(r, vec![(node, 1), (camera, 2), (dynamic, 3)]);
+
+	// 派发器构建后新增的原型，在下次运行时被匹配
+	let light = world.insert_component_dynamic(dynamic, Light(1));
+	futures::executor::block_on(dispatcher.run());
+
+	let mut r = world.get_resource::<Collected>().unwrap().0.clone();
+	r.sort_by_key(|r| r.1);
+	assert_eq!(r, vec![(node, 1), (camera, 2), (light, 3)]);
+}
+
+fn get_dispatcher(world: &mut World) -> SingleDispatcher<MultiTaskRuntime> {
+	let rt = AsyncRuntimeBuilder::default_multi_thread(
+		None,
+		None,
+		None,
+		None,
+	);
+	let system = transform.system(world);
+	let system1 = count.system(world);
+	let system2 = write_transform.system(world);
+	let (id, id1, id2) = (system.id().id(), system1.id().id(), system2.id().id());
+	// 访问包含所有已匹配原型（Node、Camera、动态原型）的Transform
+	assert_eq!(system2.archetype_component_access().get_writes().count_ones(..), 3);
+
+	let mut stage = StageBuilder::new();
+	stage.add_node(system);
+	stage.add_node(system1);
+	stage.add_node(system2);
+
+	// 写Transform的系统虽然最后加入，仍先于读Transform的系统执行
+	let graph = stage.build(world);
+	let order = graph.topological_sort();
+	let index = |id| order.iter().position(|r| *r == id).unwrap();
+	assert!(index(id2) < index(id));
+	assert!(index(id2) < index(id1));
+
+	let mut stages = Vec::new();
+	stages.push(Arc::new(graph));
+	let mut dispatcher = SingleDispatcher::new(rt);
+	dispatcher.init(stages, world);
+
+	dispatcher
+}
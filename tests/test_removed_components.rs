@@ -0,0 +1,102 @@
+/// 测试被删除组件的追踪
+/// 组件被移除、实体被销毁，都会发出组件删除事件，RemovedComponents能取到自系统上次运行以来失去组件的实体，
+/// Deleted过滤器能查询到仍然存在、但失去了组件的实体
+
+use pi_ecs::{
+	prelude::{World, StageBuilder, SingleDispatcher, Dispatcher, Query, ResMut, Id, IntoSystem, RemovedComponents},
+	monitor::{Event, ListenSetup, Listeners},
+	query::filter::Deleted,
+};
+use pi_ecs_macros::listen;
+use pi_async::prelude::{multi_thread::MultiTaskRuntime, AsyncRuntimeBuilder};
+use std::sync::Arc;
+
+pub struct Node;
+
+#[derive(Debug, PartialEq)]
+pub struct Position(pub usize);
+
+#[derive(Default)]
+pub struct Record {
+	// 每次运行RemovedComponents取到的实体
+	removed: Vec<Vec<Id<Node>>>,
+	// 每次运行Deleted过滤器取到的实体
+	deleted: Vec<Vec<Id<Node>>>,
+	// 删除事件中读到的组件
+	events: Vec<usize>,
+}
+
+/// 删除事件发出时，组件仍然可以读取
+#[listen(component = (Node, Position, Delete))]
+fn on_delete(input: Event, query: Query<Node, &Position>, mut record: ResMut<Record>) {
+	let position = query.get(unsafe { Id::new(input.id.local()) }).unwrap();
+	record.events.push(position.0);
+}
+
+fn collect(removed: RemovedComponents<Node, Position>, query: Query<Node, Id<Node>, Deleted<Position>>, mut record: ResMut<Record>) {
+	let mut ids: Vec<Id<Node>> = removed.iter().collect();
+	ids.sort();
+	record.removed.push(ids);
+	let mut ids: Vec<Id<Node>> = query.iter().collect();
+	ids.sort();
+	record.deleted.push(ids);
+}
+
+#[test]
+fn test() {
+	let mut world = World::new();
+	world.insert_resource(Record::default());
+	world.new_archetype::<Node>()
+		.register::<Position>()
+		.create();
+	on_delete.listeners().setup(&mut world);
+
+	let e1 = world.spawn::<Node>().insert(Position(1)).entity();
+	let e2 = world.spawn::<Node>().insert(Position(2)).entity();
+	let e3 = world.spawn::<Node>().insert(Position(3)).entity();
+	let id1: Id<Node> = unsafe { Id::new(e1.local()) };
+	let id2: Id<Node> = unsafe { Id::new(e2.local()) };
+
+	let dispatcher = get_dispatcher(&mut world);
+	futures::executor::block_on(dispatcher.run());
+
+	// 移除组件、销毁实体
+	world.remove_component::<Position>(e1);
+	world.despawn(e2);
+	futures::executor::block_on(dispatcher.run());
+	// 没有新的删除
+	futures::executor::block_on(dispatcher.run());
+
+	// 删除后又重新插入的组件，RemovedComponents能取到，Deleted过滤器不再认为其被删除
+	world.insert_component(e1, Position(4));
+	world.remove_component::<Position>(e3);
+	world.insert_component(e3, Position(5));
+	futures::executor::block_on(dispatcher.run());
+
+	let record = world.get_resource::<Record>().unwrap();
+	let mut removed = vec![id1, id2];
+	removed.sort();
+	assert_eq!(record.removed, vec![vec![], removed, vec![], vec![unsafe { Id::new(e3.local()) }]]);
+	assert_eq!(record.deleted, vec![vec![], vec![id1], vec![], vec![]]);
+	assert_eq!(record.events, vec![1, 2, 3]);
+}
+
+fn get_dispatcher(world: &mut World) -> SingleDispatcher<MultiTaskRuntime> {
+	let rt = AsyncRuntimeBuilder::default_multi_thread(
+		None,
+		None,
+		None,
+		None,
+	);
+	let system = collect.system(world);
+
+	let mut stage = StageBuilder::new();
+	stage.add_node(system);
+
+	let mut stages = Vec::new();
+	stages.push(Arc::new(stage.build(world)));
+	let mut dispatcher = SingleDispatcher::new(rt);
+	dispatcher.init(stages, world);
+
+	dispatcher
+}
@@ -0,0 +1,46 @@
+/// 测试资源存储的对齐
+/// 资源按其类型的对齐要求存储，替换、删除和世界销毁时，资源被正确销毁
+
+use pi_ecs::prelude::World;
+use std::sync::Arc;
+
+#[derive(Debug, PartialEq)]
+#[repr(align(64))]
+pub struct Matrix(pub [f32; 16]);
+
+#[derive(Debug, PartialEq)]
+pub struct Byte(pub u8);
+
+#[derive(Debug, PartialEq)]
+pub struct Empty;
+
+pub struct Counted(pub Arc<()>);
+
+#[test]
+fn test() {
+	let mut world = World::new();
+	world.insert_resource(Byte(1));
+	world.insert_resource(Matrix([1.0; 16]));
+	world.insert_resource(Empty);
+
+	let matrix = world.get_resource::<Matrix>().unwrap();
+	assert_eq!(matrix as *const Matrix as usize % std::mem::align_of::<Matrix>(), 0);
+	assert_eq!(matrix, &Matrix([1.0; 16]));
+	assert_eq!(world.get_resource::<Byte>(), Some(&Byte(1)));
+	assert_eq!(world.get_resource::<Empty>(), Some(&Empty));
+
+	world.get_resource_mut::<Matrix>().unwrap().0[3] = 2.0;
+	assert_eq!(world.get_resource::<Matrix>().unwrap().0[3], 2.0);
+	assert_eq!(world.remove_resource::<Matrix>().map(|r| r.0[3]), Some(2.0));
+
+	// 资源被替换、删除、世界销毁时，资源被销毁
+	let counter = Arc::new(());
+	world.insert_resource(Counted(counter.clone()));
+	world.insert_resource(Counted(counter.clone()));
+	assert_eq!(Arc::strong_count(&counter), 2);
+	drop(world.remove_resource::<Counted>());
+	assert_eq!(Arc::strong_count(&counter), 1);
+	world.insert_resource(Counted(counter.clone()));
+	drop(world);
+	assert_eq!(Arc::strong_count(&counter), 1);
+}
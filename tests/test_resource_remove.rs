@@ -0,0 +1,81 @@
+/// 测试资源删除
+/// 删除资源会发出资源的删除事件，删除后Option<Res<T>>取到None
+
+use pi_ecs::{
+	prelude::{World, StageBuilder, SingleDispatcher, Dispatcher, Res, ResMut, ResourceCommands, IntoSystem},
+	monitor::{Event, ListenSetup, Listeners},
+};
+use pi_ecs_macros::listen;
+use pi_async::prelude::{multi_thread::MultiTaskRuntime, AsyncRuntimeBuilder};
+use std::sync::Arc;
+
+#[derive(Debug, PartialEq)]
+pub struct Config(pub usize);
+
+#[derive(Default)]
+pub struct Record {
+	// 删除事件中读到的资源
+	deleted: Vec<usize>,
+	// 系统每次运行时资源是否存在
+	exist: Vec<bool>,
+}
+
+/// 删除事件发出时，资源仍然可以读取
+#[listen(resource = (Config, Delete))]
+fn deleted(_input: Event, config: Res<Config>, mut record: ResMut<Record>) {
+	record.deleted.push(config.0);
+}
+
+fn remove(mut command: ResourceCommands<Config>, config: Option<Res<Config>>, mut record: ResMut<Record>) {
+	record.exist.push(config.is_some());
+	if config.is_some() {
+		command.remove();
+	}
+}
+
+#[test]
+fn test() {
+	let mut world = World::new();
+	world.insert_resource(Record::default());
+	world.insert_resource(Config(1));
+	deleted.listeners().setup(&mut world);
+
+	assert_eq!(world.remove_resource::<Config>(), Some(Config(1)));
+	assert_eq!(world.remove_resource::<Config>(), None);
+	assert!(world.get_resource::<Config>().is_none());
+	assert_eq!(world.get_resource::<Record>().unwrap().deleted, vec![1]);
+
+	let r = world.insert_resource(Config(2));
+	assert_eq!(r.take(&mut world), Some(Config(2)));
+	assert!(r.take(&mut world).is_none());
+	assert_eq!(world.get_resource::<Record>().unwrap().deleted, vec![1, 2]);
+
+	// 通过指令删除
+	world.insert_resource(Config(3));
+	let dispatcher = get_dispatcher(&mut world);
+	futures::executor::block_on(dispatcher.run());
+	futures::executor::block_on(dispatcher.run());
+	let record = world.get_resource::<Record>().unwrap();
+	assert_eq!(record.exist, vec![true, false]);
+	assert_eq!(record.deleted, vec![1, 2, 3]);
+}
+
+fn get_dispatcher(world: &mut World) -> SingleDispatcher<MultiTaskRuntime> {
+	let rt = AsyncRuntimeBuilder::default_multi_thread(
+		None,
+		None,
+		None,
+		None,
+	);
+	let system = remove.system(world);
+
+	let mut stage = StageBuilder::new();
+	stage.add_node(system);
+
+	let mut stages = Vec::new();
+	stages.push(Arc::new(stage.build(world)));
+	let mut dispatcher = SingleDispatcher::new(rt);
+	dispatcher.init(stages, world);
+
+	dispatcher
+}
@@ -0,0 +1,95 @@
+#![cfg(feature = "serde")]
+/// 测试世界的序列化与反序列化
+/// 加载后，实体的原型、版本保持不变，组件中持有的Id、Entity仍然有效
+
+use pi_ecs::{prelude::{World, Id}, entity::Entity};
+use serde::{Serialize, Deserialize};
+
+pub struct Node;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Position(pub usize);
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Parent(pub Id<Node>);
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Link(pub Entity);
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Config(pub String);
+
+fn new_world() -> World {
+	let mut world = World::new();
+	world.new_archetype::<Node>()
+		.register::<Position>()
+		.register::<Parent>()
+		.create();
+	world.register_serde::<Position>();
+	world.register_serde::<Parent>();
+	world.register_serde::<Link>();
+	world.register_resource_serde::<Config>();
+	world
+}
+
+#[test]
+fn test() {
+	let mut world = new_world();
+	world.insert_resource(Config(String::from("config")));
+
+	let root = world.spawn::<Node>().insert(Position(0)).id();
+	let removed = world.spawn::<Node>().insert(Position(1)).entity();
+	world.despawn(removed);
+	// 复用被删除实体的槽位，版本不同
+	let child = world.spawn::<Node>().insert(Position(2)).insert(Parent(root)).id();
+
+	let e = world.spawn_dynamic();
+	let e = world.insert_component_dynamic(e, Position(3));
+	let linked = world.insert_component_dynamic(e, Link(removed));
+
+	let mut buffer = Vec::new();
+	world.save(&mut buffer).unwrap();
+
+	let mut loaded = new_world();
+	loaded.load(buffer.as_slice()).unwrap();
+
+	let mut query = loaded.query::<Node, (&Position, Option<&Parent>)>();
+	assert_eq!(query.get(&loaded, root), Some((&Position(0), None)));
+	assert_eq!(query.get(&loaded, child), Some((&Position(2), Some(&Parent(root)))));
+	// 被删除的实体，加载后仍然不存在
+	let mut query = loaded.query::<Node, &Position>();
+	assert_eq!(query.iter(&loaded).count(), 2);
+
+	// 动态原型被重新创建，Entity仍然有效
+	let mut query = loaded.query_all::<(&Position, &Link)>();
+	assert_eq!(query.get(&loaded, linked), Some((&Position(3), &Link(removed))));
+
+	assert_eq!(loaded.get_resource::<Config>(), Some(&Config(String::from("config"))));
+
+	// 加载后新建的实体不会与已加载的实体冲突
+	let id = loaded.spawn::<Node>().insert(Position(4)).id();
+	assert_ne!(id, root);
+	assert_ne!(id, child);
+
+	// 原型不一致时，加载失败
+	let mut other = World::new();
+	other.register_serde::<Position>();
+	assert!(other.load(buffer.as_slice()).is_err());
+
+	// 资源未注册时加载失败，世界保持不变（原型的实体、组件不被替换，动态原型不被创建）
+	let mut partial = World::new();
+	partial.new_archetype::<Node>()
+		.register::<Position>()
+		.register::<Parent>()
+		.create();
+	partial.register_serde::<Position>();
+	partial.register_serde::<Parent>();
+	partial.register_serde::<Link>();
+	let kept = partial.spawn::<Node>().insert(Position(9)).id();
+	let len = partial.archetypes().len();
+	assert!(partial.load(buffer.as_slice()).is_err());
+	let mut query = partial.query::<Node, &Position>();
+	assert_eq!(query.get(&partial, kept), Some(&Position(9)));
+	assert_eq!(query.iter(&partial).count(), 1);
+	assert_eq!(partial.archetypes().len(), len);
+}
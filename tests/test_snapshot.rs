@@ -0,0 +1,101 @@
+/// 测试世界快照
+/// 恢复快照不发出实体、组件事件，只发出一次世界恢复事件
+
+use pi_ecs::{
+	prelude::{World, ResMut},
+	monitor::{Event, ListenSetup, Listeners},
+};
+use pi_ecs_macros::listen;
+
+pub struct Node;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Position(pub usize);
+
+/// 未注册克隆函数的组件
+#[derive(Debug, PartialEq)]
+pub struct Velocity(pub usize);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Score(pub usize);
+
+/// 快照之后创建的原型
+pub struct Leaf;
+
+#[derive(Default)]
+pub struct Count {
+	restored: usize,
+	changed: usize,
+}
+
+#[listen(world = Restore)]
+fn restored(_input: Event, mut count: ResMut<Count>) {
+	count.restored += 1;
+}
+
+#[listen(entity = (Node, (Create, Delete)), component = (Node, Position, (Create, Modify, Delete)))]
+fn changed(_input: Event, mut count: ResMut<Count>) {
+	count.changed += 1;
+}
+
+#[test]
+fn test() {
+	let mut world = World::new();
+	world.new_archetype::<Node>()
+		.register::<Position>()
+		.register::<Velocity>()
+		.create();
+	world.register_clone::<Position>();
+	world.register_resource_clone::<Score>();
+	world.insert_resource(Count::default());
+	world.insert_resource(Score(10));
+
+	restored.listeners().setup(&mut world);
+	changed.listeners().setup(&mut world);
+
+	let e1 = world.spawn::<Node>().insert(Position(1)).insert(Velocity(1)).entity();
+	let e2 = world.spawn::<Node>().insert(Position(2)).entity();
+
+	let snapshot = world.snapshot();
+
+	world.insert_component(e1, Position(100));
+	world.insert_component(e1, Velocity(100));
+	let e3 = world.spawn::<Node>().insert(Position(3)).insert(Velocity(3)).entity();
+	world.despawn(e2);
+	world.new_archetype::<Leaf>().register::<Position>().create();
+	let l1 = world.spawn::<Leaf>().insert(Position(4)).entity();
+	*world.get_resource_mut::<Score>().unwrap() = Score(20);
+
+	let changed = world.get_resource::<Count>().unwrap().changed;
+	assert!(changed > 0);
+	world.restore(&snapshot);
+
+	let count = world.get_resource::<Count>().unwrap();
+	assert_eq!(count.restored, 1);
+	assert_eq!(count.changed, changed);
+
+	// 未注册克隆函数的组件，在快照前后都存在的实体上保持不变
+	let mut query = world.query_all::<(&Position, Option<&Velocity>)>();
+	assert_eq!(query.get(&world, e1), Some((&Position(1), Some(&Velocity(100)))));
+	assert_eq!(query.get(&world, e2), Some((&Position(2), None)));
+	assert!(query.get(&world, e3).is_none());
+	assert_eq!(query.iter(&world).count(), 2);
+	assert_eq!(world.get_resource::<Score>(), Some(&Score(10)));
+
+	// 快照之后创建的实体，不会与恢复后创建的实体混淆
+	let e4 = world.spawn::<Node>().insert(Position(4)).entity();
+	assert_ne!(e3, e4);
+	assert!(query.get(&world, e3).is_none());
+	world.despawn(e4);
+
+	// 快照之后创建的原型，实体被移除，之后创建的实体不会与其混淆
+	assert!(!world.entities(l1.archetype_id()).contains(l1.local()));
+	let l2 = world.spawn::<Leaf>().insert(Position(5)).entity();
+	assert_ne!(l1, l2);
+
+	// 快照可以多次恢复
+	world.despawn(e1);
+	world.restore(&snapshot);
+	assert_eq!(world.get_resource::<Count>().unwrap().restored, 2);
+	assert_eq!(query.iter(&world).count(), 2);
+}
@@ -0,0 +1,102 @@
+/// 测试批量创建实体
+/// 批量创建的实体拥有元组中的全部组件，实体和组件的创建事件在全部实体创建完成后发出
+
+use pi_ecs::{
+	prelude::{World, Query, ResMut, Id, IntoSystem, StageBuilder, SingleDispatcher, Dispatcher},
+	monitor::{Event, ListenSetup, Listeners},
+};
+use pi_ecs_macros::listen;
+use pi_async::prelude::{multi_thread::MultiTaskRuntime, AsyncRuntimeBuilder};
+use std::sync::Arc;
+
+pub struct Node;
+
+#[derive(Debug, PartialEq)]
+pub struct Position(pub usize);
+
+#[derive(Debug, PartialEq)]
+pub struct Velocity(pub usize);
+
+#[derive(Default)]
+pub struct Count {
+	entity: usize,
+	position: usize,
+	velocity: usize,
+	// 实体创建事件发出时，已经可以读到组件
+	complete: usize,
+}
+
+#[listen(entity = (Node, Create))]
+fn entity_created(input: Event, query: Query<Node, (&Position, &Velocity)>, mut count: ResMut<Count>) {
+	count.entity += 1;
+	if query.get(unsafe { Id::new(input.id.local()) }).is_some() {
+		count.complete += 1;
+	}
+}
+
+#[listen(component = (Node, Position, Create))]
+fn position_created(_input: Event, mut count: ResMut<Count>) {
+	count.position += 1;
+}
+
+#[listen(component = (Node, Velocity, Create))]
+fn velocity_created(_input: Event, mut count: ResMut<Count>) {
+	count.velocity += 1;
+}
+
+#[derive(Default)]
+pub struct Sum(pub usize, pub usize);
+
+fn sum(query: Query<Node, (&Position, &Velocity)>, mut sum: ResMut<Sum>) {
+	for (p, v) in query.iter() {
+		sum.0 += p.0;
+		sum.1 += v.0;
+	}
+}
+
+#[test]
+fn test() {
+	let mut world = World::new();
+	world.insert_resource(Count::default());
+	world.insert_resource(Sum::default());
+	entity_created.listeners().setup(&mut world);
+	position_created.listeners().setup(&mut world);
+	velocity_created.listeners().setup(&mut world);
+
+	let ids = world.spawn_batch::<Node, _>((0..100).map(|i| (Position(i), Velocity(i * 2))));
+	assert_eq!(ids.len(), 100);
+
+	let count = world.get_resource::<Count>().unwrap();
+	assert_eq!((count.entity, count.position, count.velocity, count.complete), (100, 100, 100, 100));
+
+	// 与单个创建的实体共存
+	world.spawn::<Node>().insert(Position(1000)).insert(Velocity(1000));
+	let ids1 = world.spawn_batch::<Node, _>(vec![(Position(1), Velocity(1))]);
+	assert_eq!(ids1.len(), 1);
+	assert!(!ids.contains(&ids1[0]));
+
+	let dispatcher = get_dispatcher(&mut world);
+	futures::executor::block_on(dispatcher.run());
+	let s = world.get_resource::<Sum>().unwrap();
+	assert_eq!((s.0, s.1), ((0..100).sum::<usize>() + 1001, (0..100).map(|i| i * 2).sum::<usize>() + 1001));
+}
+
+fn get_dispatcher(world: &mut World) -> SingleDispatcher<MultiTaskRuntime> {
+	let rt = AsyncRuntimeBuilder::default_multi_thread(
+		None,
+		None,
+		None,
+		None,
+	);
+	let system = sum.system(world);
+
+	let mut stage = StageBuilder::new();
+	stage.add_node(system);
+
+	let mut stages = Vec::new();
+	stages.push(Arc::new(stage.build(world)));
+	let mut dispatcher = SingleDispatcher::new(rt);
+	dispatcher.init(stages, world);
+
+	dispatcher
+}
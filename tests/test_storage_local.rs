@@ -0,0 +1,20 @@
+/// 测试以Local为键的SecondaryMap：迭代得到的键与插入时相同（KeyData中补回的版本不会进入Local）
+
+use pi_ecs::storage::{Local, SecondaryMap, Offset};
+use pi_map::Map;
+
+#[test]
+fn test() {
+	let mut map: SecondaryMap<Local, &'static str> = SecondaryMap::with_capacity(0);
+	map.insert(Local::new(3), "a");
+	map.insert(Local::new(7), "b");
+
+	// Local::data()在高32位补上版本1，From<KeyData>需要去掉版本，只保留槽位
+	let mut keys: Vec<(usize, &str)> = map.iter().map(|(k, v)| (k.offset(), *v)).collect();
+	keys.sort();
+	assert_eq!(keys, vec![(3, "a"), (7, "b")]);
+	// 迭代得到的键可以再次用于查找
+	for (k, v) in map.iter() {
+		assert_eq!(map.get(&k), Some(v));
+	}
+}
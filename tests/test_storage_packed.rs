@@ -0,0 +1,103 @@
+/// 测试紧凑存储的组件：删除时交换到末尾，查询由紧凑存放的实体列表驱动迭代
+
+use std::any::TypeId;
+
+use pi_ecs::{
+	prelude::{World, Query, IntoSystem, System, Entity, With, Write},
+	component::StorageType,
+};
+use pi_ecs_macros::Component;
+
+/// 定义一个名为Node原型类型
+pub struct Node;
+
+#[derive(Debug, PartialEq)]
+/// 定义一个组件类型
+pub struct Position(pub usize);
+
+/// 只有少数实体拥有的组件
+#[derive(Debug, PartialEq, Component)]
+#[storage(packed)]
+pub struct Rare(pub usize);
+
+fn rare(world: &mut World) -> Vec<usize> {
+	let mut query = world.query::<Node, &Rare>();
+	query.iter(world).map(|r| r.0).collect()
+}
+
+fn rare_positions(world: &mut World) -> Vec<usize> {
+	let mut query = world.query_filtered::<Node, &Position, With<Rare>>();
+	query.iter(world).map(|r| r.0).collect()
+}
+
+fn double(mut q: Query<Node, &mut Rare>) {
+	for mut r in q.iter_mut() {
+		r.0 *= 2;
+	}
+}
+
+/// 迭代中删除组件（交换删除），由With<Rare>的紧凑列表驱动迭代
+fn remove_all(mut q: Query<Node, Write<Rare>, With<Rare>>) {
+	for mut r in q.iter_mut() {
+		r.remove();
+	}
+}
+
+/// 为每10个实体插入一个组件
+fn spread(mut q: Query<Node, (Write<Rare>, &Position)>) {
+	for (mut r, p) in q.iter_mut() {
+		if p.0 % 10 == 0 {
+			r.write(Rare(p.0));
+		}
+	}
+}
+
+#[test]
+fn test() {
+	let mut world = World::new();
+	world.new_archetype::<Node>()
+		.register::<Position>()
+		.register::<Rare>()
+		.create();
+
+	let components = world.components();
+	let id = components.get_id(TypeId::of::<Rare>()).unwrap();
+	assert_eq!(components.get_info(id).unwrap().storage_type(), StorageType::Packed);
+
+	let entities: Vec<Entity> = (0..100).map(|i| world.spawn::<Node>().insert(Position(i)).entity()).collect();
+	for i in [50, 10, 90] {
+		world.insert_component(entities[i], Rare(i));
+	}
+
+	// 按组件插入的顺序迭代，而不是实体的顺序
+	assert_eq!(rare(&mut world), vec![50, 10, 90]);
+	assert_eq!(rare_positions(&mut world), vec![50, 10, 90]);
+
+	// 删除时，最后一个元素被移到删除的位置
+	world.remove_component::<Rare>(entities[50]);
+	assert_eq!(rare(&mut world), vec![90, 10]);
+
+	// 替换已有的组件
+	world.insert_component(entities[10], Rare(11));
+	let mut sys = double.system(&mut world);
+	sys.run(());
+	assert_eq!(rare(&mut world), vec![180, 22]);
+
+	// 实体销毁后，槽位被复用的新实体没有该组件
+	world.despawn(entities[90]);
+	let e = world.spawn::<Node>().insert(Position(100)).entity();
+	assert_eq!(rare(&mut world), vec![22]);
+	world.insert_component(e, Rare(1));
+	assert_eq!(rare(&mut world), vec![22, 1]);
+
+	// 迭代中删除当前组件，被移到当前位置的组件不会被跳过
+	let mut sys = remove_all.system(&mut world);
+	sys.run(());
+	assert_eq!(rare(&mut world), Vec::<usize>::new());
+
+	let mut spread = spread.system(&mut world);
+	spread.run(());
+	assert_eq!(rare(&mut world), vec![0, 10, 20, 30, 40, 50, 60, 70, 80, 100]);
+	sys.run(());
+	assert_eq!(rare(&mut world), Vec::<usize>::new());
+}
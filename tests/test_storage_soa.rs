@@ -0,0 +1,141 @@
+/// 测试SoA存储的组件：字段分列存放，通过Field、FieldMut查询字段，整体借用组件的用法在创建时被拒绝
+
+use std::{
+	panic::{catch_unwind, AssertUnwindSafe},
+	sync::atomic::{AtomicUsize, Ordering},
+};
+
+use pi_ecs::{
+	prelude::{World, Query, Id, IntoSystem, System, Field, FieldMut, Entity, Write},
+	query::{QueryState, QueryError, filter::Changed},
+	monitor::{Event, Old, ListenSetup, Listeners},
+	storage::Offset,
+};
+use pi_ecs_macros::{listen, Component};
+
+/// 定义一个名为Node原型类型
+pub struct Node;
+
+/// 粒子，位置和速度分列存放
+#[derive(Debug, Clone, PartialEq, Component)]
+#[storage(soa)]
+pub struct Particle {
+	pub position: f32,
+	pub velocity: f32,
+}
+
+/// 只读取速度、修改位置
+fn step(mut q: Query<Node, (Field<Particle, particle_fields::velocity>, FieldMut<Particle, particle_fields::position>)>) {
+	for (velocity, mut position) in q.iter_mut() {
+		*position += *velocity;
+	}
+}
+
+/// 重写速度（值不变），只要可变借用过字段即视为修改
+fn rewrite_velocity(mut q: Query<Node, FieldMut<Particle, particle_fields::velocity>>) {
+	for mut velocity in q.iter_mut() {
+		let v = *velocity;
+		velocity.write(v);
+	}
+}
+
+static CHANGED_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+fn count_changed(q: Query<Node, Field<Particle, particle_fields::velocity>, Changed<Particle>>) {
+	CHANGED_COUNT.fetch_add(q.iter().count(), Ordering::Relaxed);
+}
+
+static VELOCITY_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// 字段修改事件
+#[listen(component = (Node, Particle, ModifyField<particle_fields::velocity>))]
+fn listener_velocity(_e: Event) {
+	VELOCITY_COUNT.fetch_add(1, Ordering::Relaxed);
+}
+
+static OLD_POSITION: AtomicUsize = AtomicUsize::new(0);
+
+/// 旧值由容器取出后整体传给监听器，SoA组件也可以接收旧值
+#[listen(component = (Node, Particle, Modify))]
+fn listener_old(e: Event<Old<&Particle>>) {
+	OLD_POSITION.store(e.old.0.position as usize, Ordering::Relaxed);
+}
+
+fn is_soa_error(r: Result<(), QueryError>) -> bool {
+	matches!(r, Err(QueryError::SoA { .. }))
+}
+
+fn positions(world: &mut World, entities: &[Entity]) -> Vec<Option<f32>> {
+	let query = world.query::<Node, Field<Particle, particle_fields::position>>();
+	entities.iter().map(|e| {
+		let id: Id<Node> = unsafe { Id::new(e.local()) };
+		query.get(world, id).copied()
+	}).collect()
+}
+
+#[test]
+fn test() {
+	let mut world = World::new();
+	world.new_archetype::<Node>()
+		.register::<Particle>()
+		.create();
+
+	let entities: Vec<Entity> = (0..4).map(|i| {
+		world.spawn::<Node>().insert(Particle { position: i as f32, velocity: 1.0 }).entity()
+	}).collect();
+	// 没有粒子组件的实体
+	let empty = world.spawn::<Node>().entity();
+
+	// 整体借用组件的查询在创建时返回错误，而不是在迭代中panic
+	assert!(is_soa_error(QueryState::<Node, &Particle, ()>::try_new(&mut world).map(|_| ())));
+	assert!(is_soa_error(QueryState::<Node, Option<&mut Particle>, ()>::try_new(&mut world).map(|_| ())));
+	assert!(is_soa_error(QueryState::<Node, Write<Particle>, ()>::try_new(&mut world).map(|_| ())));
+	assert!(QueryState::<Node, Field<Particle, particle_fields::velocity>, ()>::try_new(&mut world).is_ok());
+
+	// 快照需要整体克隆组件，注册时即被拒绝
+	let r = catch_unwind(AssertUnwindSafe(|| world.register_clone::<Particle>()));
+	assert!(r.is_err());
+
+	listener_old.listeners().setup(&mut world);
+	listener_velocity.listeners().setup(&mut world);
+
+	let mut sys = step.system(&mut world);
+	sys.run(());
+	sys.run(());
+	assert_eq!(positions(&mut world, &entities), vec![Some(2.0), Some(3.0), Some(4.0), Some(5.0)]);
+	assert_eq!(positions(&mut world, &[empty]), vec![None]);
+	// 位置被修改了两次，速度没有被修改
+	assert_eq!(VELOCITY_COUNT.load(Ordering::Relaxed), 0);
+
+	// 通过FieldMut修改字段，更新修改节拍并发出修改事件，Changed过滤器和字段监听器都能感知
+	// 直接运行系统时不经过派发器，需手动推进节拍
+	let mut changed = count_changed.system(&mut world);
+	changed.run(());
+	CHANGED_COUNT.store(0, Ordering::Relaxed);
+	world.increment_change_tick();
+	changed.run(());
+	assert_eq!(CHANGED_COUNT.load(Ordering::Relaxed), 0);
+	world.increment_change_tick();
+	let mut rewrite = rewrite_velocity.system(&mut world);
+	rewrite.run(());
+	world.increment_change_tick();
+	assert_eq!(VELOCITY_COUNT.load(Ordering::Relaxed), 4);
+	changed.run(());
+	assert_eq!(CHANGED_COUNT.load(Ordering::Relaxed), 4);
+
+	// 替换、删除组件
+	world.insert_component(entities[1], Particle { position: 10.0, velocity: 0.0 });
+	assert_eq!(OLD_POSITION.load(Ordering::Relaxed), 3);
+	world.remove_component::<Particle>(entities[2]);
+	sys.run(());
+	assert_eq!(positions(&mut world, &entities), vec![Some(3.0), Some(10.0), None, Some(6.0)]);
+
+	// 槽位被新实体复用后，旧实体不再命中新实体的字段
+	world.despawn(entities[3]);
+	world.archetypes_mut()[entities[3].archetype_id()].flush();
+	let reused = world.spawn::<Node>().insert(Particle { position: 20.0, velocity: 0.0 }).entity();
+	assert_eq!(reused.local().offset(), entities[3].local().offset());
+	assert_eq!(positions(&mut world, &[entities[3], reused]), vec![None, Some(20.0)]);
+	let mut query = world.query::<Node, FieldMut<Particle, particle_fields::position>>();
+	assert!(query.get_mut(&mut world, unsafe { Id::new(entities[3].local()) }).is_none());
+}
@@ -0,0 +1,99 @@
+/// 测试位集存储的标记组件：With、WithOut过滤，及创建、删除事件
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use pi_ecs::{
+	prelude::{World, Id, Entity, With, WithOut},
+	monitor::{Event, EventType, ListenSetup, Listeners},
+	storage::{Offset, LocalVersion, TagMap, Map},
+};
+use pi_ecs_macros::{listen, Component};
+
+/// 定义一个名为Node原型类型
+pub struct Node;
+
+#[derive(Debug, PartialEq)]
+/// 定义一个组件类型
+pub struct Position(pub usize);
+
+/// 标记组件
+#[derive(Debug, PartialEq, Component)]
+#[storage(tag)]
+pub struct Dirty;
+
+static CREATE_COUNT: AtomicUsize = AtomicUsize::new(0);
+static DELETE_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+#[listen(component = (Node, Dirty, (Create, Delete)))]
+fn listener_dirty(e: Event) {
+	match e.ty {
+		EventType::Create => CREATE_COUNT.fetch_add(1, Ordering::Relaxed),
+		_ => DELETE_COUNT.fetch_add(1, Ordering::Relaxed),
+	};
+}
+
+fn dirty_positions(world: &mut World) -> Vec<usize> {
+	let mut query = world.query_filtered::<Node, &Position, With<Dirty>>();
+	let mut r: Vec<usize> = query.iter(world).map(|p| p.0).collect();
+	r.sort();
+	r
+}
+
+fn clean_positions(world: &mut World) -> Vec<usize> {
+	let mut query = world.query_filtered::<Node, &Position, WithOut<Dirty>>();
+	let mut r: Vec<usize> = query.iter(world).map(|p| p.0).collect();
+	r.sort();
+	r
+}
+
+#[test]
+fn test() {
+	let mut world = World::new();
+	world.new_archetype::<Node>()
+		.register::<Position>()
+		.register::<Dirty>()
+		.create();
+
+	listener_dirty.listeners().setup(&mut world);
+
+	let entities: Vec<Entity> = (0..70).map(|i| world.spawn::<Node>().insert(Position(i)).entity()).collect();
+	for e in entities.iter().step_by(10) {
+		world.insert_component(*e, Dirty);
+	}
+	// 重复插入不会改变标记
+	world.insert_component(entities[0], Dirty);
+
+	assert_eq!(CREATE_COUNT.load(Ordering::Relaxed), 7);
+	assert_eq!(dirty_positions(&mut world), vec![0, 10, 20, 30, 40, 50, 60]);
+	assert_eq!(clean_positions(&mut world).len(), 63);
+
+	world.remove_component::<Dirty>(entities[60]);
+	world.despawn(entities[10]);
+	assert_eq!(DELETE_COUNT.load(Ordering::Relaxed), 2);
+	assert_eq!(dirty_positions(&mut world), vec![0, 20, 30, 40, 50]);
+
+	// 复用槽位的新实体没有标记
+	let e = world.spawn::<Node>().insert(Position(100)).entity();
+	assert_eq!(e.local().offset(), entities[10].local().offset());
+	let query = world.query::<Node, Option<&Dirty>>();
+	let id: Id<Node> = unsafe { Id::new(e.local()) };
+	assert_eq!(query.get(&world, id), Some(None));
+
+	// 标记记录了键的版本，同一槽位上旧版本的键不会命中
+	let old = LocalVersion::from_ffi(1 << 32 | 3);
+	let new = LocalVersion::from_ffi(3 << 32 | 3);
+	let mut tags = TagMap::<LocalVersion, Dirty>::with_capacity(0);
+	tags.insert(old, Dirty);
+	assert!(tags.contains(&old));
+	assert!(!tags.contains(&new));
+	assert_eq!(tags.get(&new), None);
+	assert_eq!(tags.remove(&new), None);
+
+	// 新版本覆盖已失效的标记，数量不变
+	assert_eq!(tags.insert(new, Dirty), None);
+	assert_eq!(tags.len(), 1);
+	assert!(!tags.contains(&old));
+	assert_eq!(tags.remove(&old), None);
+	assert_eq!(tags.remove(&new), Some(Dirty));
+	assert_eq!(tags.len(), 0);
+}
@@ -0,0 +1,106 @@
+/// 测试通过#[storage(..)]选择内置的组件存储，并由ComponentInfo::storage_type报告
+
+use std::any::TypeId;
+
+use pi_ecs::{
+	prelude::{World, Query, Id, IntoSystem, System, With, Write},
+	component::StorageType,
+	storage::{SecondaryMap, LocalVersion, TagMap, Map},
+};
+use pi_ecs_macros::Component;
+
+/// 定义一个名为Node原型类型
+pub struct Node;
+
+#[derive(Debug, PartialEq, Component)]
+#[storage(dense)]
+pub struct Position(pub usize);
+
+#[derive(Debug, PartialEq, Component)]
+#[storage(sparse)]
+pub struct Velocity(pub usize);
+
+#[derive(Debug, PartialEq, Component)]
+#[storage(hash)]
+pub struct Name(pub String);
+
+#[derive(Debug, PartialEq, Component)]
+#[storage(tag)]
+pub struct Visible;
+
+/// 自定义存储容器
+#[derive(Debug, PartialEq, Component)]
+#[storage(SecondaryMap<LocalVersion, Self>)]
+pub struct Custom(pub usize);
+
+/// 未指定存储
+#[derive(Debug, PartialEq)]
+pub struct Plain(pub usize);
+
+fn storage_type<T: 'static>(world: &World) -> StorageType {
+	let components = world.components();
+	let id = components.get_id(TypeId::of::<T>()).unwrap();
+	components.get_info(id).unwrap().storage_type()
+}
+
+/// 修改标记了Visible的实体的Name
+fn rename(mut q: Query<Node, Write<Name>, With<Visible>>) {
+	for mut name in q.iter_mut() {
+		name.write(Name("visible".to_string()));
+	}
+}
+
+#[test]
+fn test() {
+	let mut world = World::new();
+	world.new_archetype::<Node>()
+		.register::<Position>()
+		.register::<Velocity>()
+		.register::<Name>()
+		.register::<Visible>()
+		.register::<Custom>()
+		.register::<Plain>()
+		.create();
+
+	assert_eq!(storage_type::<Position>(&world), StorageType::Dense);
+	assert_eq!(storage_type::<Velocity>(&world), StorageType::Sparse);
+	assert_eq!(storage_type::<Name>(&world), StorageType::Hash);
+	assert_eq!(storage_type::<Visible>(&world), StorageType::Tag);
+	assert_eq!(storage_type::<Custom>(&world), StorageType::Dense);
+	assert_eq!(storage_type::<Plain>(&world), StorageType::Dense);
+	// 旧名称仍可使用
+	#[allow(deprecated)]
+	{
+		assert_eq!(storage_type::<Plain>(&world), StorageType::Table);
+		assert_eq!(storage_type::<Velocity>(&world), StorageType::SparseSet);
+	}
+
+	let e1 = world.spawn::<Node>()
+		.insert(Position(1))
+		.insert(Velocity(1))
+		.insert(Name("e1".to_string()))
+		.insert(Visible)
+		.entity();
+	let e2 = world.spawn::<Node>()
+		.insert(Position(2))
+		.insert(Name("e2".to_string()))
+		.entity();
+
+	let mut sys = rename.system(&mut world);
+	sys.run(());
+
+	let id1: Id<Node> = unsafe { Id::new(e1.local()) };
+	let id2: Id<Node> = unsafe { Id::new(e2.local()) };
+	let query = world.query::<Node, (&Position, Option<&Velocity>, &Name, Option<&Visible>)>();
+	assert_eq!(query.get(&world, id1), Some((&Position(1), Some(&Velocity(1)), &Name("visible".to_string()), Some(&Visible))));
+	assert_eq!(query.get(&world, id2), Some((&Position(2), None, &Name("e2".to_string()), None)));
+
+	world.remove_component::<Visible>(e1);
+	let query = world.query::<Node, &Visible>();
+	assert_eq!(query.get(&world, id1), None);
+
+	// 标记存储的容量是位集的长度，而不是组件数量
+	let tags = TagMap::<LocalVersion, Visible>::with_capacity(8);
+	assert_eq!(tags.len(), 0);
+	assert!(tags.capacity() >= 8);
+}
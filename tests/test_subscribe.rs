@@ -0,0 +1,84 @@
+/// 测试订阅事件流，在ECS之外的线程中接收事件和组件监听事件
+
+use std::any::TypeId;
+
+use futures::{executor::block_on, StreamExt};
+use pi_ecs::{
+	prelude::{World, Modify, Create, Entity},
+	component::CellMultiCase,
+	sys::param::event::Events,
+	subscribe::{Overflow, SubscribeConfig},
+};
+
+/// 定义一个名为Node原型类型
+pub struct Node;
+
+/// 定义一个组件类型
+pub struct Position(pub usize);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message(pub usize);
+
+fn send(world: &World, i: usize) {
+	world.get_resource_mut::<Events<Message>>().unwrap().send(Message(i));
+}
+
+/// Position的修改监听器数量
+fn modify_listeners(world: &World, e: Entity) -> usize {
+	let id = world.components().get_id(TypeId::of::<Position>()).unwrap();
+	let container = unsafe { world.archetypes()[e.archetype_id()].get_component(id) };
+	let container = container.downcast_ref::<CellMultiCase<Position>>().unwrap();
+	let r = container.borrow().get_notify_ref().modify.len();
+	r
+}
+
+#[test]
+fn test() {
+	let mut world = World::new();
+	world.new_archetype::<Node>()
+		.register::<Position>()
+		.create();
+
+	// 订阅时自动注册事件
+	let mut oldest = world.subscribe_with::<Message>(SubscribeConfig { capacity: 2, overflow: Overflow::DropOldest });
+	let mut newest = world.subscribe_with::<Message>(SubscribeConfig { capacity: 2, overflow: Overflow::DropNewest });
+	for i in 0..3 {
+		send(&world, i);
+	}
+	assert_eq!(block_on(oldest.next()), Some(Message(1)));
+	assert_eq!(block_on(oldest.next()), Some(Message(2)));
+	assert_eq!(block_on(newest.next()), Some(Message(0)));
+	assert_eq!(block_on(newest.next()), Some(Message(1)));
+	drop(newest);
+
+	// 阻塞策略：发送方等待接收线程取走事件
+	let mut blocked = world.subscribe_with::<Message>(SubscribeConfig { capacity: 1, overflow: Overflow::Block });
+	let reader = std::thread::spawn(move || {
+		let mut r = Vec::new();
+		while let Some(m) = block_on(blocked.next()) {
+			r.push(m.0);
+			if r.len() == 3 {
+				break;
+			}
+		}
+		r
+	});
+	world.get_resource_mut::<Events<Message>>().unwrap().extend((3..6).map(Message));
+	assert_eq!(reader.join().unwrap(), vec![3, 4, 5]);
+	// 接收线程的流已丢弃，继续发送不会阻塞
+	send(&world, 6);
+
+	// 订阅组件事件
+	let mut created = world.subscribe_component::<Node, Position, Create>();
+	let mut modified = world.subscribe_component::<Node, Position, Modify>();
+	let e = world.spawn::<Node>().insert(Position(1)).entity();
+	world.insert_component(e, Position(2));
+	assert_eq!(block_on(created.next()).unwrap().id, e);
+	assert_eq!(block_on(modified.next()).unwrap().id, e);
+
+	// 流丢弃后，监听器在应用点被移除
+	assert_eq!(modify_listeners(&world, e), 1);
+	drop(modified);
+	world.apply_listeners();
+	assert_eq!(modify_listeners(&world, e), 0);
+}
@@ -0,0 +1,71 @@
+/// 测试节拍回绕
+/// 世界节拍每前进CHECK_TICK_THRESHOLD检查一次节拍，过旧的节拍被截断，回绕后不会被误判为新增或修改
+
+use pi_ecs::{
+	component::{ComponentTicks, CHECK_TICK_THRESHOLD, MAX_CHANGE_AGE},
+	prelude::{World, Res, IntoSystem, StageBuilder, SingleDispatcher, Dispatcher},
+};
+use pi_async::prelude::{multi_thread::MultiTaskRuntime, AsyncRuntimeBuilder};
+use std::sync::Arc;
+
+pub struct Config(pub usize);
+
+fn read(_config: Res<Config>) {}
+
+#[test]
+fn test() {
+	// 未截断的节拍，节拍回绕一圈后，被误判为新增
+	let ticks = ComponentTicks::new(10);
+	let change_tick = 10u32.wrapping_add(u32::MAX).wrapping_add(3);
+	assert!(ticks.is_added(change_tick.wrapping_sub(5), change_tick));
+
+	// 按整理节点的方式定期检查，节拍被截断，不再误判
+	let mut ticks = ComponentTicks::new(10);
+	let mut last_change_tick = 10u32;
+	let mut tick = 10u32;
+	for _ in 0..(u32::MAX / CHECK_TICK_THRESHOLD + 1) {
+		tick = tick.wrapping_add(CHECK_TICK_THRESHOLD);
+		ticks.check_ticks(tick);
+		pi_ecs::component::check_tick(&mut last_change_tick, tick);
+	}
+	let change_tick = tick;
+	assert!(!ticks.is_added(change_tick.wrapping_sub(5), change_tick));
+	assert!(!ticks.is_changed(change_tick.wrapping_sub(5), change_tick));
+	// 长时间未运行的系统，节拍同样被截断
+	assert_eq!(change_tick.wrapping_sub(last_change_tick), MAX_CHANGE_AGE);
+
+	// 新的节拍不受检查影响
+	let mut ticks = ComponentTicks::new(100);
+	ticks.check_ticks(200);
+	assert!(ticks.is_added(99, 200));
+	assert!(!ticks.is_added(100, 200));
+
+	// 检查世界中的组件、资源、系统节拍
+	let mut world = World::new();
+	world.insert_resource(Config(1));
+	let dispatcher = get_dispatcher(&mut world);
+	futures::executor::block_on(dispatcher.run());
+	world.check_change_ticks();
+	futures::executor::block_on(dispatcher.run());
+	assert!(world.get_resource::<Config>().is_some());
+}
+
+fn get_dispatcher(world: &mut World) -> SingleDispatcher<MultiTaskRuntime> {
+	let rt = AsyncRuntimeBuilder::default_multi_thread(
+		None,
+		None,
+		None,
+		None,
+	);
+	let system = read.system(world);
+
+	let mut stage = StageBuilder::new();
+	stage.add_node(system);
+
+	let mut stages = Vec::new();
+	stages.push(Arc::new(stage.build(world)));
+	let mut dispatcher = SingleDispatcher::new(rt);
+	dispatcher.init(stages, world);
+
+	dispatcher
+}
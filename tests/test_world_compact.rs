@@ -0,0 +1,151 @@
+/// 测试压缩：大量实体销毁后释放容器容量，以及将实体重新编号并通过EntityRemap修正组件中的实体id
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use pi_ecs::{
+	prelude::{World, Query, Res, Id, Entity},
+	compact::EntityRemap,
+	monitor::{Event, ListenSetup, Listeners},
+	storage::Offset,
+};
+use pi_ecs_macros::{listen, Component};
+
+/// 定义一个名为Node原型类型
+pub struct Node;
+
+/// 定义一个名为Leaf原型类型
+pub struct Leaf;
+
+#[derive(Debug, PartialEq)]
+/// 定义一个组件类型
+pub struct Position(pub usize);
+
+/// 指向父节点
+#[derive(Debug, PartialEq)]
+pub struct Parent(pub Id<Node>);
+
+#[derive(Debug, PartialEq, Component)]
+#[storage(packed)]
+pub struct Rare(pub usize);
+
+#[derive(Debug, PartialEq, Component)]
+#[storage(tag)]
+pub struct Dirty;
+
+static REMAP_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// 按映射表修正Parent中的实体id
+#[listen(resource = (EntityRemap, (Create, Modify)))]
+fn fix_parent(
+	_input: Event,
+	remap: Res<EntityRemap>,
+	mut query: Query<Node, &mut Parent>,
+) {
+	REMAP_COUNT.fetch_add(1, Ordering::Relaxed);
+	for mut parent in query.iter_mut() {
+		if let Some(id) = remap.get_id(parent.0) {
+			parent.0 = id;
+		}
+	}
+}
+
+fn position_bytes(world: &World) -> usize {
+	let report = world.memory_report();
+	let node = report.archetype(std::any::type_name::<Node>()).unwrap();
+	node.component(std::any::type_name::<Position>()).unwrap().bytes
+}
+
+fn entity_bytes(world: &World) -> usize {
+	world.memory_report().archetype(std::any::type_name::<Node>()).unwrap().entities
+}
+
+#[test]
+fn test() {
+	let mut world = World::new();
+	world.new_archetype::<Node>()
+		.register::<Position>()
+		.register::<Parent>()
+		.register::<Rare>()
+		.register::<Dirty>()
+		.create();
+	world.new_archetype::<Leaf>()
+		.register::<Position>()
+		.create();
+
+	fix_parent.listeners().setup(&mut world);
+
+	let entities: Vec<Entity> = (0..1000).map(|i| world.spawn::<Node>().insert(Position(i)).entity()).collect();
+	// 每100个实体保留一个，保留的实体指向前一个保留的实体
+	for i in (100..1000).step_by(100) {
+		let parent = unsafe { Id::<Node>::new(entities[i - 100].local()) };
+		world.insert_component(entities[i], Parent(parent));
+		world.insert_component(entities[i], Rare(i));
+		world.insert_component(entities[i], Dirty);
+	}
+	for (i, e) in entities.iter().enumerate() {
+		if i % 100 != 0 {
+			world.despawn(*e);
+		}
+	}
+	world.archetypes_mut()[entities[0].archetype_id()].flush();
+
+	let leaves: Vec<Entity> = (0..100).map(|i| world.spawn::<Leaf>().insert(Position(i)).entity()).collect();
+	for (i, e) in leaves.iter().enumerate() {
+		if i % 10 != 9 {
+			world.despawn(*e);
+		}
+	}
+	world.archetypes_mut()[leaves[0].archetype_id()].flush();
+
+	let peak = position_bytes(&world);
+	let peak_entities = entity_bytes(&world);
+
+	// 只收缩容量，实体编号不变
+	world.compact();
+	assert!(position_bytes(&world) < peak);
+	assert_eq!(entity_bytes(&world), peak_entities);
+	assert_eq!(REMAP_COUNT.load(Ordering::Relaxed), 0);
+	let query = world.query::<Node, &Position>();
+	assert_eq!(query.get(&world, unsafe { Id::new(entities[900].local()) }), Some(&Position(900)));
+
+	// 重新编号，组件随实体移动，所有原型的映射表在同一个资源中
+	world.compact_with_remap();
+	assert_eq!(REMAP_COUNT.load(Ordering::Relaxed), 1);
+	let remap = world.get_resource::<EntityRemap>().unwrap();
+	assert_eq!(remap.archetypes().count(), 2);
+	let leaf = remap.get_entity(leaves[99]).unwrap();
+	assert_eq!(world.query::<Leaf, &Position>().get(&world, unsafe { Id::new(leaf.local()) }), Some(&Position(99)));
+	assert!(position_bytes(&world) * 10 < peak);
+	assert!(entity_bytes(&world) * 10 < peak_entities);
+
+	let mut query = world.query::<Node, (Id<Node>, &Position, Option<&Parent>, Option<&Rare>, Option<&Dirty>)>();
+	let mut items: Vec<(usize, usize, Option<usize>, Option<usize>, bool)> = query.iter(&world)
+		.map(|(id, p, parent, rare, dirty)| (id.offset(), p.0, parent.map(|r| r.0.offset()), rare.map(|r| r.0), dirty.is_some()))
+		.collect();
+	items.sort();
+	assert_eq!(items.len(), 10);
+	let offsets: Vec<usize> = items.iter().map(|r| r.0).collect();
+	let first = offsets[0];
+	assert_eq!(offsets, (first..first + 10).collect::<Vec<usize>>());
+	for (i, item) in items.iter().enumerate() {
+		assert_eq!(item.1, i * 100);
+		if i == 0 {
+			assert_eq!(item.2, None);
+			assert!(!item.4);
+		} else {
+			// 父节点已被修正为前一个实体的新编号
+			assert_eq!(item.2, Some(items[i - 1].0));
+			assert_eq!(item.3, Some(i * 100));
+			assert!(item.4);
+		}
+	}
+
+	// 重新编号前的实体（包括已销毁的实体）不会与复用其槽位的新实体混淆
+	for e in entities.iter().chain(leaves.iter()) {
+		assert!(!world.entities(e.archetype_id()).contains(e.local()));
+	}
+
+	// 重新编号后，新实体继续使用连续的槽位
+	let e = world.spawn::<Node>().insert(Position(5000)).entity();
+	assert_eq!(e.local().offset(), first + 10);
+}